@@ -1,11 +1,77 @@
-import type { FeatureCollection } from "geojson";
-import type { ConvertOptions } from "./types.js";
-export type { ConvertOptions, GpxElementType } from "./types.js";
+import type { Feature, FeatureCollection } from "geojson";
+import type {
+  ConversionReport,
+  ConvertOptions,
+  DeviationOptions,
+  FeatureCollectionsByType,
+  GpxInfo,
+  GpxJson,
+  InitConfig,
+  LogLevel,
+  PreviewOptions,
+  ValidationReport,
+  Viewport,
+  ViewportOptions,
+} from "./types.js";
+export type {
+  ActivitySplitOptions,
+  ConversionReport,
+  ConversionWarning,
+  ConversionWarningCode,
+  ConvertOptions,
+  CoordinateTransform,
+  CropDistance,
+  DescriptionMode,
+  DeviationOptions,
+  DistanceFormula,
+  DroppedDataReport,
+  ElementCounts,
+  FeatureCollectionsByType,
+  FixQuality,
+  GpxElementType,
+  GpxError,
+  GpxErrorCode,
+  GpxInfo,
+  GpxJson,
+  GpxJsonExtension,
+  GpxJsonLink,
+  GpxJsonMetadata,
+  GpxJsonPoint,
+  GpxJsonRoute,
+  GpxJsonTrack,
+  InitConfig,
+  LimitOptions,
+  LogLevel,
+  PreviewOptions,
+  RouteMode,
+  SectionOptions,
+  ValidationReport,
+  Viewport,
+  ViewportOptions,
+} from "./types.js";
 export type { FeatureCollection } from "geojson";
 
 import initWasm, {
+  init as rawInit,
   gpxToGeoJson as rawGpxToGeoJson,
   gpxToGeoJsonString as rawGpxToGeoJsonString,
+  gpxToGeoJsonGzip as rawGpxToGeoJsonGzip,
+  gpxToGeoJsonWithReport as rawGpxToGeoJsonWithReport,
+  gpxToGeoJsonByType as rawGpxToGeoJsonByType,
+  gpxBytesToGeoJson as rawGpxBytesToGeoJson,
+  gpxBase64ToGeoJson as rawGpxBase64ToGeoJson,
+  gpxFileToGeoJson as rawGpxFileToGeoJson,
+  gpxZipToGeoJson as rawGpxZipToGeoJson,
+  GpxStreamParser,
+  gpxUrlToGeoJson as rawGpxUrlToGeoJson,
+  gpxStreamToGeoJson as rawGpxStreamToGeoJson,
+  gpxDeviation as rawGpxDeviation,
+  gpxPreview as rawGpxPreview,
+  gpxViewport as rawGpxViewport,
+  initLogging as rawInitLogging,
+  getGpxInfo as rawGetGpxInfo,
+  validateGpx as rawValidateGpx,
+  gpxToJson as rawGpxToJson,
 } from "../pkg/gpx2geojson_wasm.js";
 
 let initPromise: Promise<void> | null = null;
@@ -17,6 +83,11 @@ function ensureInit(): Promise<void> {
   return initPromise;
 }
 
+export async function init(config?: InitConfig): Promise<void> {
+  await ensureInit();
+  rawInit(config ?? undefined);
+}
+
 export async function gpxToGeoJson(
   gpxString: string,
   options?: ConvertOptions
@@ -32,3 +103,153 @@ export async function gpxToGeoJsonString(
   await ensureInit();
   return rawGpxToGeoJsonString(gpxString, options ?? undefined);
 }
+
+/** GeoJSON serialized and gzip-compressed, e.g. for upload with `Content-Encoding: gzip`. */
+export async function gpxToGeoJsonGzip(
+  gpxString: string,
+  options?: ConvertOptions
+): Promise<Uint8Array> {
+  await ensureInit();
+  return rawGpxToGeoJsonGzip(gpxString, options ?? undefined);
+}
+
+export async function gpxToGeoJsonWithReport(
+  gpxString: string,
+  options?: ConvertOptions
+): Promise<ConversionReport> {
+  await ensureInit();
+  return rawGpxToGeoJsonWithReport(gpxString, options ?? undefined) as ConversionReport;
+}
+
+export async function gpxToGeoJsonByType(
+  gpxString: string,
+  options?: ConvertOptions
+): Promise<FeatureCollectionsByType> {
+  await ensureInit();
+  return rawGpxToGeoJsonByType(gpxString, options ?? undefined) as FeatureCollectionsByType;
+}
+
+export async function gpxBytesToGeoJson(
+  bytes: Uint8Array,
+  options?: ConvertOptions
+): Promise<FeatureCollection> {
+  await ensureInit();
+  return rawGpxBytesToGeoJson(bytes, options ?? undefined) as FeatureCollection;
+}
+
+/**
+ * Convert a `File` or `Blob`, e.g. from `<input type="file">`, without
+ * reading it into a JS string first.
+ */
+export async function gpxFileToGeoJson(
+  file: Blob,
+  options?: ConvertOptions
+): Promise<FeatureCollection> {
+  await ensureInit();
+  return (await rawGpxFileToGeoJson(file, options ?? undefined)) as FeatureCollection;
+}
+
+/**
+ * Convert a base64 string or a `data:application/gpx+xml;base64,...` URL.
+ */
+export async function gpxBase64ToGeoJson(
+  data: string,
+  options?: ConvertOptions
+): Promise<FeatureCollection> {
+  await ensureInit();
+  return rawGpxBase64ToGeoJson(data, options ?? undefined) as FeatureCollection;
+}
+
+/**
+ * Convert every `.gpx` file in a ZIP archive into one FeatureCollection.
+ * Each feature has a `sourceFile` property with its path in the archive.
+ */
+export async function gpxZipToGeoJson(
+  bytes: Uint8Array,
+  options?: ConvertOptions
+): Promise<FeatureCollection> {
+  await ensureInit();
+  return rawGpxZipToGeoJson(bytes, options ?? undefined) as FeatureCollection;
+}
+
+export type { GpxStreamParser };
+
+/**
+ * Create a parser for a GPX file read in chunks. Call `push(chunk)` for each
+ * `Uint8Array` chunk, then `finish()` to get the FeatureCollection.
+ */
+export async function createGpxStreamParser(
+  options?: ConvertOptions
+): Promise<GpxStreamParser> {
+  await ensureInit();
+  return new GpxStreamParser(options ?? undefined);
+}
+
+/**
+ * Download a GPX file with `fetch()` and convert it, parsing the response
+ * body as it arrives.
+ */
+export async function gpxUrlToGeoJson(
+  url: string,
+  options?: ConvertOptions
+): Promise<FeatureCollection> {
+  await ensureInit();
+  return (await rawGpxUrlToGeoJson(url, options ?? undefined)) as FeatureCollection;
+}
+
+/**
+ * Convert a `ReadableStream` of UTF-8 bytes, e.g. a fetch response body,
+ * without buffering the whole file.
+ */
+export async function gpxStreamToGeoJson(
+  stream: ReadableStream<Uint8Array>,
+  options?: ConvertOptions
+): Promise<FeatureCollection> {
+  await ensureInit();
+  return (await rawGpxStreamToGeoJson(stream, options ?? undefined)) as FeatureCollection;
+}
+
+export async function gpxDeviation(
+  trackGpx: string,
+  routeGpx: string,
+  options?: DeviationOptions
+): Promise<FeatureCollection> {
+  await ensureInit();
+  return rawGpxDeviation(trackGpx, routeGpx, options ?? undefined) as FeatureCollection;
+}
+
+export async function gpxPreview(
+  gpxString: string,
+  options?: PreviewOptions
+): Promise<Feature> {
+  await ensureInit();
+  return rawGpxPreview(gpxString, options ?? undefined) as Feature;
+}
+
+export async function gpxViewport(
+  gpxString: string,
+  options?: ViewportOptions
+): Promise<Viewport | null> {
+  await ensureInit();
+  return (rawGpxViewport(gpxString, options ?? undefined) as Viewport | undefined) ?? null;
+}
+
+export async function getGpxInfo(gpxString: string): Promise<GpxInfo> {
+  await ensureInit();
+  return rawGetGpxInfo(gpxString) as GpxInfo;
+}
+
+export async function validateGpx(gpxString: string): Promise<ValidationReport> {
+  await ensureInit();
+  return rawValidateGpx(gpxString) as ValidationReport;
+}
+
+export async function gpxToJson(gpxString: string, options?: ConvertOptions): Promise<GpxJson> {
+  await ensureInit();
+  return rawGpxToJson(gpxString, options ?? undefined) as GpxJson;
+}
+
+export async function initLogging(level: LogLevel): Promise<void> {
+  await ensureInit();
+  rawInitLogging(level);
+}
@@ -2,12 +2,333 @@ import type { FeatureCollection } from "geojson";
 
 export type { FeatureCollection };
 
+export type LogLevel = "off" | "error" | "warn" | "info" | "debug" | "trace";
+
+export type FixQuality = "none" | "2d" | "3d" | "dgps" | "pps";
+
+export type RouteMode = "lineString" | "multiPoint";
+
+export type DescriptionMode = "separate" | "merged";
+
+export type DistanceFormula = "haversine" | "geodesic";
+
 export type GpxElementType = "waypoint" | "route" | "track";
 
+export interface CropDistance {
+  startMeters: number;
+  endMeters: number;
+}
+
+/** `matrix` [a, b, c, d, e, f] maps (lon, lat) to (a*lon + b*lat + c, d*lon + e*lat + f). */
+export interface CoordinateTransform {
+  dLat?: number;
+  dLon?: number;
+  dEle?: number;
+  matrix?: [number, number, number, number, number, number];
+}
+
+export interface ActivitySplitOptions {
+  minGapSeconds?: number;
+  minGapMeters?: number;
+}
+
+export interface SectionOptions {
+  from: string;
+  to: string;
+}
+
+export interface LimitOptions {
+  waypoints?: number;
+  routes?: number;
+  tracks?: number;
+}
+
 export interface ConvertOptions {
   includeElevation?: boolean;
   includeTime?: boolean;
   includeMetadata?: boolean;
   types?: GpxElementType[];
+  trackMode?: "feature-per-segment" | "feature-per-track" | "feature-per-file";
+  /** @deprecated Use `trackMode: "feature-per-track"`. */
   joinTrackSegments?: boolean;
+  /** @deprecated Use `trackMode: "feature-per-file"`. */
+  joinTracks?: boolean;
+  cropDistance?: CropDistance;
+  section?: SectionOptions;
+  resolveDtdEntities?: boolean;
+  minFixQuality?: FixQuality;
+  maxHdop?: number;
+  includeFixQuality?: boolean;
+  includeSummary?: boolean;
+  extraPointElements?: string[];
+  routeMode?: RouteMode;
+  splitActivities?: ActivitySplitOptions;
+  includeExtensions?: boolean;
+  autoColor?: boolean | string[];
+  minimal?: boolean;
+  includeSource?: boolean;
+  strictRfc7946?: boolean;
+  descriptionMode?: DescriptionMode;
+  includeGeometryHash?: boolean;
+  limit?: LimitOptions;
+  expandRouteShapePoints?: boolean;
+  stripHtml?: boolean;
+  extensionMappings?: Record<string, string>;
+  autoExtensions?: boolean;
+  includeDiagnostics?: boolean;
+  includeStats?: boolean;
+  gradientWindowMeters?: number;
+  waypointsNearTracksOnly?: number;
+  transform?: CoordinateTransform;
+  strict?: boolean;
+  distanceFormula?: DistanceFormula;
+  detectSelfIntersections?: boolean;
+  includeSourceIndex?: boolean;
+  validateOutput?: boolean | "error" | "warn";
+  bbox?: "bounds" | "checkedBounds";
+  lenientNumbers?: boolean;
+  trimText?: boolean;
+  elevationRange?: [number, number];
+  featureId?: "none" | "index" | "name" | "hash";
+  elevationMode?: "coordinate" | "property" | "both";
+  sensorNaming?: "default" | "togeojson";
+  output?: "features" | "points" | "geometries";
+  waypointsAsMultiPoint?: boolean;
+  trackGeometryCollection?: boolean;
+  compat?: "togeojson";
+  timeFormat?: "iso" | "epochMs" | "epochS";
+  timezone?: string;
+  includeProperties?: string[];
+  excludeProperties?: string[];
+  gpxTypeKey?: string | null;
+  splitAntimeridian?: boolean;
+}
+
+export interface DroppedDataReport {
+  skippedPoints: number;
+  filteredPoints: number;
+  ignoredElements: Record<string, number>;
+  droppedExtensions: Record<string, number>;
+  unknownEntities: Record<string, number>;
+}
+
+export interface DeviationOptions {
+  toleranceMeters?: number;
+}
+
+export interface PreviewOptions {
+  maxPoints?: number;
+}
+
+export interface ViewportOptions {
+  width?: number;
+  height?: number;
+  padding?: number;
+  includePixels?: boolean;
+}
+
+export interface Viewport {
+  bbox: [number, number, number, number];
+  center: [number, number];
+  zoom: number;
+  metersPerPixel: number;
+  width: number;
+  height: number;
+  pixels?: FeatureCollection;
+}
+
+export type GpxErrorCode =
+  | "xmlParse"
+  | "missingAttribute"
+  | "invalidAttribute"
+  | "floatParse"
+  | "limitExceeded"
+  | "invalidEncoding"
+  | "unsupportedEncoding"
+  | "validation"
+  | "invalidOutput"
+  | "invalidArchive"
+  | "fetch"
+  | "emptyReference";
+
+/** Error thrown by the conversion functions. */
+export interface GpxError extends Error {
+  code: GpxErrorCode;
+  /** Local name of the element being read, when known. */
+  element?: string;
+  /** 1-based position in the input, for errors found while parsing a string. */
+  line?: number;
+  column?: number;
+}
+
+export interface ConversionReport {
+  geojson: FeatureCollection;
+  warnings: ConversionWarning[];
+}
+
+export interface FeatureCollectionsByType {
+  waypoints: FeatureCollection;
+  routes: FeatureCollection;
+  tracks: FeatureCollection;
+}
+
+export type ConversionWarningCode =
+  | "skippedPoints"
+  | "filteredPoints"
+  | "ignoredElement"
+  | "droppedExtensions"
+  | "unknownEntity";
+
+export interface ConversionWarning {
+  code: ConversionWarningCode;
+  message: string;
+  context: Record<string, string | number>;
+}
+
+export interface GpxInfo {
+  version?: string;
+  creator?: string;
+  hasMetadata: boolean;
+  trackCount: number;
+  routeCount: number;
+  waypointCount: number;
+}
+
+export interface ValidationReport {
+  valid: boolean;
+  version?: string;
+  errors: string[];
+  warnings: string[];
+  counts: ElementCounts;
+}
+
+export interface ElementCounts {
+  waypoints: number;
+  routes: number;
+  routePoints: number;
+  tracks: number;
+  trackSegments: number;
+  trackPoints: number;
+}
+
+export interface GpxJson {
+  version?: string;
+  metadata?: GpxJsonMetadata;
+  waypoints: GpxJsonPoint[];
+  routes: GpxJsonRoute[];
+  tracks: GpxJsonTrack[];
+}
+
+export interface GpxJsonMetadata {
+  name?: string;
+  desc?: string;
+  time?: string;
+  keywords?: string;
+  bounds?: { minLat: number; minLon: number; maxLat: number; maxLon: number };
+}
+
+export interface GpxJsonLink {
+  href: string;
+  text?: string;
+  type?: string;
+}
+
+export interface GpxJsonExtension {
+  name: string;
+  prefix?: string;
+  attributes: [string, string][];
+  text: string;
+  children: GpxJsonExtension[];
+}
+
+export interface GpxJsonPoint {
+  lat: number;
+  lon: number;
+  ele?: number;
+  time?: string;
+  name?: string;
+  cmt?: string;
+  desc?: string;
+  src?: string;
+  sym?: string;
+  type?: string;
+  link?: GpxJsonLink;
+  fix?: string;
+  sat?: number;
+  hdop?: number;
+  vdop?: number;
+  pdop?: number;
+  magvar?: number;
+  geoidHeight?: number;
+  dgpsId?: number;
+  ageOfDgpsData?: number;
+  speed?: number;
+  course?: number;
+  heartRate?: number;
+  cadence?: number;
+  temperature?: number;
+  distance?: number;
+  power?: number;
+  shapePoints?: [number, number][];
+  garminWaypoint?: {
+    address?: {
+      street?: string;
+      city?: string;
+      state?: string;
+      country?: string;
+      postalCode?: string;
+    };
+    phone?: string;
+    categories?: string[];
+    proximity?: number;
+    depth?: number;
+  };
+  extra?: Record<string, string>;
+  extensionValues?: Record<string, number>;
+  extensions?: GpxJsonExtension[];
+  hadExtensions?: boolean;
+}
+
+export interface GpxJsonRoute {
+  name?: string;
+  cmt?: string;
+  desc?: string;
+  src?: string;
+  link?: GpxJsonLink;
+  number?: number;
+  type?: string;
+  points: GpxJsonPoint[];
+  displayColor?: string;
+  extensions?: GpxJsonExtension[];
+  hadExtensions?: boolean;
+  extra?: Record<string, string>;
+}
+
+export interface GpxJsonTrack {
+  name?: string;
+  cmt?: string;
+  desc?: string;
+  src?: string;
+  link?: GpxJsonLink;
+  number?: number;
+  type?: string;
+  segments: { points: GpxJsonPoint[]; extensions?: GpxJsonExtension[] }[];
+  displayColor?: string;
+  extensions?: GpxJsonExtension[];
+  hadExtensions?: boolean;
+  extra?: Record<string, string>;
+}
+
+export interface InitConfig {
+  panicHook?: boolean;
+  logLevel?: LogLevel;
+  defaultOptions?: ConvertOptions;
+  limits?: {
+    maxInputBytes?: number;
+    maxEntityDepth?: number;
+    maxExpandedTextBytes?: number;
+    maxTotalExpansionBytes?: number;
+    maxDepth?: number;
+    maxAttributes?: number;
+  };
 }
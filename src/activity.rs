@@ -0,0 +1,210 @@
+use crate::geo;
+use crate::gpx_types::*;
+use crate::options::{ActivitySplitOptions, DistanceFormula};
+
+/// One detected activity: the part of a track or route between two
+/// recording gaps.
+pub struct Activity<T> {
+    pub element: T,
+    /// Length in meters (gaps between segments are not counted).
+    pub distance: f64,
+    /// Seconds between the first and last timestamp, if there are any.
+    pub duration: Option<f64>,
+}
+
+/// Split a track into activities wherever two consecutive points are at least
+/// `min_gap_seconds` apart in time and `min_gap_meters` apart in space.
+///
+/// Points without a parseable timestamp never start a new activity. Segment
+/// boundaries inside an activity are kept.
+pub fn split_activities(
+    trk: &GpxTrack,
+    opts: &ActivitySplitOptions,
+    formula: DistanceFormula,
+) -> Vec<Activity<GpxTrack>> {
+    let empty = || GpxTrack {
+        segments: Vec::new(),
+        ..trk.clone()
+    };
+    let mut tracks = vec![empty()];
+    let mut prev: Option<&GpxPoint> = None;
+
+    for seg in &trk.segments {
+        for (i, run) in split_at_gaps(&seg.points, prev, opts)
+            .into_iter()
+            .enumerate()
+        {
+            if i > 0 {
+                tracks.push(empty());
+            }
+            if let Some(current) = tracks.last_mut()
+                && !run.is_empty()
+            {
+                current.segments.push(GpxSegment {
+                    points: run.to_vec(),
+                    extensions: seg.extensions.clone(),
+                });
+            }
+        }
+        prev = seg.points.last().or(prev);
+    }
+
+    tracks
+        .into_iter()
+        .filter(|t| !t.segments.is_empty())
+        .map(|track| {
+            let lines: Vec<&[GpxPoint]> =
+                track.segments.iter().map(|s| s.points.as_slice()).collect();
+            let (distance, duration) = measure(&lines, formula);
+            Activity {
+                element: track,
+                distance,
+                duration,
+            }
+        })
+        .collect()
+}
+
+/// Split a route into activities the same way, e.g. a planned multi-day
+/// route whose <rtept>s carry ETAs.
+pub fn split_route_activities(
+    rte: &GpxRoute,
+    opts: &ActivitySplitOptions,
+    formula: DistanceFormula,
+) -> Vec<Activity<GpxRoute>> {
+    split_at_gaps(&rte.points, None, opts)
+        .into_iter()
+        .filter(|run| !run.is_empty())
+        .map(|run| {
+            let (distance, duration) = measure(&[run], formula);
+            Activity {
+                element: GpxRoute {
+                    points: run.to_vec(),
+                    ..rte.clone()
+                },
+                distance,
+                duration,
+            }
+        })
+        .collect()
+}
+
+/// Cut `points` before every point that follows a gap. `prev` is the point
+/// before them, so a gap before the first point gives an empty first run.
+fn split_at_gaps<'a>(
+    points: &'a [GpxPoint],
+    mut prev: Option<&'a GpxPoint>,
+    opts: &ActivitySplitOptions,
+) -> Vec<&'a [GpxPoint]> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for (i, pt) in points.iter().enumerate() {
+        if prev.is_some_and(|prev| is_gap(prev, pt, opts)) {
+            runs.push(&points[start..i]);
+            start = i;
+        }
+        prev = Some(pt);
+    }
+    runs.push(&points[start..]);
+    runs
+}
+
+/// Length and duration of an activity made of `lines`.
+fn measure(lines: &[&[GpxPoint]], formula: DistanceFormula) -> (f64, Option<f64>) {
+    let distance = lines
+        .iter()
+        .map(|line| geo::path_length(line, formula))
+        .sum();
+    (distance, duration(lines.iter().copied().flatten()))
+}
+
+/// Seconds between the first and last timestamp of `points`, if there are any.
+pub fn duration<'a>(points: impl IntoIterator<Item = &'a GpxPoint>) -> Option<f64> {
+    let mut times = points.into_iter().filter_map(GpxPoint::timestamp);
+    let first = times.next()?;
+    Some(times.last().unwrap_or(first) - first)
+}
+
+fn is_gap(a: &GpxPoint, b: &GpxPoint, opts: &ActivitySplitOptions) -> bool {
+    match (a.timestamp(), b.timestamp()) {
+        (Some(ta), Some(tb)) => {
+            tb - ta >= opts.min_gap_seconds && geo::haversine_distance(a, b) >= opts.min_gap_meters
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(lon: f64, time: &str) -> GpxPoint {
+        let mut pt = GpxPoint::new(0.0, lon);
+        pt.time = Some(time.to_string());
+        pt
+    }
+
+    #[test]
+    fn test_split_on_time_gap() {
+        let trk = GpxTrack {
+            segments: vec![GpxSegment {
+                extensions: None,
+                points: vec![
+                    point(0.0, "2025-01-01T08:00:00Z"),
+                    point(0.01, "2025-01-01T08:20:00Z"),
+                    // lunch break
+                    point(0.01, "2025-01-01T12:00:00Z"),
+                    point(0.02, "2025-01-01T12:25:00Z"),
+                ],
+            }],
+            ..Default::default()
+        };
+        let activities = split_activities(
+            &trk,
+            &ActivitySplitOptions::default(),
+            DistanceFormula::Haversine,
+        );
+
+        assert_eq!(activities.len(), 2);
+        assert_eq!(activities[0].duration, Some(1200.0));
+        assert_eq!(activities[1].duration, Some(1500.0));
+        assert!((activities[0].distance - 1111.95).abs() < 0.1);
+
+        // Requiring a distance jump as well keeps them together
+        let opts = ActivitySplitOptions {
+            min_gap_meters: 100.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            split_activities(&trk, &opts, DistanceFormula::Haversine).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_split_route() {
+        let rte = GpxRoute {
+            name: Some("Two days".to_string()),
+            points: vec![
+                point(0.0, "2025-01-01T08:00:00Z"),
+                point(0.01, "2025-01-01T08:20:00Z"),
+                // overnight
+                point(0.01, "2025-01-02T08:00:00Z"),
+                point(0.02, "2025-01-02T08:25:00Z"),
+            ],
+            ..Default::default()
+        };
+        let activities = split_route_activities(
+            &rte,
+            &ActivitySplitOptions::default(),
+            DistanceFormula::Haversine,
+        );
+
+        assert_eq!(activities.len(), 2);
+        assert_eq!(activities[0].element.points.len(), 2);
+        assert_eq!(activities[1].element.name.as_deref(), Some("Two days"));
+        assert_eq!(activities[0].duration, Some(1200.0));
+        assert_eq!(activities[1].duration, Some(1500.0));
+        assert!((activities[1].distance - 1111.95).abs() < 0.1);
+    }
+}
@@ -0,0 +1,193 @@
+use std::io::{Cursor, Read};
+
+use geojson::{FeatureCollection, feature::Id};
+use serde_json::Map;
+use zip::ZipArchive;
+
+use crate::config;
+use crate::converter;
+use crate::encoding;
+use crate::error::Gpx2GeoJsonError;
+use crate::options::{ConvertOptions, FeatureIdMode};
+use crate::parser;
+use crate::summary;
+
+type Result<T> = std::result::Result<T, Gpx2GeoJsonError>;
+
+/// Convert every `.gpx` file in a ZIP archive (e.g. a Garmin or Strava bulk
+/// export) and merge the features into one FeatureCollection, in archive
+/// order, each tagged with the `sourceFile` path it came from.
+///
+/// A file that fails to parse is skipped with a warning, so one broken
+/// activity doesn't lose the rest of the export. Per-file foreign members
+/// are not carried over; `includeSummary` summarizes all the features.
+/// `maxInputBytes` applies to the archive and to the total uncompressed size
+/// of the GPX files, counted as they are decompressed rather than trusting the
+/// sizes the archive declares. With `bbox`, the result covers the per-file bboxes if
+/// every converted file has one. `featureId: "index"` numbers the merged
+/// features.
+pub fn zip_to_feature_collection(bytes: &[u8], opts: &ConvertOptions) -> Result<FeatureCollection> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(invalid_archive)?;
+    let mut features = Vec::new();
+    let mut uncompressed = 0usize;
+    let mut bbox: Option<Vec<f64>> = None;
+    let mut every_file_has_bbox = true;
+    let max_input_bytes = config::limits().max_input_bytes;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(invalid_archive)?;
+        let name = entry.name().to_string();
+        if !entry.is_file() || !is_gpx_file(&name) {
+            continue;
+        }
+        // One byte past the remaining budget is enough to tell it's exceeded
+        let budget =
+            max_input_bytes.map_or(u64::MAX, |max| max.saturating_sub(uncompressed) as u64 + 1);
+        let mut content = Vec::new();
+        (&mut entry)
+            .take(budget)
+            .read_to_end(&mut content)
+            .map_err(|e| invalid_archive(e.into()))?;
+        uncompressed += content.len();
+        config::check_input_size(uncompressed)?;
+
+        let fc = match convert_file(&content, opts) {
+            Ok(fc) => fc,
+            Err(err) => {
+                log::warn!("Skipping {name}: {err}");
+                continue;
+            }
+        };
+        match (&mut bbox, fc.bbox) {
+            (_, None) => every_file_has_bbox = false,
+            (None, Some(file_bbox)) => bbox = Some(file_bbox),
+            (Some(b), Some(file_bbox)) => {
+                b[0] = b[0].min(file_bbox[0]);
+                b[1] = b[1].min(file_bbox[1]);
+                b[2] = b[2].max(file_bbox[2]);
+                b[3] = b[3].max(file_bbox[3]);
+            }
+        }
+        for mut feature in fc.features {
+            if !opts.minimal {
+                let props = feature.properties.get_or_insert_with(Map::new);
+                props.insert("sourceFile".to_string(), name.as_str().into());
+            }
+            if opts.feature_id == FeatureIdMode::Index {
+                feature.id = Some(Id::Number(features.len().into()));
+            }
+            features.push(feature);
+        }
+    }
+
+    let mut foreign_members = Map::new();
+    if opts.include_summary && !opts.minimal && !opts.strict_rfc7946 {
+        foreign_members.insert(
+            "summary".to_string(),
+            summary::build_summary(&features, opts.distance_formula),
+        );
+    }
+    Ok(FeatureCollection {
+        bbox: bbox.filter(|_| every_file_has_bbox),
+        features,
+        foreign_members: (!foreign_members.is_empty()).then_some(foreign_members),
+    })
+}
+
+fn convert_file(bytes: &[u8], opts: &ConvertOptions) -> Result<FeatureCollection> {
+    let xml = encoding::decode(bytes)?;
+    let data = parser::parse_gpx_with_options(&xml, opts)?;
+    Ok(converter::to_feature_collection(&data, opts))
+}
+
+/// A `.gpx` entry, other than the `__MACOSX/` resource forks that macOS
+/// adds to archives it creates.
+fn is_gpx_file(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    !path.starts_with("__MACOSX/")
+        && !file_name.starts_with("._")
+        && file_name.to_ascii_lowercase().ends_with(".gpx")
+}
+
+fn invalid_archive(e: zip::result::ZipError) -> Gpx2GeoJsonError {
+    Gpx2GeoJsonError::InvalidArchive {
+        message: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    fn zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_zip_to_feature_collection() {
+        let bytes = zip(&[
+            (
+                "activities/morning.gpx",
+                r#"<gpx><wpt lat="35.0" lon="139.0"/></gpx>"#,
+            ),
+            ("activities/notes.txt", "not a GPX file"),
+            ("activities/broken.gpx", "<gpx><trk></gpx>"),
+            ("__MACOSX/activities/._morning.gpx", "resource fork"),
+            (
+                "EVENING.GPX",
+                r#"<gpx><rte><rtept lat="35.0" lon="139.0"/><rtept lat="35.1" lon="139.1"/></rte></gpx>"#,
+            ),
+        ]);
+        let opts: ConvertOptions = serde_json::from_str(r#"{"includeSummary": true}"#).unwrap();
+        let fc = zip_to_feature_collection(&bytes, &opts).unwrap();
+        let sources: Vec<_> = fc
+            .features
+            .iter()
+            .map(|f| f.properties.as_ref().unwrap()["sourceFile"].clone())
+            .collect();
+        assert_eq!(sources, vec!["activities/morning.gpx", "EVENING.GPX"]);
+        assert!(fc.foreign_members.unwrap().contains_key("summary"));
+
+        let err = zip_to_feature_collection(b"not a zip", &opts).unwrap_err();
+        assert!(matches!(err, Gpx2GeoJsonError::InvalidArchive { .. }));
+    }
+
+    #[test]
+    fn test_zip_forged_entry_size() {
+        let wpts = r#"<wpt lat="35.0" lon="139.0"/>"#.repeat(1000);
+        let mut bytes = zip(&[("big.gpx", &format!("<gpx>{wpts}</gpx>"))]);
+        // Declare the entry as 100 bytes in the local and central headers
+        let forge = |bytes: &mut Vec<u8>, signature: &[u8], offset: usize| {
+            let start = bytes.windows(4).position(|w| w == signature).unwrap();
+            bytes[start + offset..start + offset + 4].copy_from_slice(&100u32.to_le_bytes());
+        };
+        forge(&mut bytes, b"PK\x03\x04", 22);
+        forge(&mut bytes, b"PK\x01\x02", 24);
+
+        config::init(
+            serde_json::from_value(serde_json::json!({
+                "panicHook": false,
+                "limits": { "maxInputBytes": 1000 }
+            }))
+            .unwrap(),
+        );
+        let result = zip_to_feature_collection(&bytes, &ConvertOptions::default());
+        config::init(config::InitConfig::default());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Limit exceeded: maxInputBytes (max 1000)"
+        );
+    }
+}
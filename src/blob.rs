@@ -0,0 +1,24 @@
+use js_sys::{Promise, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::config;
+
+#[wasm_bindgen]
+extern "C" {
+    /// A `Blob`, or a `File` from `<input type="file">` or drag and drop.
+    #[wasm_bindgen(typescript_type = "Blob")]
+    pub type Blob;
+    #[wasm_bindgen(method, getter)]
+    fn size(this: &Blob) -> f64;
+    #[wasm_bindgen(method, js_name = arrayBuffer)]
+    fn array_buffer(this: &Blob) -> Promise;
+}
+
+/// The contents of `blob`, copied straight into WASM memory. `maxInputBytes`
+/// is checked against the blob's size before anything is read.
+pub async fn read_blob(blob: &Blob) -> Result<Vec<u8>, JsValue> {
+    config::check_input_size(blob.size() as usize)?;
+    let buffer = JsFuture::from(blob.array_buffer()).await?;
+    Ok(Uint8Array::new(&buffer).to_vec())
+}
@@ -0,0 +1,37 @@
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::Serialize;
+
+/// Serialize `value` as JSON straight into a gzip stream, so the
+/// uncompressed JSON is never held in memory as a whole.
+pub fn to_gzip_json<T: Serialize>(value: &T) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, value)?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+    use crate::converter::to_feature_collection;
+    use crate::options::ConvertOptions;
+    use crate::parser::parse_gpx;
+
+    #[test]
+    fn test_to_gzip_json() {
+        let xml = r#"<gpx><trk><trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/></trkseg></trk></gpx>"#;
+        let fc = to_feature_collection(&parse_gpx(xml).unwrap(), &ConvertOptions::default());
+        let bytes = to_gzip_json(&fc).unwrap();
+        assert_eq!(&bytes[..2], &[0x1f, 0x8b]);
+
+        let mut json = String::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut json)
+            .unwrap();
+        assert_eq!(json, serde_json::to_string(&fc).unwrap());
+    }
+}
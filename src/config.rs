@@ -0,0 +1,197 @@
+use std::cell::RefCell;
+
+use serde::Deserialize;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::error::Gpx2GeoJsonError;
+
+/// Startup configuration passed to `init()`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitConfig {
+    /// Install console_error_panic_hook (default: true)
+    #[serde(default = "default_true")]
+    pub panic_hook: bool,
+
+    /// Console log level, as accepted by initLogging (default: logging off)
+    #[serde(default)]
+    pub log_level: Option<String>,
+
+    /// ConvertOptions used for any field a call leaves unset (default: none)
+    #[serde(default)]
+    pub default_options: Map<String, JsonValue>,
+
+    /// Resource limits applied to every call
+    #[serde(default)]
+    pub limits: Limits,
+}
+
+impl Default for InitConfig {
+    fn default() -> Self {
+        Self {
+            panic_hook: true,
+            log_level: None,
+            default_options: Map::new(),
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// Resource limits protecting the module against oversized input.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Limits {
+    /// Maximum GPX input size in bytes (default: unlimited)
+    #[serde(default)]
+    pub max_input_bytes: Option<usize>,
+
+    /// Maximum nesting of entity references inside DOCTYPE entity values (default: 8)
+    #[serde(default = "default_max_entity_depth")]
+    pub max_entity_depth: usize,
+
+    /// Maximum length in bytes of a single text value after entity expansion (default: 1 MiB)
+    #[serde(default = "default_max_expanded_text_bytes")]
+    pub max_expanded_text_bytes: usize,
+
+    /// Maximum bytes produced by entity expansion in a whole document (default: 16 MiB)
+    #[serde(default = "default_max_total_expansion_bytes")]
+    pub max_total_expansion_bytes: usize,
+
+    /// Maximum element nesting depth, counting the <gpx> root as 1 (default: 256)
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+
+    /// Maximum number of attributes on one element (default: 256)
+    #[serde(default = "default_max_attributes")]
+    pub max_attributes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_input_bytes: None,
+            max_entity_depth: default_max_entity_depth(),
+            max_expanded_text_bytes: default_max_expanded_text_bytes(),
+            max_total_expansion_bytes: default_max_total_expansion_bytes(),
+            max_depth: default_max_depth(),
+            max_attributes: default_max_attributes(),
+        }
+    }
+}
+
+thread_local! {
+    static CONFIG: RefCell<Option<InitConfig>> = const { RefCell::new(None) };
+}
+
+/// Apply a configuration, replacing any previous one.
+///
+/// A panic hook, once installed, stays installed even if a later call
+/// passes `panic_hook: false`.
+pub fn init(config: InitConfig) {
+    if config.panic_hook {
+        console_error_panic_hook::set_once();
+    }
+    CONFIG.with(|c| *c.borrow_mut() = Some(config));
+}
+
+/// Apply the default configuration unless `init` has already been called.
+pub fn ensure_init() {
+    if CONFIG.with(|c| c.borrow().is_none()) {
+        init(InitConfig::default());
+    }
+}
+
+/// Merge per-call options over the configured default options.
+/// Non-object values are returned unchanged so deserialization reports them.
+pub fn with_default_options(overrides: JsonValue) -> JsonValue {
+    let JsonValue::Object(overrides) = overrides else {
+        return overrides;
+    };
+    let mut merged = CONFIG.with(|c| {
+        c.borrow()
+            .as_ref()
+            .map(|config| config.default_options.clone())
+            .unwrap_or_default()
+    });
+    merged.extend(overrides);
+    JsonValue::Object(merged)
+}
+
+/// Fail with `LimitExceeded` if the input is larger than `maxInputBytes`.
+pub fn check_input_size(len: usize) -> Result<(), Gpx2GeoJsonError> {
+    let max = CONFIG.with(|c| c.borrow().as_ref().and_then(|c| c.limits.max_input_bytes));
+    match max {
+        Some(max) if len > max => Err(Gpx2GeoJsonError::LimitExceeded {
+            limit: "maxInputBytes",
+            max,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// The configured limits, or the defaults if `init` hasn't been called.
+pub fn limits() -> Limits {
+    CONFIG.with(|c| {
+        c.borrow()
+            .as_ref()
+            .map(|config| config.limits.clone())
+            .unwrap_or_default()
+    })
+}
+
+fn default_max_entity_depth() -> usize {
+    8
+}
+
+fn default_max_expanded_text_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_max_total_expansion_bytes() -> usize {
+    16 * 1024 * 1024
+}
+
+fn default_max_depth() -> usize {
+    256
+}
+
+fn default_max_attributes() -> usize {
+    256
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn init_with(config: JsonValue) {
+        init(serde_json::from_value(config).unwrap());
+    }
+
+    #[test]
+    fn test_default_options_merged_under_overrides() {
+        init_with(json!({
+            "panicHook": false,
+            "defaultOptions": { "includeTime": false, "includeElevation": false }
+        }));
+        let merged = with_default_options(json!({ "includeElevation": true }));
+        assert_eq!(
+            merged,
+            json!({ "includeTime": false, "includeElevation": true })
+        );
+    }
+
+    #[test]
+    fn test_max_input_bytes() {
+        init_with(json!({ "panicHook": false, "limits": { "maxInputBytes": 10 } }));
+        assert!(check_input_size(10).is_ok());
+        assert!(matches!(
+            check_input_size(11),
+            Err(Gpx2GeoJsonError::LimitExceeded { max: 10, .. })
+        ));
+    }
+}
@@ -1,44 +1,608 @@
-use geojson::{Feature, FeatureCollection, Geometry, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+use geojson::{Feature, FeatureCollection, GeoJson, Geometry, Value, feature::Id};
+use serde::Serialize;
 use serde_json::{Map, Value as JsonValue};
 
+use crate::activity;
+use crate::datetime;
+use crate::filter;
+use crate::geo;
 use crate::gpx_types::*;
-use crate::options::{ConvertOptions, GpxElementType};
+use crate::html;
+use crate::intersections;
+use crate::options::{
+    AutoColor, BboxSource, Compat, ConvertOptions, DescriptionMode, FeatureIdMode, GpxElementType,
+    OutputMode, RouteMode, SectionOptions, SensorNaming, TimeFormat, TrackMode,
+};
+use crate::rfc7946;
+use crate::stats;
+use crate::style;
+use crate::summary;
+
+/// Douglas-Peucker tolerance applied to lines in `minimal` mode.
+const MINIMAL_TOLERANCE_METERS: f64 = 5.0;
+
+/// Coordinate decimals kept in `minimal` mode (~1 m at the equator).
+const MINIMAL_DECIMALS: i32 = 5;
+
+/// Coordinate scale applied before hashing, so float noise below ~1 cm is ignored.
+const HASH_SCALE: f64 = 1e7;
+
+/// Distance within which a waypoint belongs to a track in
+/// `trackGeometryCollection`, unless `waypointsNearTracksOnly` sets one.
+const LINKED_WAYPOINT_METERS: f64 = 100.0;
 
 /// Convert parsed GPX data to a GeoJSON FeatureCollection.
 pub fn to_feature_collection(data: &GpxData, opts: &ConvertOptions) -> FeatureCollection {
+    let transformed;
+    let data = match &opts.transform {
+        Some(transform) => {
+            transformed = filter::transform_coordinates(data, transform);
+            &transformed
+        }
+        None => data,
+    };
+    let waypoint_indices = opts
+        .include_source_index
+        .then(|| filter::kept_waypoint_indices(data, opts));
+    let filtered;
+    let data = if filter::is_enabled(opts) {
+        filtered = filter::filter_points(data, opts);
+        &filtered
+    } else {
+        data
+    };
+    let compat_opts;
+    let opts = match opts.compat {
+        Some(Compat::Togeojson) => {
+            compat_opts = ConvertOptions {
+                track_mode: opts.track_mode.or(Some(TrackMode::FeaturePerTrack)),
+                sensor_naming: SensorNaming::Togeojson,
+                ..opts.clone()
+            };
+            &compat_opts
+        }
+        None => opts,
+    };
+    let (simplified, minimal_opts);
+    let (data, opts) = if opts.minimal {
+        simplified = filter::simplify_lines(data, MINIMAL_TOLERANCE_METERS);
+        minimal_opts = ConvertOptions {
+            include_elevation: false,
+            ..opts.clone()
+        };
+        (&simplified, &minimal_opts)
+    } else {
+        (data, opts)
+    };
     let mut features = Vec::new();
 
     if opts.should_include(GpxElementType::Waypoint) {
-        for wpt in &data.waypoints {
-            features.push(waypoint_to_feature(wpt, opts));
+        let waypoints = match opts.waypoints_near_tracks_only {
+            Some(max_m) => filter::waypoints_near_lines(data, max_m),
+            None => data.waypoints.iter().enumerate().collect(),
+        };
+        if opts.waypoints_as_multi_point {
+            let points: Vec<GpxPoint> = waypoints.into_iter().map(|(_, wpt)| wpt.clone()).collect();
+            if !points.is_empty() {
+                features.push(waypoints_to_multi_point(&points, opts));
+            }
+        } else {
+            for (index, wpt) in waypoints {
+                let mut feature = waypoint_to_feature(wpt, opts);
+                if let Some(indices) = &waypoint_indices {
+                    insert_source_index(
+                        std::slice::from_mut(&mut feature),
+                        "waypointIndex",
+                        indices[index],
+                    );
+                }
+                features.push(feature);
+            }
         }
     }
 
+    // Activities are numbered across routes and tracks
+    let mut activity_number = 0;
     if opts.should_include(GpxElementType::Route) {
-        for rte in &data.routes {
-            if rte.points.len() >= 2 {
-                features.push(route_to_feature(rte, opts));
-            } else if rte.points.len() == 1 {
-                features.push(single_point_feature(&rte.points[0], "route", opts));
+        for (index, rte) in data.routes.iter().enumerate() {
+            let start = features.len();
+            let expanded;
+            let rte = if opts.expand_route_shape_points {
+                expanded = expand_shape_points(rte);
+                &expanded
+            } else {
+                rte
+            };
+            if opts.output == OutputMode::Points {
+                features.extend(
+                    rte.points
+                        .iter()
+                        .map(|pt| line_point_feature(pt, "route", &rte.name, opts)),
+                );
+            } else if let Some(split) = &opts.split_activities {
+                for act in activity::split_route_activities(rte, split, opts.distance_formula) {
+                    activity_number += 1;
+                    let start = features.len();
+                    features.extend(route_or_point_feature(&act.element, opts));
+                    insert_activity(&mut features[start..], &act, activity_number);
+                }
+            } else {
+                features.extend(route_or_point_feature(rte, opts));
+            }
+            if opts.include_source_index {
+                insert_source_index(&mut features[start..], "routeIndex", index);
             }
         }
     }
 
     if opts.should_include(GpxElementType::Track) {
-        for trk in &data.tracks {
-            features.extend(track_to_features(trk, opts));
+        let tracks_start = features.len();
+        let mut joined = Vec::new();
+        for (index, trk) in data.tracks.iter().enumerate() {
+            let start = features.len();
+            let cropped;
+            let trk = match &opts.crop_distance {
+                Some(crop) => {
+                    cropped = geo::crop_track_by_distance(
+                        trk,
+                        crop.start_meters,
+                        crop.end_meters,
+                        opts.distance_formula,
+                    );
+                    &cropped
+                }
+                None => trk,
+            };
+            match &opts.split_activities {
+                _ if opts.output == OutputMode::Points => {
+                    let points = trk.segments.iter().flat_map(|seg| &seg.points);
+                    features
+                        .extend(points.map(|pt| line_point_feature(pt, "track", &trk.name, opts)));
+                }
+                _ if opts.track_mode() == TrackMode::FeaturePerFile => joined.push(trk.clone()),
+                Some(split) => {
+                    for act in activity::split_activities(trk, split, opts.distance_formula) {
+                        activity_number += 1;
+                        features.extend(activity_features(&act, activity_number, opts));
+                    }
+                }
+                None => features.extend(track_to_features(trk, opts)),
+            }
+            if opts.track_geometry_collection && features.len() > start {
+                let waypoints = linked_waypoints(data, std::slice::from_ref(trk), opts);
+                for feature in &mut features[start..] {
+                    into_geometry_collection(feature, &waypoints, opts);
+                }
+            }
+            if opts.include_source_index {
+                insert_source_index(&mut features[start..], "trackIndex", index);
+            }
+        }
+        if !joined.is_empty() {
+            let join_opts = ConvertOptions {
+                track_mode: Some(TrackMode::FeaturePerTrack),
+                ..opts.clone()
+            };
+            let start = features.len();
+            features.extend(track_to_features(&join_tracks(&joined), &join_opts));
+            if opts.track_geometry_collection {
+                let waypoints = linked_waypoints(data, &joined, opts);
+                for feature in &mut features[start..] {
+                    into_geometry_collection(feature, &waypoints, opts);
+                }
+            }
+        }
+
+        if opts.detect_self_intersections {
+            let crossings =
+                intersections::self_intersection_features(&mut features[tracks_start..]);
+            features.extend(crossings);
         }
+        if let Some(section) = &opts.section {
+            features.extend(section_features(data, section, opts));
+        }
+    }
+
+    match &opts.auto_color {
+        Some(AutoColor::Enabled(true)) => {
+            style::apply_auto_color(&mut features, style::DEFAULT_PALETTE)
+        }
+        Some(AutoColor::Palette(palette)) => {
+            let palette: Vec<&str> = palette.iter().map(String::as_str).collect();
+            style::apply_auto_color(&mut features, &palette);
+        }
+        _ => {}
+    }
+
+    if opts.include_source
+        && let Some(version) = &data.version
+    {
+        for feature in &mut features {
+            let props = feature.properties.get_or_insert_with(Map::new);
+            props.insert("sourceVersion".to_string(), version.as_str().into());
+        }
+    }
+
+    log::debug!("Converted to {} features", features.len());
+    if opts.strict_rfc7946 {
+        rfc7946::make_strict(&mut features);
+    } else if opts.split_antimeridian {
+        features.iter_mut().for_each(rfc7946::cut_antimeridian);
+    }
+    if opts.include_geometry_hash && !opts.minimal {
+        for feature in &mut features {
+            if let Some(geometry) = &feature.geometry {
+                let hash = geometry_hash(&geometry.value);
+                let props = feature.properties.get_or_insert_with(Map::new);
+                props.insert("geometryHash".to_string(), JsonValue::String(hash));
+            }
+        }
+    }
+    assign_feature_ids(&mut features, opts.feature_id);
+    let bbox = opts
+        .bbox
+        .and_then(|source| collection_bbox(data, &features, source));
+    if opts.minimal {
+        for feature in &mut features {
+            minimize_feature(feature);
+        }
+    }
+    if opts.compat == Some(Compat::Togeojson) {
+        features.iter_mut().for_each(togeojson_properties);
     }
+    if opts.minimal || opts.strict_rfc7946 || opts.compat.is_some() {
+        finish_properties(&mut features, opts);
+        return FeatureCollection {
+            bbox,
+            features,
+            foreign_members: None,
+        };
+    }
+
+    let mut foreign_members = Map::new();
+    if opts.include_metadata
+        && let Some(metadata) = &data.metadata
+    {
+        foreign_members.insert("gpx_metadata".to_string(), metadata_to_json(metadata));
+    }
+    if opts.include_summary {
+        foreign_members.insert(
+            "summary".to_string(),
+            summary::build_summary(&features, opts.distance_formula),
+        );
+    }
+    if opts.include_diagnostics {
+        foreign_members.insert(
+            "diagnostics".to_string(),
+            serde_json::json!({ "droppedDataReport": dropped_data_report(&data.dropped) }),
+        );
+    }
+    format_foreign_member_times(&mut foreign_members, opts);
+    finish_properties(&mut features, opts);
 
     FeatureCollection {
-        bbox: None,
+        bbox,
         features,
-        foreign_members: None,
+        foreign_members: (!foreign_members.is_empty()).then_some(foreign_members),
+    }
+}
+
+/// The value returned for `output`: `fc` itself, or for "geometries" a
+/// GeometryCollection of its geometries.
+pub fn into_output(fc: FeatureCollection, opts: &ConvertOptions) -> GeoJson {
+    if opts.output != OutputMode::Geometries {
+        return GeoJson::FeatureCollection(fc);
+    }
+    let geometries = fc.features.into_iter().filter_map(|f| f.geometry).collect();
+    GeoJson::Geometry(Geometry::new(Value::GeometryCollection(geometries)))
+}
+
+/// Result of `gpxToGeoJsonByType`: one FeatureCollection per element type.
+#[derive(Debug, Serialize)]
+pub struct FeatureCollectionsByType {
+    pub waypoints: FeatureCollection,
+    pub routes: FeatureCollection,
+    pub tracks: FeatureCollection,
+}
+
+/// Convert waypoints, routes and tracks into separate FeatureCollections.
+/// A type left out by `types` gets an empty collection.
+pub fn to_feature_collections_by_type(
+    data: &GpxData,
+    opts: &ConvertOptions,
+) -> FeatureCollectionsByType {
+    let only = |element_type: GpxElementType| {
+        let types = if opts.should_include(element_type) {
+            vec![element_type]
+        } else {
+            Vec::new()
+        };
+        let opts = ConvertOptions {
+            types: Some(types),
+            ..opts.clone()
+        };
+        to_feature_collection(data, &opts)
+    };
+    FeatureCollectionsByType {
+        waypoints: only(GpxElementType::Waypoint),
+        routes: only(GpxElementType::Route),
+        tracks: only(GpxElementType::Track),
+    }
+}
+
+/// For `featureId`: set Feature.id on every feature in `features`.
+fn assign_feature_ids(features: &mut [Feature], mode: FeatureIdMode) {
+    for (index, feature) in features.iter_mut().enumerate() {
+        feature.id = match mode {
+            FeatureIdMode::None => continue,
+            FeatureIdMode::Index => Some(Id::Number(index.into())),
+            FeatureIdMode::Name => feature
+                .properties
+                .as_ref()
+                .and_then(|props| props.get("name"))
+                .and_then(JsonValue::as_str)
+                .map(|name| Id::String(name.to_string())),
+            FeatureIdMode::Hash => feature
+                .geometry
+                .as_ref()
+                .map(|geometry| Id::String(geometry_hash(&geometry.value))),
+        };
+    }
+}
+
+/// For `bbox`: `[minLon, minLat, maxLon, maxLat]` from the <bounds> in <metadata>.
+///
+/// With `CheckedBounds`, bounds that leave out an output coordinate (beyond
+/// rounding in the file) are replaced by the extent of `features`, which is
+/// also used when the file declares no bounds.
+fn collection_bbox(data: &GpxData, features: &[Feature], source: BboxSource) -> Option<Vec<f64>> {
+    const TOLERANCE: f64 = 1e-6;
+    let bounds = data
+        .metadata
+        .as_ref()
+        .and_then(|m| m.bounds.as_ref())
+        .map(|b| [b.min_lon, b.min_lat, b.max_lon, b.max_lat]);
+    let bbox = match (source, bounds) {
+        (BboxSource::Bounds, bounds) => bounds,
+        (BboxSource::CheckedBounds, None) => summary::features_bbox(features),
+        (BboxSource::CheckedBounds, Some(bounds)) => match summary::features_bbox(features) {
+            Some(extent)
+                if extent[0] < bounds[0] - TOLERANCE
+                    || extent[1] < bounds[1] - TOLERANCE
+                    || extent[2] > bounds[2] + TOLERANCE
+                    || extent[3] > bounds[3] + TOLERANCE =>
+            {
+                log::warn!("<bounds> {bounds:?} do not contain the output extent {extent:?}");
+                Some(extent)
+            }
+            _ => Some(bounds),
+        },
+    };
+    bbox.map(Vec::from)
+}
+
+/// Property options applied once everything that reads the properties
+/// (the summary reads `gpxType` and the timestamp text) is done:
+/// `timeFormat`, `gpxTypeKey` and the property filters, which see the
+/// renamed key.
+fn finish_properties(features: &mut [Feature], opts: &ConvertOptions) {
+    format_times(features, opts);
+    rename_gpx_type(features, opts);
+    filter_properties(features, opts);
+}
+
+/// For `timeFormat` and `timezone`: rewrite the timestamps among the
+/// foreign members, `gpx_metadata.time` and `summary.timeRange`.
+fn format_foreign_member_times(
+    foreign_members: &mut Map<String, JsonValue>,
+    opts: &ConvertOptions,
+) {
+    if !formats_times(opts) {
+        return;
+    }
+    let format = |text: &str| format_time(text, opts);
+    let members = [("gpx_metadata", "time"), ("summary", "timeRange")];
+    for (member, key) in members {
+        if let Some(value) = foreign_members.get_mut(member).and_then(|m| m.get_mut(key)) {
+            map_strings(value, &format);
+        }
+    }
+}
+
+/// For `gpxTypeKey`: move `gpxType` to another key, or drop it.
+fn rename_gpx_type(features: &mut [Feature], opts: &ConvertOptions) {
+    if opts.gpx_type_key.as_deref() == Some("gpxType") {
+        return;
+    }
+    for props in features.iter_mut().filter_map(|f| f.properties.as_mut()) {
+        if let Some(gpx_type) = props.remove("gpxType")
+            && let Some(key) = &opts.gpx_type_key
+        {
+            props.insert(key.clone(), gpx_type);
+        }
+    }
+}
+
+/// Whether `timeFormat` or `timezone` rewrites timestamps at all.
+fn formats_times(opts: &ConvertOptions) -> bool {
+    opts.time_format != TimeFormat::Iso || opts.timezone.is_some()
+}
+
+/// A timestamp as `timeFormat` and `timezone` ask for. Unparseable
+/// timestamps become null as numbers and are kept as they are in ISO format.
+fn format_time(text: &str, opts: &ConvertOptions) -> JsonValue {
+    let Some(secs) = datetime::parse_timestamp(text) else {
+        return match opts.time_format {
+            TimeFormat::Iso => JsonValue::String(text.to_string()),
+            _ => JsonValue::Null,
+        };
+    };
+    match (opts.time_format, opts.timezone) {
+        (TimeFormat::Iso, Some(zone)) => {
+            JsonValue::String(datetime::format_timestamp(secs, zone.offset_secs))
+        }
+        (TimeFormat::Iso, None) => JsonValue::String(text.to_string()),
+        (TimeFormat::EpochMs, _) => ((secs * 1000.0).round() as i64).into(),
+        (TimeFormat::EpochS, _) if secs.fract() == 0.0 => (secs as i64).into(),
+        (TimeFormat::EpochS, _) => optional_number(Some(secs)),
+    }
+}
+
+/// For `timeFormat` and `timezone`: rewrite the `time` and `times`
+/// properties and `coordinateProperties.times` of `features`.
+fn format_times(features: &mut [Feature], opts: &ConvertOptions) {
+    if !formats_times(opts) {
+        return;
+    }
+    let format = |text: &str| format_time(text, opts);
+    for props in features.iter_mut().filter_map(|f| f.properties.as_mut()) {
+        for key in ["time", "times"] {
+            if let Some(value) = props.get_mut(key) {
+                map_strings(value, &format);
+            }
+        }
+        if let Some(times) = props
+            .get_mut("coordinateProperties")
+            .and_then(|c| c.get_mut("times"))
+        {
+            map_strings(times, &format);
+        }
+    }
+}
+
+/// For `includeProperties` and `excludeProperties`: drop properties of
+/// `features` by name.
+fn filter_properties(features: &mut [Feature], opts: &ConvertOptions) {
+    if opts.include_properties.is_none() && opts.exclude_properties.is_empty() {
+        return;
+    }
+    for props in features.iter_mut().filter_map(|f| f.properties.as_mut()) {
+        props.retain(|key, _| {
+            opts.include_properties
+                .as_ref()
+                .is_none_or(|keep| keep.contains(key))
+                && !opts.exclude_properties.contains(key)
+        });
+    }
+}
+
+/// Replace every string in `value`, descending into arrays.
+fn map_strings(value: &mut JsonValue, f: &impl Fn(&str) -> JsonValue) {
+    match value {
+        JsonValue::String(text) => *value = f(text),
+        JsonValue::Array(items) => items.iter_mut().for_each(|item| map_strings(item, f)),
+        _ => {}
+    }
+}
+
+/// For `compat: "togeojson"`: rename `gpxType` to `_gpxType` with the GPX
+/// element names, and put the `link` in a `links` array.
+fn togeojson_properties(feature: &mut Feature) {
+    let Some(props) = feature.properties.as_mut() else {
+        return;
+    };
+    if let Some(JsonValue::String(gpx_type)) = props.remove("gpxType") {
+        let element = match gpx_type.as_str() {
+            "waypoint" => "wpt",
+            "route" => "rte",
+            "track" => "trk",
+            other => other,
+        };
+        props.insert("_gpxType".to_string(), element.into());
+    }
+    if let Some(link) = props.remove("link") {
+        props.insert("links".to_string(), JsonValue::Array(vec![link]));
+    }
+}
+
+/// Strip a feature down to its geometry with rounded coordinates.
+fn minimize_feature(feature: &mut Feature) {
+    feature.properties = None;
+    let Some(geometry) = &mut feature.geometry else {
+        return;
+    };
+    let scale = 10f64.powi(MINIMAL_DECIMALS);
+    let round = |pos: &mut Vec<f64>| {
+        for c in pos.iter_mut() {
+            *c = (*c * scale).round() / scale;
+        }
+    };
+    match &mut geometry.value {
+        Value::Point(pos) => round(pos),
+        Value::MultiPoint(line) | Value::LineString(line) => line.iter_mut().for_each(round),
+        Value::MultiLineString(lines) => lines.iter_mut().flatten().for_each(round),
+        _ => {}
+    }
+}
+
+/// 64-bit FNV-1a hash of a geometry's type and rounded coordinates, as 16 hex digits.
+/// Stable across runs and platforms, so it can be stored for change detection.
+fn geometry_hash(value: &Value) -> String {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut hash = FNV_OFFSET;
+    let mut write = |bytes: &[u8]| {
+        for b in bytes {
+            hash ^= u64::from(*b);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    };
+
+    write(value.type_name().as_bytes());
+    let lines: Vec<&[Vec<f64>]> = match value {
+        Value::Point(pos) => vec![std::slice::from_ref(pos)],
+        Value::MultiPoint(line) | Value::LineString(line) => vec![line],
+        Value::MultiLineString(lines) => lines.iter().map(Vec::as_slice).collect(),
+        _ => Vec::new(),
+    };
+    for line in lines {
+        write(b"|");
+        for pos in line {
+            for c in pos {
+                write(&((c * HASH_SCALE).round() as i64).to_le_bytes());
+            }
+            write(b",");
+        }
     }
+    format!("{hash:016x}")
+}
+
+/// The document <metadata> as a `gpx_metadata` foreign member.
+fn metadata_to_json(metadata: &GpxMetadata) -> JsonValue {
+    let mut obj = Map::new();
+    insert_optional(&mut obj, "name", &metadata.name);
+    insert_optional(&mut obj, "desc", &metadata.desc);
+    insert_optional(&mut obj, "time", &metadata.time);
+    insert_optional(&mut obj, "keywords", &metadata.keywords);
+    if let Some(b) = &metadata.bounds {
+        obj.insert(
+            "bounds".to_string(),
+            serde_json::json!({
+                "minlat": b.min_lat,
+                "minlon": b.min_lon,
+                "maxlat": b.max_lat,
+                "maxlon": b.max_lon,
+            }),
+        );
+    }
+    JsonValue::Object(obj)
+}
+
+fn dropped_data_report(dropped: &DroppedData) -> JsonValue {
+    serde_json::json!({
+        "skippedPoints": dropped.skipped_points,
+        "filteredPoints": dropped.filtered_points,
+        "ignoredElements": dropped.ignored_elements,
+        "droppedExtensions": dropped.dropped_extensions,
+        "unknownEntities": dropped.unknown_entities,
+    })
 }
 
 fn waypoint_to_feature(pt: &GpxPoint, opts: &ConvertOptions) -> Feature {
-    let coords = point_coords(pt, opts.include_elevation);
+    let coords = point_coords(pt, opts.elevation_in_coordinates());
     let geometry = Geometry::new(Value::Point(coords));
 
     let mut props = Map::new();
@@ -48,8 +612,11 @@ fn waypoint_to_feature(pt: &GpxPoint, opts: &ConvertOptions) -> Feature {
     );
 
     if opts.include_metadata {
-        insert_point_metadata(&mut props, pt);
+        insert_point_metadata(&mut props, pt, opts);
     }
+    insert_extensions(&mut props, &pt.extensions);
+    insert_mapped_extensions(&mut props, &pt.extra, opts);
+    insert_had_extensions(&mut props, pt.had_extensions, opts);
 
     Feature {
         bbox: None,
@@ -60,14 +627,83 @@ fn waypoint_to_feature(pt: &GpxPoint, opts: &ConvertOptions) -> Feature {
     }
 }
 
+/// For `waypointsAsMultiPoint`: one MultiPoint Feature for all `points`,
+/// laid out like a route in `routeMode: "multiPoint"`.
+fn waypoints_to_multi_point(points: &[GpxPoint], opts: &ConvertOptions) -> Feature {
+    let coords = points
+        .iter()
+        .map(|pt| point_coords(pt, opts.elevation_in_coordinates()))
+        .collect();
+
+    let mut props = Map::new();
+    props.insert(
+        "gpxType".to_string(),
+        JsonValue::String("waypoint".to_string()),
+    );
+    let had_extensions = points.iter().any(|pt| pt.had_extensions);
+    insert_had_extensions(&mut props, had_extensions, opts);
+
+    let mut columns = coordinate_columns(opts);
+    if opts.include_metadata {
+        columns.push(("names", Box::new(|pt| optional_string(&pt.name))));
+        columns.push(("syms", Box::new(|pt| optional_string(&pt.sym))));
+        columns.push(("descs", Box::new(|pt| optional_string(&pt.desc))));
+    }
+    push_auto_extension_columns(&mut columns, &[points], opts);
+    insert_columns(&mut props, &[points], false, columns);
+
+    Feature {
+        bbox: None,
+        geometry: Some(Geometry::new(Value::MultiPoint(coords))),
+        id: None,
+        properties: Some(props),
+        foreign_members: None,
+    }
+}
+
+/// A copy of `rte` with each rtept's Garmin shape points inserted after it.
+fn expand_shape_points(rte: &GpxRoute) -> GpxRoute {
+    let points = rte
+        .points
+        .iter()
+        .flat_map(|pt| {
+            std::iter::once(pt.clone()).chain(
+                pt.shape_points
+                    .iter()
+                    .map(|&(lat, lon)| GpxPoint::new(lat, lon)),
+            )
+        })
+        .collect();
+    GpxRoute {
+        points,
+        ..rte.clone()
+    }
+}
+
+/// The Feature for a route: a line (or MultiPoint), a Point for a single
+/// point, or none for a route without points.
+fn route_or_point_feature(rte: &GpxRoute, opts: &ConvertOptions) -> Option<Feature> {
+    let multi_point = opts.route_mode == RouteMode::MultiPoint;
+    if rte.points.len() >= 2 || (multi_point && !rte.points.is_empty()) {
+        Some(route_to_feature(rte, opts))
+    } else {
+        rte.points
+            .first()
+            .map(|pt| single_point_feature(pt, "route", opts))
+    }
+}
+
 fn route_to_feature(rte: &GpxRoute, opts: &ConvertOptions) -> Feature {
     let coords: Vec<Vec<f64>> = rte
         .points
         .iter()
-        .map(|pt| point_coords(pt, opts.include_elevation))
+        .map(|pt| point_coords(pt, opts.elevation_in_coordinates()))
         .collect();
 
-    let geometry = Geometry::new(Value::LineString(coords));
+    let geometry = match opts.route_mode {
+        RouteMode::LineString => Geometry::new(Value::LineString(coords)),
+        RouteMode::MultiPoint => Geometry::new(Value::MultiPoint(coords)),
+    };
 
     let mut props = Map::new();
     props.insert(
@@ -77,19 +713,29 @@ fn route_to_feature(rte: &GpxRoute, opts: &ConvertOptions) -> Feature {
 
     if opts.include_metadata {
         insert_optional(&mut props, "name", &rte.name);
-        insert_optional(&mut props, "cmt", &rte.cmt);
-        insert_optional(&mut props, "desc", &rte.desc);
+        insert_description(&mut props, &rte.desc, &rte.cmt, opts);
         insert_optional(&mut props, "src", &rte.src);
         insert_optional(&mut props, "type", &rte.route_type);
         if let Some(n) = rte.number {
             props.insert("number".to_string(), JsonValue::Number(n.into()));
         }
         insert_link(&mut props, &rte.link);
+        insert_stroke(&mut props, &rte.display_color);
     }
+    insert_extensions(&mut props, &rte.extensions);
+    insert_mapped_extensions(&mut props, &rte.extra, opts);
+    let had_extensions = rte.had_extensions || rte.points.iter().any(|pt| pt.had_extensions);
+    insert_had_extensions(&mut props, had_extensions, opts);
+    insert_track_stats(&mut props, &[&rte.points], opts);
 
-    if opts.include_time {
-        insert_coordinate_times(&mut props, &rte.points);
+    let mut columns = coordinate_columns(opts);
+    if opts.route_mode == RouteMode::MultiPoint && opts.include_metadata {
+        columns.push(("names", Box::new(|pt| optional_string(&pt.name))));
+        columns.push(("syms", Box::new(|pt| optional_string(&pt.sym))));
+        columns.push(("descs", Box::new(|pt| optional_string(&pt.desc))));
     }
+    push_auto_extension_columns(&mut columns, &[&rte.points], opts);
+    insert_columns(&mut props, &[&rte.points], false, columns);
 
     Feature {
         bbox: None,
@@ -100,6 +746,98 @@ fn route_to_feature(rte: &GpxRoute, opts: &ConvertOptions) -> Feature {
     }
 }
 
+/// For `includeSourceIndex`: record which GPX element `features` came from.
+fn insert_source_index(features: &mut [Feature], key: &str, index: usize) {
+    for feature in features {
+        let props = feature.properties.get_or_insert_with(Map::new);
+        props.insert(key.to_string(), index.into());
+    }
+}
+
+/// For `trackMode: "feature-per-file"`: one track with the segments of all `tracks`, named
+/// after all of them. The type is kept if every track has the same one.
+fn join_tracks(tracks: &[GpxTrack]) -> GpxTrack {
+    let names: Vec<&str> = tracks.iter().filter_map(|t| t.name.as_deref()).collect();
+    let name = (!names.is_empty()).then(|| names.join(", "));
+    let track_type = tracks[0]
+        .track_type
+        .clone()
+        .filter(|ty| tracks.iter().all(|t| t.track_type.as_ref() == Some(ty)));
+    let had_extensions = tracks.iter().any(|t| t.had_extensions);
+    GpxTrack {
+        name,
+        track_type,
+        segments: tracks.iter().flat_map(|t| t.segments.clone()).collect(),
+        had_extensions,
+        ..Default::default()
+    }
+}
+
+/// Waypoints within `LINKED_WAYPOINT_METERS` (or `waypointsNearTracksOnly`)
+/// of a segment of `tracks`.
+fn linked_waypoints<'a>(
+    data: &'a GpxData,
+    tracks: &[GpxTrack],
+    opts: &ConvertOptions,
+) -> Vec<&'a GpxPoint> {
+    let max_m = opts
+        .waypoints_near_tracks_only
+        .unwrap_or(LINKED_WAYPOINT_METERS);
+    let lines: Vec<Vec<&GpxPoint>> = tracks
+        .iter()
+        .flat_map(|trk| &trk.segments)
+        .filter(|seg| !seg.points.is_empty())
+        .map(|seg| seg.points.iter().collect())
+        .collect();
+    data.waypoints
+        .iter()
+        .filter(|wpt| {
+            lines
+                .iter()
+                .any(|line| geo::distance_to_line(wpt, line) <= max_m)
+        })
+        .collect()
+}
+
+/// For `trackGeometryCollection`: replace a track line with a
+/// GeometryCollection of the line, its start and end Points and a Point per
+/// waypoint, listed in a `geometryRoles` property. Point features (single
+/// point tracks) are left as they are.
+fn into_geometry_collection(feature: &mut Feature, waypoints: &[&GpxPoint], opts: &ConvertOptions) {
+    let Some(geometry) = feature.geometry.take() else {
+        return;
+    };
+    let ends = match &geometry.value {
+        Value::LineString(line) => line.first().cloned().zip(line.last().cloned()),
+        Value::MultiLineString(lines) => lines
+            .first()
+            .and_then(|line| line.first().cloned())
+            .zip(lines.last().and_then(|line| line.last().cloned())),
+        _ => None,
+    };
+    let Some((start, end)) = ends else {
+        feature.geometry = Some(geometry);
+        return;
+    };
+
+    let mut geometries = vec![
+        geometry,
+        Geometry::new(Value::Point(start)),
+        Geometry::new(Value::Point(end)),
+    ];
+    let mut roles = vec!["track", "start", "end"];
+    for wpt in waypoints {
+        geometries.push(Geometry::new(Value::Point(point_coords(
+            wpt,
+            opts.elevation_in_coordinates(),
+        ))));
+        roles.push("waypoint");
+    }
+    feature.geometry = Some(Geometry::new(Value::GeometryCollection(geometries)));
+    let props = feature.properties.get_or_insert_with(Map::new);
+    props.insert("geometryRoles".to_string(), roles.into());
+}
+
 fn track_to_features(trk: &GpxTrack, opts: &ConvertOptions) -> Vec<Feature> {
     let non_empty_segments: Vec<&GpxSegment> =
         trk.segments.iter().filter(|s| !s.points.is_empty()).collect();
@@ -115,22 +853,21 @@ fn track_to_features(trk: &GpxTrack, opts: &ConvertOptions) -> Vec<Feature> {
         return vec![single_point_feature(pt, "track", opts)];
     }
 
-    if opts.join_track_segments || non_empty_segments.len() == 1 {
+    if opts.track_mode() != TrackMode::FeaturePerSegment || non_empty_segments.len() == 1 {
         // Single feature: LineString (1 segment) or MultiLineString (multiple)
         if non_empty_segments.len() == 1 && non_empty_segments[0].points.len() >= 2 {
             let seg = non_empty_segments[0];
             let coords: Vec<Vec<f64>> = seg
                 .points
                 .iter()
-                .map(|pt| point_coords(pt, opts.include_elevation))
+                .map(|pt| point_coords(pt, opts.elevation_in_coordinates()))
                 .collect();
 
             let geometry = Geometry::new(Value::LineString(coords));
             let mut props = build_track_props(trk, opts);
-
-            if opts.include_time {
-                insert_coordinate_times(&mut props, &seg.points);
-            }
+            insert_segment_extensions(&mut props, &[seg], false);
+            insert_coordinate_properties(&mut props, &[&seg.points], false, opts);
+            insert_track_stats(&mut props, &[&seg.points], opts);
 
             return vec![Feature {
                 bbox: None,
@@ -142,13 +879,21 @@ fn track_to_features(trk: &GpxTrack, opts: &ConvertOptions) -> Vec<Feature> {
         }
 
         // MultiLineString
-        let line_strings: Vec<Vec<Vec<f64>>> = non_empty_segments
+        let line_segments: Vec<&GpxSegment> = non_empty_segments
             .iter()
+            .copied()
             .filter(|s| s.points.len() >= 2)
-            .map(|seg| {
-                seg.points
+            .collect();
+        let lines: Vec<&[GpxPoint]> = line_segments
+            .iter()
+            .map(|seg| seg.points.as_slice())
+            .collect();
+        let line_strings: Vec<Vec<Vec<f64>>> = lines
+            .iter()
+            .map(|points| {
+                points
                     .iter()
-                    .map(|pt| point_coords(pt, opts.include_elevation))
+                    .map(|pt| point_coords(pt, opts.elevation_in_coordinates()))
                     .collect()
             })
             .collect();
@@ -159,32 +904,10 @@ fn track_to_features(trk: &GpxTrack, opts: &ConvertOptions) -> Vec<Feature> {
 
         let geometry = Geometry::new(Value::MultiLineString(line_strings));
         let mut props = build_track_props(trk, opts);
+        insert_segment_extensions(&mut props, &line_segments, true);
 
-        if opts.include_time {
-            let all_times: Vec<Vec<JsonValue>> = non_empty_segments
-                .iter()
-                .filter(|s| s.points.len() >= 2)
-                .map(|seg| {
-                    seg.points
-                        .iter()
-                        .map(|pt| match &pt.time {
-                            Some(t) => JsonValue::String(t.clone()),
-                            None => JsonValue::Null,
-                        })
-                        .collect()
-                })
-                .collect();
-            if all_times.iter().any(|times| times.iter().any(|t| !t.is_null())) {
-                let mut coord_props = Map::new();
-                coord_props.insert("times".to_string(), JsonValue::Array(
-                    all_times.into_iter().map(JsonValue::Array).collect(),
-                ));
-                props.insert(
-                    "coordinateProperties".to_string(),
-                    JsonValue::Object(coord_props),
-                );
-            }
-        }
+        insert_coordinate_properties(&mut props, &lines, true, opts);
+        insert_track_stats(&mut props, &lines, opts);
 
         vec![Feature {
             bbox: None,
@@ -195,21 +918,24 @@ fn track_to_features(trk: &GpxTrack, opts: &ConvertOptions) -> Vec<Feature> {
         }]
     } else {
         // Each segment as a separate Feature
-        non_empty_segments
+        trk.segments
             .iter()
-            .filter(|seg| seg.points.len() >= 2)
-            .map(|seg| {
+            .enumerate()
+            .filter(|(_, seg)| seg.points.len() >= 2)
+            .map(|(index, seg)| {
                 let coords: Vec<Vec<f64>> = seg
                     .points
                     .iter()
-                    .map(|pt| point_coords(pt, opts.include_elevation))
+                    .map(|pt| point_coords(pt, opts.elevation_in_coordinates()))
                     .collect();
 
                 let geometry = Geometry::new(Value::LineString(coords));
                 let mut props = build_track_props(trk, opts);
-
-                if opts.include_time {
-                    insert_coordinate_times(&mut props, &seg.points);
+                insert_segment_extensions(&mut props, &[seg], false);
+                insert_coordinate_properties(&mut props, &[&seg.points], false, opts);
+                insert_track_stats(&mut props, &[&seg.points], opts);
+                if opts.include_source_index {
+                    props.insert("segmentIndex".to_string(), index.into());
                 }
 
                 Feature {
@@ -224,8 +950,96 @@ fn track_to_features(trk: &GpxTrack, opts: &ConvertOptions) -> Vec<Feature> {
     }
 }
 
+/// Features for one detected activity of a track.
+fn activity_features(
+    act: &activity::Activity<GpxTrack>,
+    number: usize,
+    opts: &ConvertOptions,
+) -> Vec<Feature> {
+    let mut features = track_to_features(&act.element, opts);
+    insert_activity(&mut features, act, number);
+    features
+}
+
+/// Number the features of an activity, from 1 across the file, and give
+/// them the activity's own distance and duration.
+fn insert_activity<T>(features: &mut [Feature], act: &activity::Activity<T>, number: usize) {
+    for feature in features {
+        let props = feature.properties.get_or_insert_with(Map::new);
+        props.insert("activity".to_string(), number.into());
+        props.insert("distance".to_string(), optional_number(Some(act.distance)));
+        props.insert("duration".to_string(), optional_number(act.duration));
+    }
+}
+
+/// Build one "section" Feature per track, covering the trackpoints between
+/// the points nearest to the `from` and `to` waypoints, with its own
+/// distance, duration and `includeStats` statistics.
+fn section_features(
+    data: &GpxData,
+    section: &SectionOptions,
+    opts: &ConvertOptions,
+) -> Vec<Feature> {
+    let find = |name: &str| {
+        data.waypoints
+            .iter()
+            .find(|wpt| wpt.name.as_deref() == Some(name))
+    };
+    let (Some(from), Some(to)) = (find(&section.from), find(&section.to)) else {
+        return Vec::new();
+    };
+
+    data.tracks
+        .iter()
+        .filter_map(|trk| {
+            let points: Vec<&GpxPoint> = trk.segments.iter().flat_map(|s| &s.points).collect();
+            let i = geo::nearest_point_index(&points, from)?;
+            let j = geo::nearest_point_index(&points, to)?;
+            let (start, end) = if i <= j { (i, j) } else { (j, i) };
+            if start == end {
+                return None;
+            }
+
+            let section_points: Vec<GpxPoint> =
+                points[start..=end].iter().map(|pt| (*pt).clone()).collect();
+            let coords: Vec<Vec<f64>> = section_points
+                .iter()
+                .map(|pt| point_coords(pt, opts.elevation_in_coordinates()))
+                .collect();
+
+            let mut props = Map::new();
+            props.insert(
+                "gpxType".to_string(),
+                JsonValue::String("section".to_string()),
+            );
+            props.insert("from".to_string(), JsonValue::String(section.from.clone()));
+            props.insert("to".to_string(), JsonValue::String(section.to.clone()));
+            let distance = geo::path_length(&section_points, opts.distance_formula);
+            if let Some(distance) = serde_json::Number::from_f64(distance) {
+                props.insert("distance".to_string(), JsonValue::Number(distance));
+            }
+            if let Some(duration) = activity::duration(&section_points) {
+                props.insert("duration".to_string(), optional_number(Some(duration)));
+            }
+            if opts.include_metadata {
+                insert_optional(&mut props, "name", &trk.name);
+            }
+            insert_track_stats(&mut props, &[&section_points], opts);
+            insert_coordinate_properties(&mut props, &[&section_points], false, opts);
+
+            Some(Feature {
+                bbox: None,
+                geometry: Some(Geometry::new(Value::LineString(coords))),
+                id: None,
+                properties: Some(props),
+                foreign_members: None,
+            })
+        })
+        .collect()
+}
+
 fn single_point_feature(pt: &GpxPoint, gpx_type: &str, opts: &ConvertOptions) -> Feature {
-    let coords = point_coords(pt, opts.include_elevation);
+    let coords = point_coords(pt, opts.elevation_in_coordinates());
     let geometry = Geometry::new(Value::Point(coords));
 
     let mut props = Map::new();
@@ -235,8 +1049,11 @@ fn single_point_feature(pt: &GpxPoint, gpx_type: &str, opts: &ConvertOptions) ->
     );
 
     if opts.include_metadata {
-        insert_point_metadata(&mut props, pt);
+        insert_point_metadata(&mut props, pt, opts);
     }
+    insert_extensions(&mut props, &pt.extensions);
+    insert_mapped_extensions(&mut props, &pt.extra, opts);
+    insert_had_extensions(&mut props, pt.had_extensions, opts);
 
     Feature {
         bbox: None,
@@ -247,6 +1064,68 @@ fn single_point_feature(pt: &GpxPoint, gpx_type: &str, opts: &ConvertOptions) ->
     }
 }
 
+/// For `output: "points"`: a "routePoint"/"trackPoint" Feature for a point
+/// of a route or track, named by its `parent` ("route"/"track") property.
+/// The values that lines carry in coordinateProperties become scalars.
+fn line_point_feature(
+    pt: &GpxPoint,
+    parent: &str,
+    parent_name: &Option<String>,
+    opts: &ConvertOptions,
+) -> Feature {
+    let mut feature = single_point_feature(pt, &format!("{parent}Point"), opts);
+    let props = feature.properties.get_or_insert_with(Map::new);
+    if opts.include_metadata {
+        insert_optional(props, parent, parent_name);
+    }
+    if opts.include_time {
+        insert_optional(props, "time", &pt.time);
+    }
+    if opts.include_elevation
+        && let Some(ele) = pt.ele
+    {
+        props.insert("ele".to_string(), optional_number(Some(ele)));
+    }
+
+    let (cadence, temperature) = match opts.sensor_naming {
+        SensorNaming::Default => ("cadence", "temperature"),
+        SensorNaming::Togeojson => ("cad", "atemp"),
+    };
+    for (key, value) in [
+        ("speed", pt.speed),
+        ("course", pt.course),
+        (temperature, pt.temperature),
+        ("distance", pt.distance),
+    ] {
+        if value.is_some() {
+            props.insert(key.to_string(), optional_number(value));
+        }
+    }
+    for (key, value) in [
+        ("heartRate", pt.heart_rate),
+        (cadence, pt.cadence),
+        ("power", pt.power),
+    ] {
+        if value.is_some() {
+            props.insert(key.to_string(), optional_int(value));
+        }
+    }
+    for name in &opts.extra_point_elements {
+        let value = extra_point_value(pt, name);
+        if !value.is_null() {
+            props.insert(name.clone(), value);
+        }
+    }
+    if opts.auto_extensions {
+        for (name, &value) in &pt.extension_values {
+            props
+                .entry(name.clone())
+                .or_insert_with(|| optional_number(Some(value)));
+        }
+    }
+    feature
+}
+
 fn build_track_props(trk: &GpxTrack, opts: &ConvertOptions) -> Map<String, JsonValue> {
     let mut props = Map::new();
     props.insert(
@@ -256,31 +1135,66 @@ fn build_track_props(trk: &GpxTrack, opts: &ConvertOptions) -> Map<String, JsonV
 
     if opts.include_metadata {
         insert_optional(&mut props, "name", &trk.name);
-        insert_optional(&mut props, "cmt", &trk.cmt);
-        insert_optional(&mut props, "desc", &trk.desc);
+        insert_description(&mut props, &trk.desc, &trk.cmt, opts);
         insert_optional(&mut props, "src", &trk.src);
         insert_optional(&mut props, "type", &trk.track_type);
         if let Some(n) = trk.number {
             props.insert("number".to_string(), JsonValue::Number(n.into()));
         }
         insert_link(&mut props, &trk.link);
+        insert_stroke(&mut props, &trk.display_color);
     }
+    insert_extensions(&mut props, &trk.extensions);
+    insert_mapped_extensions(&mut props, &trk.extra, opts);
+    let had_extensions = trk.had_extensions
+        || trk
+            .segments
+            .iter()
+            .flat_map(|seg| &seg.points)
+            .any(|pt| pt.had_extensions);
+    insert_had_extensions(&mut props, had_extensions, opts);
 
     props
 }
 
+/// Add the `includeStats` track statistics to `props`, for a track, route
+/// or section.
+fn insert_track_stats(
+    props: &mut Map<String, JsonValue>,
+    lines: &[&[GpxPoint]],
+    opts: &ConvertOptions,
+) {
+    if opts.include_stats {
+        props.extend(stats::track_stats(
+            lines,
+            opts.gradient_window_meters,
+            opts.distance_formula,
+        ));
+    }
+}
+
+/// `hadExtensions: true` when `includeSource` is on and the element or its points had <extensions>.
+fn insert_had_extensions(
+    props: &mut Map<String, JsonValue>,
+    had_extensions: bool,
+    opts: &ConvertOptions,
+) {
+    if opts.include_source && had_extensions {
+        props.insert("hadExtensions".to_string(), JsonValue::Bool(true));
+    }
+}
+
 /// Build [lon, lat] or [lon, lat, ele] coordinate array.
-fn point_coords(pt: &GpxPoint, include_elevation: bool) -> Vec<f64> {
+pub(crate) fn point_coords(pt: &GpxPoint, include_elevation: bool) -> Vec<f64> {
     match (include_elevation, pt.ele) {
         (true, Some(ele)) => vec![pt.lon, pt.lat, ele],
         _ => vec![pt.lon, pt.lat],
     }
 }
 
-fn insert_point_metadata(props: &mut Map<String, JsonValue>, pt: &GpxPoint) {
+fn insert_point_metadata(props: &mut Map<String, JsonValue>, pt: &GpxPoint, opts: &ConvertOptions) {
     insert_optional(props, "name", &pt.name);
-    insert_optional(props, "cmt", &pt.cmt);
-    insert_optional(props, "desc", &pt.desc);
+    insert_description(props, &pt.desc, &pt.cmt, opts);
     insert_optional(props, "src", &pt.src);
     insert_optional(props, "sym", &pt.sym);
     insert_optional(props, "type", &pt.point_type);
@@ -293,16 +1207,92 @@ fn insert_point_metadata(props: &mut Map<String, JsonValue>, pt: &GpxPoint) {
     if let Some(ref time) = pt.time {
         props.insert("time".to_string(), JsonValue::String(time.clone()));
     }
+    insert_optional(props, "fix", &pt.fix);
+    if let Some(sat) = pt.sat {
+        props.insert("sat".to_string(), sat.into());
+    }
+    for (key, value) in [
+        ("hdop", pt.hdop),
+        ("vdop", pt.vdop),
+        ("pdop", pt.pdop),
+        ("magvar", pt.magvar),
+        ("geoidheight", pt.geoid_height),
+        ("ageofdgpsdata", pt.age_of_dgps_data),
+    ] {
+        if value.is_some() {
+            props.insert(key.to_string(), optional_number(value));
+        }
+    }
+    if let Some(id) = pt.dgps_id {
+        props.insert("dgpsid".to_string(), id.into());
+    }
     insert_link(props, &pt.link);
-}
-
-fn insert_optional(props: &mut Map<String, JsonValue>, key: &str, value: &Option<String>) {
-    if let Some(v) = value {
-        props.insert(key.to_string(), JsonValue::String(v.clone()));
+    if let Some(garmin) = &pt.garmin_waypoint {
+        insert_garmin_waypoint(props, garmin);
     }
 }
 
-fn insert_link(props: &mut Map<String, JsonValue>, link: &Option<GpxLink>) {
+/// Garmin WaypointExtension details as `address`, `phone`, `categories`,
+/// `proximity` and `depth`.
+fn insert_garmin_waypoint(props: &mut Map<String, JsonValue>, garmin: &GarminWaypoint) {
+    if let Some(addr) = &garmin.address {
+        let mut obj = Map::new();
+        insert_optional(&mut obj, "street", &addr.street);
+        insert_optional(&mut obj, "city", &addr.city);
+        insert_optional(&mut obj, "state", &addr.state);
+        insert_optional(&mut obj, "country", &addr.country);
+        insert_optional(&mut obj, "postalCode", &addr.postal_code);
+        props.insert("address".to_string(), JsonValue::Object(obj));
+    }
+    insert_optional(props, "phone", &garmin.phone);
+    if !garmin.categories.is_empty() {
+        props.insert("categories".to_string(), garmin.categories.clone().into());
+    }
+    for (key, value) in [("proximity", garmin.proximity), ("depth", garmin.depth)] {
+        if value.is_some() {
+            props.insert(key.to_string(), optional_number(value));
+        }
+    }
+}
+
+/// `desc` and `cmt`, or a merged `description`, depending on `descriptionMode`.
+/// With `stripHtml`, HTML tags are removed from both first.
+fn insert_description(
+    props: &mut Map<String, JsonValue>,
+    desc: &Option<String>,
+    cmt: &Option<String>,
+    opts: &ConvertOptions,
+) {
+    let clean = |text: &Option<String>| match text {
+        Some(text) if opts.strip_html => Some(html::strip_html(text)),
+        _ => text.clone(),
+    };
+    let (desc, cmt) = (clean(desc), clean(cmt));
+    match opts.description_mode {
+        DescriptionMode::Separate => {
+            insert_optional(props, "cmt", &cmt);
+            insert_optional(props, "desc", &desc);
+        }
+        DescriptionMode::Merged => {
+            let parts: Vec<&str> = [&desc, &cmt]
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect();
+            if !parts.is_empty() {
+                props.insert("description".to_string(), parts.join("\n").into());
+            }
+        }
+    }
+}
+
+fn insert_optional(props: &mut Map<String, JsonValue>, key: &str, value: &Option<String>) {
+    if let Some(v) = value {
+        props.insert(key.to_string(), JsonValue::String(v.clone()));
+    }
+}
+
+fn insert_link(props: &mut Map<String, JsonValue>, link: &Option<GpxLink>) {
     if let Some(link) = link {
         let mut link_obj = Map::new();
         link_obj.insert("href".to_string(), JsonValue::String(link.href.clone()));
@@ -316,19 +1306,254 @@ fn insert_link(props: &mut Map<String, JsonValue>, link: &Option<GpxLink>) {
     }
 }
 
-fn insert_coordinate_times(props: &mut Map<String, JsonValue>, points: &[GpxPoint]) {
-    let times: Vec<JsonValue> = points
+/// Insert a simplestyle `stroke` color from a Garmin DisplayColor name.
+fn insert_stroke(props: &mut Map<String, JsonValue>, display_color: &Option<String>) {
+    if let Some(hex) = display_color
+        .as_deref()
+        .and_then(style::garmin_display_color)
+    {
+        props.insert("stroke".to_string(), JsonValue::String(hex.to_string()));
+    }
+}
+
+/// Insert captured <extensions> as a nested `extensions` object.
+fn insert_extensions(props: &mut Map<String, JsonValue>, extensions: &Option<Vec<XmlElement>>) {
+    if let Some(elements) = extensions {
+        props.insert(
+            "extensions".to_string(),
+            JsonValue::Object(elements_to_json(elements)),
+        );
+    }
+}
+
+/// Insert the <trkseg> extensions as `segmentExtensions`: one object for a
+/// single segment, or with `nested` an array with one entry (or null) per line.
+fn insert_segment_extensions(
+    props: &mut Map<String, JsonValue>,
+    segments: &[&GpxSegment],
+    nested: bool,
+) {
+    if segments.iter().all(|seg| seg.extensions.is_none()) {
+        return;
+    }
+    let to_json = |seg: &&GpxSegment| match &seg.extensions {
+        Some(elements) => JsonValue::Object(elements_to_json(elements)),
+        None => JsonValue::Null,
+    };
+    let value = if nested {
+        JsonValue::Array(segments.iter().map(to_json).collect())
+    } else {
+        to_json(&segments[0])
+    };
+    props.insert("segmentExtensions".to_string(), value);
+}
+
+/// Map child elements by local name; repeated names become arrays.
+fn elements_to_json(elements: &[XmlElement]) -> Map<String, JsonValue> {
+    let mut obj = Map::new();
+    for el in elements {
+        let value = element_to_json(el);
+        match obj.get_mut(&el.name) {
+            Some(JsonValue::Array(values)) => values.push(value),
+            Some(existing) => *existing = JsonValue::Array(vec![existing.take(), value]),
+            None => {
+                obj.insert(el.name.clone(), value);
+            }
+        }
+    }
+    obj
+}
+
+/// A leaf element becomes its text; otherwise an object of `@attribute`s,
+/// children and (if any) `#text`.
+fn element_to_json(el: &XmlElement) -> JsonValue {
+    if el.children.is_empty() && el.attributes.is_empty() {
+        return JsonValue::String(el.text.clone());
+    }
+    let mut obj = Map::new();
+    for (key, value) in &el.attributes {
+        obj.insert(format!("@{key}"), JsonValue::String(value.clone()));
+    }
+    obj.extend(elements_to_json(&el.children));
+    if !el.text.is_empty() {
+        obj.insert("#text".to_string(), JsonValue::String(el.text.clone()));
+    }
+    JsonValue::Object(obj)
+}
+
+/// A per-point value emitted as a `coordinateProperties` array.
+type CoordinateColumn<'o> = (&'o str, Box<dyn Fn(&GpxPoint) -> JsonValue + 'o>);
+
+/// The per-point columns enabled by `opts`.
+fn coordinate_columns(opts: &ConvertOptions) -> Vec<CoordinateColumn<'_>> {
+    let mut columns: Vec<CoordinateColumn<'_>> = Vec::new();
+    if opts.include_time {
+        columns.push(("times", Box::new(|pt| optional_string(&pt.time))));
+    }
+    if opts.elevation_in_properties() {
+        columns.push(("elevations", Box::new(|pt| optional_number(pt.ele))));
+    }
+    columns.push(("speeds", Box::new(|pt| optional_number(pt.speed))));
+    columns.push(("courses", Box::new(|pt| optional_number(pt.course))));
+    let (cadences, temperatures) = match opts.sensor_naming {
+        SensorNaming::Default => ("cadences", "temperatures"),
+        SensorNaming::Togeojson => ("cads", "atemps"),
+    };
+    columns.push(("heartRates", Box::new(|pt| optional_int(pt.heart_rate))));
+    columns.push((cadences, Box::new(|pt| optional_int(pt.cadence))));
+    columns.push((temperatures, Box::new(|pt| optional_number(pt.temperature))));
+    columns.push(("distances", Box::new(|pt| optional_number(pt.distance))));
+    columns.push(("powers", Box::new(|pt| optional_int(pt.power))));
+    if opts.include_fix_quality {
+        columns.push(("sats", Box::new(|pt| optional_int(pt.sat))));
+        columns.push(("hdops", Box::new(|pt| optional_number(pt.hdop))));
+        columns.push(("vdops", Box::new(|pt| optional_number(pt.vdop))));
+        columns.push(("pdops", Box::new(|pt| optional_number(pt.pdop))));
+        columns.push(("fixes", Box::new(|pt| optional_string(&pt.fix))));
+    }
+    for name in &opts.extra_point_elements {
+        columns.push((name, Box::new(move |pt| extra_point_value(pt, name))));
+    }
+    for name in mapped_names(opts) {
+        columns.push((name, Box::new(move |pt| text_value(pt.extra.get(name)))));
+    }
+    columns
+}
+
+/// Distinct property names targeted by `extensionMappings`.
+fn mapped_names(opts: &ConvertOptions) -> Vec<&str> {
+    let mut names: Vec<&str> = opts
+        .extension_mappings
+        .values()
+        .map(String::as_str)
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+/// Properties for the `extensionMappings` values found on a waypoint, route or track.
+fn insert_mapped_extensions(
+    props: &mut Map<String, JsonValue>,
+    extra: &BTreeMap<String, String>,
+    opts: &ConvertOptions,
+) {
+    for name in mapped_names(opts) {
+        if let Some(text) = extra.get(name) {
+            props.insert(name.to_string(), text_value(Some(text)));
+        }
+    }
+}
+
+/// Captured element text as JSON: a number when numeric, else the string.
+fn text_value(text: Option<&String>) -> JsonValue {
+    match text {
+        Some(text) => text
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map_or_else(|| JsonValue::String(text.clone()), JsonValue::Number),
+        None => JsonValue::Null,
+    }
+}
+
+/// Value of a point child element requested via `extraPointElements`.
+///
+/// Elements the parser already models are read from their typed fields;
+/// any other element comes from the captured text, as a number when numeric.
+fn extra_point_value(pt: &GpxPoint, name: &str) -> JsonValue {
+    match name {
+        "ele" => optional_number(pt.ele),
+        "time" => optional_string(&pt.time),
+        "name" => optional_string(&pt.name),
+        "cmt" => optional_string(&pt.cmt),
+        "desc" => optional_string(&pt.desc),
+        "src" => optional_string(&pt.src),
+        "sym" => optional_string(&pt.sym),
+        "type" => optional_string(&pt.point_type),
+        "fix" => optional_string(&pt.fix),
+        "sat" => optional_int(pt.sat),
+        "hdop" => optional_number(pt.hdop),
+        "vdop" => optional_number(pt.vdop),
+        "pdop" => optional_number(pt.pdop),
+        "magvar" => optional_number(pt.magvar),
+        "geoidheight" => optional_number(pt.geoid_height),
+        "dgpsid" => optional_int(pt.dgps_id),
+        "ageofdgpsdata" => optional_number(pt.age_of_dgps_data),
+        "speed" => optional_number(pt.speed),
+        "course" => optional_number(pt.course),
+        _ => text_value(pt.extra.get(name)),
+    }
+}
+
+/// Insert `coordinateProperties` for the points of a line geometry.
+///
+/// With `nested`, each array holds one sub-array per line (MultiLineString).
+/// A column is only included if at least one point has a value.
+fn insert_coordinate_properties(
+    props: &mut Map<String, JsonValue>,
+    lines: &[&[GpxPoint]],
+    nested: bool,
+    opts: &ConvertOptions,
+) {
+    let mut columns = coordinate_columns(opts);
+    push_auto_extension_columns(&mut columns, lines, opts);
+    insert_columns(props, lines, nested, columns);
+}
+
+/// Add a column for every numeric extension found by `autoExtensions`,
+/// unless a column of that name already exists.
+fn push_auto_extension_columns<'a>(
+    columns: &mut Vec<CoordinateColumn<'a>>,
+    lines: &[&'a [GpxPoint]],
+    opts: &ConvertOptions,
+) {
+    if !opts.auto_extensions {
+        return;
+    }
+    let names: BTreeSet<&'a str> = lines
         .iter()
-        .map(|pt| match &pt.time {
-            Some(t) => JsonValue::String(t.clone()),
-            None => JsonValue::Null,
-        })
+        .flat_map(|points| points.iter())
+        .flat_map(|pt| pt.extension_values.keys().map(String::as_str))
         .collect();
+    for name in names {
+        if columns.iter().any(|(key, _)| *key == name) {
+            continue;
+        }
+        columns.push((
+            name,
+            Box::new(move |pt| optional_number(pt.extension_values.get(name).copied())),
+        ));
+    }
+}
+
+/// Insert `coordinateProperties` built from an explicit list of columns.
+fn insert_columns(
+    props: &mut Map<String, JsonValue>,
+    lines: &[&[GpxPoint]],
+    nested: bool,
+    columns: Vec<CoordinateColumn<'_>>,
+) {
+    let mut coord_props = Map::new();
 
-    // Only include if at least one time is present
-    if times.iter().any(|t| !t.is_null()) {
-        let mut coord_props = Map::new();
-        coord_props.insert("times".to_string(), JsonValue::Array(times));
+    for (key, value_of) in columns {
+        let mut arrays: Vec<Vec<JsonValue>> = lines
+            .iter()
+            .map(|points| points.iter().map(&value_of).collect())
+            .collect();
+        if arrays.iter().flatten().all(JsonValue::is_null) {
+            continue;
+        }
+        let value = if nested {
+            JsonValue::Array(arrays.into_iter().map(JsonValue::Array).collect())
+        } else {
+            JsonValue::Array(arrays.swap_remove(0))
+        };
+        coord_props.insert(key.to_string(), value);
+    }
+
+    if !coord_props.is_empty() {
         props.insert(
             "coordinateProperties".to_string(),
             JsonValue::Object(coord_props),
@@ -336,6 +1561,23 @@ fn insert_coordinate_times(props: &mut Map<String, JsonValue>, points: &[GpxPoin
     }
 }
 
+fn optional_string(value: &Option<String>) -> JsonValue {
+    match value {
+        Some(v) => JsonValue::String(v.clone()),
+        None => JsonValue::Null,
+    }
+}
+
+fn optional_int(value: Option<u32>) -> JsonValue {
+    value.map_or(JsonValue::Null, Into::into)
+}
+
+fn optional_number(value: Option<f64>) -> JsonValue {
+    value
+        .and_then(serde_json::Number::from_f64)
+        .map_or(JsonValue::Null, JsonValue::Number)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -372,6 +1614,44 @@ mod tests {
         assert_eq!(props["ele"], 40.5);
     }
 
+    #[test]
+    fn test_waypoint_accuracy_properties() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0">
+    <fix>dgps</fix><sat>11</sat><hdop>0.7</hdop><vdop>1.1</vdop><pdop>1.3</pdop>
+    <magvar>352.5</magvar><geoidheight>36.7</geoidheight>
+    <ageofdgpsdata>4.0</ageofdgpsdata><dgpsid>117</dgpsid>
+  </wpt>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(props["fix"], "dgps");
+        assert_eq!(props["sat"], 11);
+        assert_eq!(props["hdop"], 0.7);
+        assert_eq!(props["vdop"], 1.1);
+        assert_eq!(props["pdop"], 1.3);
+        assert_eq!(props["magvar"], 352.5);
+        assert_eq!(props["geoidheight"], 36.7);
+        assert_eq!(props["ageofdgpsdata"], 4.0);
+        assert_eq!(props["dgpsid"], 117);
+
+        let opts = ConvertOptions {
+            include_metadata: false,
+            ..Default::default()
+        };
+        let fc = to_feature_collection(&data, &opts);
+        assert!(
+            !fc.features[0]
+                .properties
+                .as_ref()
+                .unwrap()
+                .contains_key("magvar")
+        );
+    }
+
     #[test]
     fn test_track_with_times() {
         let xml = r#"<?xml version="1.0"?>
@@ -458,6 +1738,287 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_join_tracks() {
+        let xml = r#"<gpx version="1.1">
+  <trk><name>Day 1</name><type>hiking</type>
+    <trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/></trkseg>
+  </trk>
+  <trk><name>Day 2</name><type>hiking</type>
+    <trkseg><trkpt lat="35.1" lon="139.1"/><trkpt lat="35.2" lon="139.2"/></trkseg>
+    <trkseg><trkpt lat="35.3" lon="139.3"/><trkpt lat="35.4" lon="139.4"/></trkseg>
+  </trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions = serde_json::from_str(r#"{"joinTracks": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        assert_eq!(fc.features.len(), 1);
+        let Value::MultiLineString(lines) = &fc.features[0].geometry.as_ref().unwrap().value else {
+            panic!("expected a MultiLineString");
+        };
+        assert_eq!(lines.len(), 3);
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(props["name"], "Day 1, Day 2");
+        assert_eq!(props["type"], "hiking");
+
+        let track_mode = |mode: &str| {
+            let opts: ConvertOptions =
+                serde_json::from_str(&format!(r#"{{"trackMode": "{mode}"}}"#)).unwrap();
+            to_feature_collection(&data, &opts).features
+        };
+        assert_eq!(track_mode("feature-per-file"), fc.features);
+        assert_eq!(track_mode("feature-per-track").len(), 2);
+        assert_eq!(track_mode("feature-per-segment").len(), 3);
+    }
+
+    #[test]
+    fn test_track_geometry_collection() {
+        let xml = r#"<gpx version="1.1">
+  <wpt lat="35.0005" lon="139.0"><name>Near</name></wpt>
+  <wpt lat="36.0" lon="140.0"><name>Far</name></wpt>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.01" lon="139.0"/></trkseg></trk>
+</gpx>"#;
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"types": ["track"], "trackGeometryCollection": true}"#)
+                .unwrap();
+        // Waypoints are still read although `types` excludes them
+        let data = crate::parser::parse_gpx_with_options(xml, &opts).unwrap();
+        assert_eq!(data.waypoints.len(), 2);
+        let fc = to_feature_collection(&data, &opts);
+
+        assert_eq!(fc.features.len(), 1);
+        let Value::GeometryCollection(geometries) =
+            &fc.features[0].geometry.as_ref().unwrap().value
+        else {
+            panic!("expected a GeometryCollection");
+        };
+        let values: Vec<&Value> = geometries.iter().map(|g| &g.value).collect();
+        assert_eq!(
+            values,
+            [
+                &Value::LineString(vec![vec![139.0, 35.0], vec![139.0, 35.01]]),
+                &Value::Point(vec![139.0, 35.0]),
+                &Value::Point(vec![139.0, 35.01]),
+                &Value::Point(vec![139.0, 35.0005]),
+            ]
+        );
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(
+            props["geometryRoles"],
+            serde_json::json!(["track", "start", "end", "waypoint"])
+        );
+    }
+
+    #[test]
+    fn test_output_geometries() {
+        let xml = r#"<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><name>A</name></wpt>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/></trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions = serde_json::from_str(r#"{"output": "geometries"}"#).unwrap();
+        let output = into_output(to_feature_collection(&data, &opts), &opts);
+        let GeoJson::Geometry(geometry) = output else {
+            panic!("expected a Geometry");
+        };
+        assert_eq!(
+            geometry.value,
+            Value::GeometryCollection(vec![
+                Geometry::new(Value::Point(vec![139.0, 35.0])),
+                Geometry::new(Value::LineString(vec![
+                    vec![139.0, 35.0],
+                    vec![139.1, 35.1]
+                ])),
+            ])
+        );
+
+        let opts = ConvertOptions::default();
+        let output = into_output(to_feature_collection(&data, &opts), &opts);
+        assert!(matches!(output, GeoJson::FeatureCollection(_)));
+    }
+
+    #[test]
+    fn test_compat_togeojson() {
+        let xml = r#"<gpx version="1.1">
+  <metadata><name>File</name></metadata>
+  <wpt lat="35.0" lon="139.0"><name>A</name><link href="https://example.com"><text>Site</text></link></wpt>
+  <trk><name>Run</name>
+    <trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/></trkseg>
+    <trkseg><trkpt lat="35.2" lon="139.2"/><trkpt lat="35.3" lon="139.3"/></trkseg>
+  </trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions = serde_json::from_str(r#"{"compat": "togeojson"}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        assert!(fc.foreign_members.is_none());
+        assert_eq!(fc.features.len(), 2);
+        let wpt = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(wpt["_gpxType"], "wpt");
+        assert!(!wpt.contains_key("gpxType"));
+        assert_eq!(
+            wpt["links"],
+            serde_json::json!([{ "href": "https://example.com", "text": "Site" }])
+        );
+        let trk = &fc.features[1];
+        assert_eq!(trk.properties.as_ref().unwrap()["_gpxType"], "trk");
+        assert!(matches!(
+            trk.geometry.as_ref().unwrap().value,
+            Value::MultiLineString(_)
+        ));
+    }
+
+    #[test]
+    fn test_time_format() {
+        let xml = r#"<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><time>2025-01-01T00:00:00.5Z</time></wpt>
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><time>2025-01-01T09:00:00+09:00</time></trkpt>
+    <trkpt lat="35.1" lon="139.1"><time>bogus</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let times = |format: &str| {
+            let opts: ConvertOptions =
+                serde_json::from_str(&format!(r#"{{"timeFormat": "{format}"}}"#)).unwrap();
+            let fc = to_feature_collection(&data, &opts);
+            let wpt = fc.features[0].properties.as_ref().unwrap();
+            let trk = fc.features[1].properties.as_ref().unwrap();
+            (
+                wpt["time"].clone(),
+                trk["coordinateProperties"]["times"].clone(),
+            )
+        };
+
+        assert_eq!(
+            times("epochMs"),
+            (
+                serde_json::json!(1_735_689_600_500_i64),
+                serde_json::json!([1_735_689_600_000_i64, null])
+            )
+        );
+        assert_eq!(
+            times("epochS"),
+            (
+                serde_json::json!(1_735_689_600.5),
+                serde_json::json!([1_735_689_600, null])
+            )
+        );
+        assert_eq!(
+            times("iso").1,
+            serde_json::json!(["2025-01-01T09:00:00+09:00", "bogus"])
+        );
+    }
+
+    #[test]
+    fn test_timezone() {
+        let xml = r#"<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><time>2025-01-01T00:00:00Z</time></wpt>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions = serde_json::from_str(r#"{"timezone": "Asia/Tokyo"}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        assert_eq!(
+            fc.features[0].properties.as_ref().unwrap()["time"],
+            "2025-01-01T09:00:00+09:00"
+        );
+
+        // Foreign members are converted too, so the output uses one zone
+        let xml = r#"<gpx version="1.1">
+  <metadata><time>2025-01-01T00:00:00Z</time></metadata>
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><time>2025-01-01T00:00:00Z</time></trkpt>
+    <trkpt lat="35.1" lon="139.1"><time>2025-01-01T01:00:00Z</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"timezone": "+09:00", "includeSummary": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        let foreign_members = fc.foreign_members.as_ref().unwrap();
+        assert_eq!(
+            foreign_members["gpx_metadata"]["time"],
+            "2025-01-01T09:00:00+09:00"
+        );
+        assert_eq!(
+            foreign_members["summary"]["timeRange"],
+            serde_json::json!(["2025-01-01T09:00:00+09:00", "2025-01-01T10:00:00+09:00"])
+        );
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"timeFormat": "epochS", "includeSummary": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        assert_eq!(
+            fc.foreign_members.as_ref().unwrap()["summary"]["timeRange"],
+            serde_json::json!([1735689600, 1735693200])
+        );
+
+        let err =
+            serde_json::from_str::<ConvertOptions>(r#"{"timezone": "Europe/Berlin"}"#).unwrap_err();
+        assert!(err.to_string().contains("Unknown timezone 'Europe/Berlin'"));
+    }
+
+    #[test]
+    fn test_property_filters() {
+        let xml = r#"<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><name>A</name><desc>Long text</desc><sym>Flag</sym></wpt>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let keys = |options: &str| -> Vec<String> {
+            let opts: ConvertOptions = serde_json::from_str(options).unwrap();
+            let fc = to_feature_collection(&data, &opts);
+            fc.features[0]
+                .properties
+                .as_ref()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect()
+        };
+
+        assert_eq!(keys(r#"{"includeProperties": ["name", "time"]}"#), ["name"]);
+        assert_eq!(
+            keys(r#"{"excludeProperties": ["desc", "gpxType"]}"#),
+            ["name", "sym"]
+        );
+    }
+
+    #[test]
+    fn test_gpx_type_key() {
+        let data = parse_gpx(r#"<gpx><wpt lat="35.0" lon="139.0"/></gpx>"#).unwrap();
+        let props = |options: &str| {
+            let opts: ConvertOptions = serde_json::from_str(options).unwrap();
+            to_feature_collection(&data, &opts).features[0]
+                .properties
+                .clone()
+                .unwrap()
+        };
+
+        assert_eq!(props("{}")["gpxType"], "waypoint");
+        let renamed = props(r#"{"gpxTypeKey": "_gpxType"}"#);
+        assert_eq!(renamed["_gpxType"], "waypoint");
+        assert!(!renamed.contains_key("gpxType"));
+        assert!(props(r#"{"gpxTypeKey": null}"#).is_empty());
+    }
+
+    #[test]
+    fn test_feature_collections_by_type() {
+        let xml = r#"<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"/><wpt lat="35.1" lon="139.1"/>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/></trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let by_type = to_feature_collections_by_type(&data, &ConvertOptions::default());
+        assert_eq!(by_type.waypoints.features.len(), 2);
+        assert!(by_type.routes.features.is_empty());
+        assert_eq!(by_type.tracks.features.len(), 1);
+
+        let opts: ConvertOptions = serde_json::from_str(r#"{"types": ["track"]}"#).unwrap();
+        let by_type = to_feature_collections_by_type(&data, &opts);
+        assert!(by_type.waypoints.features.is_empty());
+        assert_eq!(by_type.tracks.features.len(), 1);
+    }
+
     #[test]
     fn test_single_point_track() {
         let xml = r#"<?xml version="1.0"?>
@@ -507,6 +2068,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_elevation_mode() {
+        let xml = r#"<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><ele>10</ele><time>2024-01-01T00:00:00Z</time></trkpt>
+    <trkpt lat="35.1" lon="139.1"><time>2024-01-01T00:01:00Z</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let convert = |mode: &str| {
+            let opts: ConvertOptions =
+                serde_json::from_str(&format!(r#"{{"elevationMode": "{mode}"}}"#)).unwrap();
+            let feature = to_feature_collection(&data, &opts).features.remove(0);
+            let Some(Value::LineString(line)) = feature.geometry.map(|g| g.value) else {
+                panic!("expected a LineString");
+            };
+            let props = feature.properties.unwrap();
+            (
+                line[0].len(),
+                props["coordinateProperties"].get("elevations").cloned(),
+            )
+        };
+
+        assert_eq!(convert("coordinate"), (3, None));
+        let elevations = serde_json::json!([10.0, null]);
+        assert_eq!(convert("property"), (2, Some(elevations.clone())));
+        assert_eq!(convert("both"), (3, Some(elevations)));
+    }
+
     #[test]
     fn test_type_filter() {
         let xml = r#"<?xml version="1.0"?>
@@ -526,4 +2116,1208 @@ mod tests {
         let props = fc.features[0].properties.as_ref().unwrap();
         assert_eq!(props["gpxType"], "waypoint");
     }
+
+    #[test]
+    fn test_section_between_waypoints() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="0.0" lon="0.011"><name>Hut A</name></wpt>
+  <wpt lat="0.0" lon="0.029"><name>Summit</name></wpt>
+  <trk><name>Climb</name><trkseg>
+    <trkpt lat="0.0" lon="0.0"/>
+    <trkpt lat="0.0" lon="0.01"/>
+    <trkpt lat="0.0" lon="0.02"/>
+    <trkpt lat="0.0" lon="0.03"/>
+    <trkpt lat="0.0" lon="0.04"/>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts = ConvertOptions {
+            section: Some(SectionOptions {
+                from: "Hut A".to_string(),
+                to: "Summit".to_string(),
+            }),
+            ..Default::default()
+        };
+        let fc = to_feature_collection(&data, &opts);
+
+        // 2 waypoints + 1 track + 1 section
+        assert_eq!(fc.features.len(), 4);
+        let section = &fc.features[3];
+        let props = section.properties.as_ref().unwrap();
+        assert_eq!(props["gpxType"], "section");
+        assert_eq!(props["name"], "Climb");
+        assert_eq!(props["from"], "Hut A");
+        let distance = props["distance"].as_f64().unwrap();
+        assert!((distance - 2223.9).abs() < 1.0, "got {distance}");
+
+        match &section.geometry.as_ref().unwrap().value {
+            Value::LineString(coords) => {
+                assert_eq!(coords.len(), 3);
+                assert!((coords[0][0] - 0.01).abs() < 1e-10);
+                assert!((coords[2][0] - 0.03).abs() < 1e-10);
+            }
+            _ => panic!("Expected LineString"),
+        }
+        assert!(!props.contains_key("duration"));
+        assert!(!props.contains_key("maxDistanceFromStart"));
+    }
+
+    #[test]
+    fn test_section_stats() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="0.0" lon="0.01"><name>Hut A</name></wpt>
+  <wpt lat="0.0" lon="0.03"><name>Summit</name></wpt>
+  <trk><trkseg>
+    <trkpt lat="0.0" lon="0.0"><ele>50</ele><time>2025-01-01T08:00:00Z</time></trkpt>
+    <trkpt lat="0.0" lon="0.01"><ele>100</ele><time>2025-01-01T08:10:00Z</time></trkpt>
+    <trkpt lat="0.0" lon="0.02"><ele>150</ele><time>2025-01-01T08:25:00Z</time></trkpt>
+    <trkpt lat="0.0" lon="0.03"><ele>300</ele><time>2025-01-01T08:45:00Z</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions = serde_json::from_str(
+            r#"{"section": {"from": "Hut A", "to": "Summit"}, "includeStats": true}"#,
+        )
+        .unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        let section = fc.features.last().unwrap().properties.as_ref().unwrap();
+        assert_eq!(section["gpxType"], "section");
+        assert_eq!(section["duration"], 2100.0);
+        // Statistics cover the section only, not the whole track
+        assert_eq!(section["minElevation"], 100.0);
+        assert_eq!(section["maxElevation"], 300.0);
+        let max = section["maxDistanceFromStart"].as_f64().unwrap();
+        assert!((max - 2223.9).abs() < 1.0, "got {max}");
+        assert!(section.contains_key("boundingCircle"));
+        assert!(section.contains_key("maxGradient"));
+    }
+
+    #[test]
+    fn test_fix_quality_coordinate_properties() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><fix>3d</fix><sat>8</sat><hdop>0.9</hdop><pdop>1.4</pdop></trkpt>
+    <trkpt lat="35.001" lon="139.001"><sat>4</sat></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts = ConvertOptions {
+            include_fix_quality: true,
+            ..Default::default()
+        };
+        let fc = to_feature_collection(&data, &opts);
+
+        let props = fc.features[0].properties.as_ref().unwrap();
+        let coord_props = props["coordinateProperties"].as_object().unwrap();
+        assert_eq!(coord_props["sats"], serde_json::json!([8, 4]));
+        assert_eq!(coord_props["hdops"], serde_json::json!([0.9, null]));
+        assert_eq!(coord_props["pdops"], serde_json::json!([1.4, null]));
+        assert_eq!(coord_props["fixes"], serde_json::json!(["3d", null]));
+        assert!(!coord_props.contains_key("vdops"));
+        assert!(!coord_props.contains_key("times"));
+
+        // Off by default
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert!(!props.contains_key("coordinateProperties"));
+    }
+
+    #[test]
+    fn test_route_multi_point_mode() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <rte>
+    <name>Stops</name>
+    <rtept lat="35.0" lon="139.0"><name>A</name><sym>Flag</sym></rtept>
+    <rtept lat="35.1" lon="139.1"><name>B</name><desc>Lunch</desc></rtept>
+  </rte>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts = ConvertOptions {
+            route_mode: RouteMode::MultiPoint,
+            ..Default::default()
+        };
+        let fc = to_feature_collection(&data, &opts);
+
+        assert_eq!(fc.features.len(), 1);
+        let f = &fc.features[0];
+        match &f.geometry.as_ref().unwrap().value {
+            Value::MultiPoint(points) => assert_eq!(points.len(), 2),
+            _ => panic!("Expected MultiPoint"),
+        }
+        let props = f.properties.as_ref().unwrap();
+        assert_eq!(props["name"], "Stops");
+        let coord_props = props["coordinateProperties"].as_object().unwrap();
+        assert_eq!(coord_props["names"], serde_json::json!(["A", "B"]));
+        assert_eq!(coord_props["syms"], serde_json::json!(["Flag", null]));
+        assert_eq!(coord_props["descs"], serde_json::json!([null, "Lunch"]));
+    }
+
+    #[test]
+    fn test_extra_point_elements() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><fix>3d</fix><odometer>1.5</odometer><customtag>a</customtag></trkpt>
+    <trkpt lat="35.001" lon="139.001"><fix>2d</fix></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let opts = ConvertOptions {
+            extra_point_elements: vec!["odometer".into(), "fix".into(), "customtag".into()],
+            ..Default::default()
+        };
+        let data = crate::parser::parse_gpx_with_options(xml, &opts).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        let props = fc.features[0].properties.as_ref().unwrap();
+        let coord_props = props["coordinateProperties"].as_object().unwrap();
+        assert_eq!(coord_props["odometer"], serde_json::json!([1.5, null]));
+        assert_eq!(coord_props["fix"], serde_json::json!(["3d", "2d"]));
+        assert_eq!(coord_props["customtag"], serde_json::json!(["a", null]));
+    }
+
+    #[test]
+    fn test_metadata_foreign_member() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <metadata>
+    <name>Weekend Hike</name>
+    <time>2025-01-01T00:00:00Z</time>
+    <keywords>hiking, tokyo</keywords>
+    <bounds minlat="35.0" minlon="139.0" maxlat="36.0" maxlon="140.0"/>
+  </metadata>
+  <wpt lat="35.0" lon="139.0"/>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+
+        let metadata = &fc.foreign_members.as_ref().unwrap()["gpx_metadata"];
+        assert_eq!(metadata["name"], "Weekend Hike");
+        assert_eq!(metadata["time"], "2025-01-01T00:00:00Z");
+        assert_eq!(metadata["keywords"], "hiking, tokyo");
+        assert_eq!(metadata["bounds"]["maxlon"], 140.0);
+        assert!(metadata.get("desc").is_none());
+
+        let opts = ConvertOptions {
+            include_metadata: false,
+            ..Default::default()
+        };
+        assert!(
+            to_feature_collection(&data, &opts)
+                .foreign_members
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_summary_foreign_member() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="0.5" lon="0.0"><time>2025-01-01T05:00:00Z</time></wpt>
+  <trk><trkseg>
+    <trkpt lat="0.0" lon="0.0"><time>2025-01-01T06:00:00Z</time></trkpt>
+    <trkpt lat="0.0" lon="0.01"><time>2025-01-01T06:10:00Z</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts = ConvertOptions {
+            include_summary: true,
+            ..Default::default()
+        };
+        let fc = to_feature_collection(&data, &opts);
+
+        let summary = &fc.foreign_members.as_ref().unwrap()["summary"];
+        let distance = summary["totalDistance"].as_f64().unwrap();
+        assert!((distance - 1111.95).abs() < 0.1, "got {distance}");
+        assert_eq!(summary["duration"], 600.0);
+        assert_eq!(
+            summary["featureCounts"],
+            serde_json::json!({"waypoint": 1, "track": 1})
+        );
+        assert_eq!(summary["bbox"], serde_json::json!([0.0, 0.0, 0.01, 0.5]));
+        assert_eq!(
+            summary["timeRange"],
+            serde_json::json!(["2025-01-01T05:00:00Z", "2025-01-01T06:10:00Z"])
+        );
+
+        // Off by default
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        assert!(fc.foreign_members.is_none());
+    }
+
+    #[test]
+    fn test_summary_counts_route_times() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <rte>
+    <rtept lat="0.0" lon="0.0"><time>2025-01-01T08:00:00Z</time></rtept>
+    <rtept lat="0.0" lon="0.01"><time>2025-01-01T08:30:00Z</time></rtept>
+  </rte>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts = ConvertOptions {
+            include_summary: true,
+            ..Default::default()
+        };
+        let fc = to_feature_collection(&data, &opts);
+
+        // Planned-route ETAs count towards duration just like track times
+        let summary = &fc.foreign_members.as_ref().unwrap()["summary"];
+        assert_eq!(summary["duration"], 1800.0);
+        assert_eq!(summary["featureCounts"], serde_json::json!({"route": 1}));
+    }
+
+    #[test]
+    fn test_split_activities() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><name>All day</name>
+    <trkseg>
+      <trkpt lat="0.0" lon="0.0"><time>2025-01-01T08:00:00Z</time></trkpt>
+      <trkpt lat="0.0" lon="0.01"><time>2025-01-01T08:20:00Z</time></trkpt>
+    </trkseg>
+    <trkseg>
+      <trkpt lat="0.0" lon="0.01"><time>2025-01-01T12:00:00Z</time></trkpt>
+      <trkpt lat="0.0" lon="0.02"><time>2025-01-01T12:15:00Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts = ConvertOptions {
+            split_activities: Some(crate::options::ActivitySplitOptions::default()),
+            ..Default::default()
+        };
+        let fc = to_feature_collection(&data, &opts);
+
+        assert_eq!(fc.features.len(), 2);
+        let first = fc.features[0].properties.as_ref().unwrap();
+        let second = fc.features[1].properties.as_ref().unwrap();
+        assert_eq!(first["activity"], 1);
+        assert_eq!(first["duration"], 1200.0);
+        assert_eq!(second["activity"], 2);
+        assert_eq!(second["duration"], 900.0);
+        assert_eq!(second["name"], "All day");
+
+        // Routes with ETAs are split too, numbered before the tracks
+        let xml = r#"<gpx version="1.1">
+  <rte><name>Two days</name>
+    <rtept lat="0.0" lon="0.0"><time>2025-01-01T08:00:00Z</time></rtept>
+    <rtept lat="0.0" lon="0.01"><time>2025-01-01T08:20:00Z</time></rtept>
+    <rtept lat="0.0" lon="0.02"><time>2025-01-02T08:00:00Z</time></rtept>
+  </rte>
+  <trk><trkseg>
+    <trkpt lat="0.0" lon="0.0"><time>2025-01-03T08:00:00Z</time></trkpt>
+    <trkpt lat="0.0" lon="0.01"><time>2025-01-03T08:20:00Z</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        let props: Vec<_> = fc
+            .features
+            .iter()
+            .map(|f| f.properties.as_ref().unwrap())
+            .collect();
+        assert_eq!(props.len(), 3);
+        assert_eq!(props[0]["gpxType"], "route");
+        assert_eq!(props[0]["activity"], 1);
+        assert_eq!(props[0]["duration"], 1200.0);
+        assert_eq!(props[0]["name"], "Two days");
+        // A lone point after the gap becomes a Point route feature
+        assert_eq!(props[1]["gpxType"], "route");
+        assert_eq!(props[1]["activity"], 2);
+        assert_eq!(props[2]["gpxType"], "track");
+        assert_eq!(props[2]["activity"], 3);
+    }
+
+    #[test]
+    fn test_include_extensions() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:gpxx="http://www.garmin.com/xmlschemas/GpxExtensions/v3">
+  <wpt lat="35.0" lon="139.0">
+    <extensions>
+      <gpxx:WaypointExtension>
+        <gpxx:Proximity>25</gpxx:Proximity>
+        <gpxx:Categories>
+          <gpxx:Category>Food</gpxx:Category>
+          <gpxx:Category>Parking</gpxx:Category>
+        </gpxx:Categories>
+      </gpxx:WaypointExtension>
+      <vendor:note xmlns:vendor="urn:vendor" lang="en">Open late</vendor:note>
+    </extensions>
+  </wpt>
+  <trk>
+    <extensions><gpxx:TrackExtension><gpxx:DisplayColor>Red</gpxx:DisplayColor></gpxx:TrackExtension></extensions>
+    <trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/></trkseg>
+  </trk>
+</gpx>"#;
+        let opts = ConvertOptions {
+            include_extensions: true,
+            ..Default::default()
+        };
+        let data = crate::parser::parse_gpx_with_options(xml, &opts).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        let wpt = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(
+            wpt["extensions"],
+            serde_json::json!({
+                "WaypointExtension": {
+                    "Proximity": "25",
+                    "Categories": {"Category": ["Food", "Parking"]}
+                },
+                "note": {"@lang": "en", "#text": "Open late"}
+            })
+        );
+        let trk = fc.features[1].properties.as_ref().unwrap();
+        assert_eq!(trk["extensions"]["TrackExtension"]["DisplayColor"], "Red");
+
+        // Off by default
+        let data = parse_gpx(xml).unwrap();
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        assert!(
+            !fc.features[0]
+                .properties
+                .as_ref()
+                .unwrap()
+                .contains_key("extensions")
+        );
+    }
+
+    #[test]
+    fn test_display_color_stroke() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:gpxx="http://www.garmin.com/xmlschemas/GpxExtensions/v3">
+  <rte>
+    <extensions><gpxx:RouteExtension><gpxx:DisplayColor>Magenta</gpxx:DisplayColor></gpxx:RouteExtension></extensions>
+    <rtept lat="35.0" lon="139.0"/><rtept lat="35.1" lon="139.1"/>
+  </rte>
+  <trk>
+    <extensions><gpxx:TrackExtension><gpxx:DisplayColor>DarkBlue</gpxx:DisplayColor></gpxx:TrackExtension></extensions>
+    <trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/></trkseg>
+  </trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+
+        assert_eq!(
+            fc.features[0].properties.as_ref().unwrap()["stroke"],
+            "#ff00ff"
+        );
+        let trk = fc.features[1].properties.as_ref().unwrap();
+        assert_eq!(trk["stroke"], "#00008b");
+        assert!(!trk.contains_key("extensions"));
+    }
+
+    #[test]
+    fn test_auto_color_option() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/></trkseg></trk>
+  <trk><trkseg><trkpt lat="36.0" lon="139.0"/><trkpt lat="36.1" lon="139.1"/></trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions = serde_json::from_str(r#"{"autoColor": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        assert_eq!(
+            fc.features[0].properties.as_ref().unwrap()["stroke"],
+            style::DEFAULT_PALETTE[0]
+        );
+        assert_eq!(
+            fc.features[1].properties.as_ref().unwrap()["stroke"],
+            style::DEFAULT_PALETTE[1]
+        );
+
+        let opts: ConvertOptions = serde_json::from_str(r##"{"autoColor": ["#abcdef"]}"##).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        assert_eq!(
+            fc.features[1].properties.as_ref().unwrap()["stroke"],
+            "#abcdef"
+        );
+    }
+
+    #[test]
+    fn test_garmin_waypoint_properties() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:gpxx="http://www.garmin.com/xmlschemas/GpxExtensions/v3">
+  <wpt lat="35.0" lon="139.0">
+    <extensions>
+      <gpxx:WaypointExtension>
+        <gpxx:Proximity>150</gpxx:Proximity>
+        <gpxx:Categories><gpxx:Category>Lodging</gpxx:Category></gpxx:Categories>
+        <gpxx:Address><gpxx:City>Minato</gpxx:City></gpxx:Address>
+        <gpxx:PhoneNumber>+81-3-0000-0000</gpxx:PhoneNumber>
+      </gpxx:WaypointExtension>
+    </extensions>
+  </wpt>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(props["address"], serde_json::json!({ "city": "Minato" }));
+        assert_eq!(props["phone"], "+81-3-0000-0000");
+        assert_eq!(props["categories"], serde_json::json!(["Lodging"]));
+        assert_eq!(props["proximity"], 150.0);
+        assert!(!props.contains_key("depth"));
+    }
+
+    #[test]
+    fn test_power_coordinate_property() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><extensions><power>215</power></extensions></trkpt>
+    <trkpt lat="35.1" lon="139.1"/>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(
+            props["coordinateProperties"]["powers"],
+            serde_json::json!([215, null])
+        );
+    }
+
+    #[test]
+    fn test_segment_extensions() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/>
+    <extensions><lap>1</lap></extensions>
+  </trkseg><trkseg>
+    <trkpt lat="36.0" lon="139.0"/><trkpt lat="36.1" lon="139.1"/>
+  </trkseg></trk>
+</gpx>"#;
+        let opts = ConvertOptions {
+            include_extensions: true,
+            ..Default::default()
+        };
+        let data = crate::parser::parse_gpx_with_options(xml, &opts).unwrap();
+
+        let fc = to_feature_collection(&data, &opts);
+        assert_eq!(
+            fc.features[0].properties.as_ref().unwrap()["segmentExtensions"],
+            serde_json::json!({ "lap": "1" })
+        );
+        assert!(
+            !fc.features[1]
+                .properties
+                .as_ref()
+                .unwrap()
+                .contains_key("segmentExtensions")
+        );
+
+        let joined = ConvertOptions {
+            join_track_segments: true,
+            ..opts
+        };
+        let fc = to_feature_collection(&data, &joined);
+        assert_eq!(
+            fc.features[0].properties.as_ref().unwrap()["segmentExtensions"],
+            serde_json::json!([{ "lap": "1" }, null])
+        );
+    }
+
+    #[test]
+    fn test_expand_route_shape_points() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:gpxx="http://www.garmin.com/xmlschemas/GpxExtensions/v3">
+  <rte>
+    <rtept lat="35.0" lon="139.0">
+      <name>Start</name>
+      <extensions><gpxx:RoutePointExtension>
+        <gpxx:rpt lat="35.05" lon="139.02"/>
+      </gpxx:RoutePointExtension></extensions>
+    </rtept>
+    <rtept lat="35.1" lon="139.1"><name>End</name></rtept>
+  </rte>
+</gpx>"#;
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"expandRouteShapePoints": true}"#).unwrap();
+        let data = crate::parser::parse_gpx_with_options(xml, &opts).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        match &fc.features[0].geometry.as_ref().unwrap().value {
+            Value::LineString(coords) => assert_eq!(
+                coords,
+                &vec![vec![139.0, 35.0], vec![139.02, 35.05], vec![139.1, 35.1]]
+            ),
+            _ => panic!("Expected LineString"),
+        }
+
+        let fc = to_feature_collection(&parse_gpx(xml).unwrap(), &ConvertOptions::default());
+        match &fc.features[0].geometry.as_ref().unwrap().value {
+            Value::LineString(coords) => assert_eq!(coords.len(), 2),
+            _ => panic!("Expected LineString"),
+        }
+    }
+
+    #[test]
+    fn test_geometry_hash() {
+        let xml = |lon: &str| {
+            format!(
+                r#"<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><name>A</name></wpt>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="{lon}"/></trkseg></trk>
+</gpx>"#
+            )
+        };
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"includeGeometryHash": true}"#).unwrap();
+        let hashes = |lon: &str| -> Vec<JsonValue> {
+            let data = parse_gpx(&xml(lon)).unwrap();
+            to_feature_collection(&data, &opts)
+                .features
+                .iter()
+                .map(|f| f.properties.as_ref().unwrap()["geometryHash"].clone())
+                .collect()
+        };
+
+        let before = hashes("139.1");
+        assert_eq!(before[0].as_str().unwrap().len(), 16);
+        assert_eq!(before, hashes("139.1"));
+        // Differences below the hashing precision are ignored
+        assert_eq!(before, hashes("139.100000001"));
+
+        let after = hashes("139.2");
+        assert_eq!(before[0], after[0]);
+        assert_ne!(before[1], after[1]);
+    }
+
+    #[test]
+    fn test_feature_id() {
+        let xml = r#"<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><name>A</name></wpt>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/></trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let ids = |mode: &str| -> Vec<Option<Id>> {
+            let opts: ConvertOptions =
+                serde_json::from_str(&format!(r#"{{"featureId": "{mode}"}}"#)).unwrap();
+            to_feature_collection(&data, &opts)
+                .features
+                .into_iter()
+                .map(|f| f.id)
+                .collect()
+        };
+
+        assert_eq!(ids("none"), vec![None, None]);
+        assert_eq!(
+            ids("index"),
+            vec![Some(Id::Number(0.into())), Some(Id::Number(1.into()))]
+        );
+        assert_eq!(ids("name"), vec![Some(Id::String("A".to_string())), None]);
+        let hashes = ids("hash");
+        assert!(matches!(&hashes[1], Some(Id::String(hash)) if hash.len() == 16));
+    }
+
+    #[test]
+    fn test_extension_mappings() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:acme="https://example.com/acme" xmlns:other="https://example.com/other">
+  <wpt lat="35.0" lon="139.0">
+    <extensions><acme:rating>4.5</acme:rating></extensions>
+  </wpt>
+  <trk>
+    <extensions><acme:device>Edge 1040</acme:device></extensions>
+    <trkseg>
+      <trkpt lat="35.0" lon="139.0">
+        <extensions><acme:sensors><acme:smo2>61</acme:smo2></acme:sensors></extensions>
+      </trkpt>
+      <trkpt lat="35.1" lon="139.1">
+        <extensions><other:smo2>99</other:smo2></extensions>
+      </trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+        let opts: ConvertOptions = serde_json::from_str(
+            r#"{"extensionMappings": {
+                "acme:smo2": "muscleOxygen",
+                "acme:device": "device",
+                "rating": "rating"
+            }}"#,
+        )
+        .unwrap();
+        let data = crate::parser::parse_gpx_with_options(xml, &opts).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        let wpt = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(wpt["rating"], 4.5);
+        let trk = fc.features[1].properties.as_ref().unwrap();
+        assert_eq!(trk["device"], "Edge 1040");
+        assert_eq!(
+            trk["coordinateProperties"]["muscleOxygen"],
+            serde_json::json!([61.0, null])
+        );
+        assert!(
+            !trk["coordinateProperties"]
+                .as_object()
+                .unwrap()
+                .contains_key("rating")
+        );
+    }
+
+    #[test]
+    fn test_auto_extensions() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <rte>
+    <rtept lat="35.0" lon="139.0"><extensions><acme:grade>3.5</acme:grade></extensions></rtept>
+    <rtept lat="35.1" lon="139.1"/>
+  </rte>
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0">
+      <extensions><gpxtpx:hr>120</gpxtpx:hr><moxy:smo2>61</moxy:smo2></extensions>
+    </trkpt>
+    <trkpt lat="35.1" lon="139.1">
+      <extensions><gpxtpx:hr>125</gpxtpx:hr><radar:range>42.5</radar:range></extensions>
+    </trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let opts: ConvertOptions = serde_json::from_str(r#"{"autoExtensions": true}"#).unwrap();
+        let data = crate::parser::parse_gpx_with_options(xml, &opts).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        let rte = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(
+            rte["coordinateProperties"]["grade"],
+            serde_json::json!([3.5, null])
+        );
+        let cp = &fc.features[1].properties.as_ref().unwrap()["coordinateProperties"];
+        assert_eq!(cp["heartRates"], serde_json::json!([120, 125]));
+        assert_eq!(cp["smo2"], serde_json::json!([61.0, null]));
+        assert_eq!(cp["range"], serde_json::json!([null, 42.5]));
+        assert!(!cp.as_object().unwrap().contains_key("hr"));
+
+        let fc = to_feature_collection(&parse_gpx(xml).unwrap(), &ConvertOptions::default());
+        let cp = &fc.features[1].properties.as_ref().unwrap()["coordinateProperties"];
+        assert!(!cp.as_object().unwrap().contains_key("smo2"));
+    }
+
+    #[test]
+    fn test_waypoints_as_multi_point() {
+        let xml = r#"<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><name>A</name><time>2024-01-01T00:00:00Z</time></wpt>
+  <wpt lat="35.1" lon="139.1"><sym>Flag</sym></wpt>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"waypointsAsMultiPoint": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        assert_eq!(fc.features.len(), 1);
+        let feature = &fc.features[0];
+        assert_eq!(
+            feature.geometry.as_ref().unwrap().value,
+            Value::MultiPoint(vec![vec![139.0, 35.0], vec![139.1, 35.1]])
+        );
+        let props = feature.properties.as_ref().unwrap();
+        assert_eq!(props["gpxType"], "waypoint");
+        let coord_props = &props["coordinateProperties"];
+        assert_eq!(coord_props["names"], serde_json::json!(["A", null]));
+        assert_eq!(coord_props["syms"], serde_json::json!([null, "Flag"]));
+        assert_eq!(
+            coord_props["times"],
+            serde_json::json!(["2024-01-01T00:00:00Z", null])
+        );
+    }
+
+    #[test]
+    fn test_summary_multi_point_distance() {
+        let xml = r#"<gpx version="1.1">
+  <wpt lat="0.0" lon="0.0"/>
+  <wpt lat="0.0" lon="1.0"/>
+  <wpt lat="1.0" lon="1.0"/>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"waypointsAsMultiPoint": true, "includeSummary": true}"#)
+                .unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        // The waypoints aren't a path, so they only add to the bounds
+        let summary = &fc.foreign_members.as_ref().unwrap()["summary"];
+        assert_eq!(summary["totalDistance"], 0.0);
+        assert_eq!(summary["bbox"], serde_json::json!([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(summary["featureCounts"], serde_json::json!({"waypoint": 1}));
+    }
+
+    #[test]
+    fn test_output_points() {
+        let xml = r#"<gpx version="1.1" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+  <rte><name>R</name><rtept lat="35.0" lon="139.0"/></rte>
+  <trk><name>Run</name>
+    <trkseg><trkpt lat="35.0" lon="139.0"><ele>10</ele><time>2024-01-01T00:00:00Z</time>
+      <extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>120</gpxtpx:hr></gpxtpx:TrackPointExtension></extensions>
+    </trkpt></trkseg>
+    <trkseg><trkpt lat="35.1" lon="139.1"/><trkpt lat="35.2" lon="139.2"/></trkseg>
+  </trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions = serde_json::from_str(r#"{"output": "points"}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        assert_eq!(fc.features.len(), 4);
+        assert!(
+            fc.features
+                .iter()
+                .all(|f| matches!(f.geometry.as_ref().unwrap().value, Value::Point(_)))
+        );
+        let rtept = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(rtept["gpxType"], "routePoint");
+        assert_eq!(rtept["route"], "R");
+        let trkpt = fc.features[1].properties.as_ref().unwrap();
+        assert_eq!(trkpt["gpxType"], "trackPoint");
+        assert_eq!(trkpt["track"], "Run");
+        assert_eq!(trkpt["time"], "2024-01-01T00:00:00Z");
+        assert_eq!(trkpt["ele"], 10.0);
+        assert_eq!(trkpt["heartRate"], 120);
+        assert!(!trkpt.contains_key("coordinateProperties"));
+    }
+
+    #[test]
+    fn test_sensor_naming() {
+        let xml = r#"<gpx version="1.1" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><extensions><gpxtpx:TrackPointExtension>
+      <gpxtpx:hr>120</gpxtpx:hr><gpxtpx:cad>85</gpxtpx:cad><gpxtpx:atemp>21.5</gpxtpx:atemp>
+    </gpxtpx:TrackPointExtension></extensions></trkpt>
+    <trkpt lat="35.1" lon="139.1"/>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let keys = |naming: &str| -> Vec<String> {
+            let opts: ConvertOptions =
+                serde_json::from_str(&format!(r#"{{"sensorNaming": "{naming}"}}"#)).unwrap();
+            let fc = to_feature_collection(&data, &opts);
+            let props = fc.features[0].properties.as_ref().unwrap();
+            props["coordinateProperties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect()
+        };
+
+        assert_eq!(keys("default"), ["cadences", "heartRates", "temperatures"]);
+        assert_eq!(keys("togeojson"), ["atemps", "cads", "heartRates"]);
+    }
+
+    #[test]
+    fn test_include_stats() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk>
+    <trkseg><trkpt lat="35.00" lon="139.0"/><trkpt lat="35.01" lon="139.0"/></trkseg>
+    <trkseg><trkpt lat="35.02" lon="139.0"/><trkpt lat="35.00" lon="139.0"/></trkseg>
+  </trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"includeStats": true, "joinTrackSegments": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        let props = fc.features[0].properties.as_ref().unwrap();
+        let max = props["maxDistanceFromStart"].as_f64().unwrap();
+        assert!((max - 2224.0).abs() < 1.0, "got {max}");
+        let center_lat = props["boundingCircle"]["center"][1].as_f64().unwrap();
+        assert!((center_lat - 35.01).abs() < 1e-9);
+
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert!(!props.contains_key("boundingCircle"));
+
+        // Routes get the same statistics
+        let xml = r#"<gpx version="1.1">
+  <rte>
+    <rtept lat="35.00" lon="139.0"><ele>100</ele></rtept>
+    <rtept lat="35.01" lon="139.0"><ele>250</ele></rtept>
+  </rte>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions = serde_json::from_str(r#"{"includeStats": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(props["gpxType"], "route");
+        let max = props["maxDistanceFromStart"].as_f64().unwrap();
+        assert!((max - 1112.0).abs() < 1.0, "got {max}");
+        assert_eq!(props["maxElevation"], 250.0);
+        assert!(!props.contains_key("duration"));
+
+        // Route ETAs give a duration and average speed, as track times do
+        let xml = r#"<gpx version="1.1">
+  <rte>
+    <rtept lat="35.00" lon="139.0"><time>2025-01-01T08:00:00Z</time></rtept>
+    <rtept lat="35.01" lon="139.0"><time>2025-01-01T08:10:00Z</time></rtept>
+  </rte>
+  <trk><trkseg>
+    <trkpt lat="35.00" lon="139.0"><time>2025-01-01T09:00:00Z</time></trkpt>
+    <trkpt lat="35.01" lon="139.0"><time>2025-01-01T09:10:00Z</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        for feature in &fc.features {
+            let props = feature.properties.as_ref().unwrap();
+            assert_eq!(props["duration"], 600.0);
+            let speed = props["averageSpeed"].as_f64().unwrap();
+            assert!((speed - 1.853).abs() < 0.001, "got {speed}");
+        }
+    }
+
+    #[test]
+    fn test_detect_self_intersections() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><name>Eight</name><trkseg>
+    <trkpt lat="35.0" lon="139.0"/><trkpt lat="35.2" lon="139.2"/>
+    <trkpt lat="35.0" lon="139.2"/><trkpt lat="35.2" lon="139.0"/>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"detectSelfIntersections": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        assert_eq!(fc.features.len(), 2);
+        assert_eq!(
+            fc.features[0].properties.as_ref().unwrap()["selfIntersections"],
+            1
+        );
+        let crossing = &fc.features[1];
+        let props = crossing.properties.as_ref().unwrap();
+        assert_eq!(props["gpxType"], "selfIntersection");
+        assert_eq!(props["track"], "Eight");
+        let Some(Value::Point(coords)) = crossing.geometry.as_ref().map(|g| &g.value) else {
+            panic!("expected a Point");
+        };
+        assert!((coords[0] - 139.1).abs() < 1e-9 && (coords[1] - 35.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_include_source_index() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><name>Dropped</name><fix>2d</fix></wpt>
+  <wpt lat="35.1" lon="139.1"><name>Kept</name></wpt>
+  <rte><rtept lat="35.0" lon="139.0"/><rtept lat="35.1" lon="139.1"/></rte>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/></trkseg></trk>
+  <trk>
+    <trkseg><trkpt lat="35.0" lon="139.0"/></trkseg>
+    <trkseg><trkpt lat="35.2" lon="139.2"/><trkpt lat="35.3" lon="139.3"/></trkseg>
+  </trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"includeSourceIndex": true, "minFixQuality": "3d"}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        let indices: Vec<_> = fc
+            .features
+            .iter()
+            .map(|f| {
+                let props = f.properties.as_ref().unwrap();
+                let get = |key| props.get(key).and_then(JsonValue::as_u64);
+                (
+                    get("waypointIndex"),
+                    get("routeIndex"),
+                    get("trackIndex"),
+                    get("segmentIndex"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            indices,
+            vec![
+                (Some(1), None, None, None),
+                (None, Some(0), None, None),
+                (None, None, Some(0), None),
+                (None, None, Some(1), Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bbox_from_bounds() {
+        let gpx = |bounds: &str| {
+            format!(
+                r#"<gpx version="1.1"><metadata>{bounds}</metadata>
+  <trk><trkseg><trkpt lat="35.1" lon="139.1"/><trkpt lat="35.2" lon="139.3"/></trkseg></trk>
+</gpx>"#
+            )
+        };
+        let bbox = |xml: &str, source: &str| {
+            let data = parse_gpx(xml).unwrap();
+            let opts: ConvertOptions =
+                serde_json::from_str(&format!(r#"{{"bbox": "{source}"}}"#)).unwrap();
+            to_feature_collection(&data, &opts).bbox
+        };
+
+        let declared =
+            gpx(r#"<bounds minlat="35.0" minlon="139.0" maxlat="36.0" maxlon="140.0"/>"#);
+        assert_eq!(
+            bbox(&declared, "bounds"),
+            Some(vec![139.0, 35.0, 140.0, 36.0])
+        );
+        assert_eq!(
+            bbox(&declared, "checkedBounds"),
+            Some(vec![139.0, 35.0, 140.0, 36.0])
+        );
+        let data = parse_gpx(&declared).unwrap();
+        assert_eq!(
+            to_feature_collection(&data, &ConvertOptions::default()).bbox,
+            None
+        );
+
+        // Bounds that leave out the last point fall back to the computed extent
+        let wrong = gpx(r#"<bounds minlat="35.0" minlon="139.0" maxlat="36.0" maxlon="139.2"/>"#);
+        assert_eq!(bbox(&wrong, "bounds"), Some(vec![139.0, 35.0, 139.2, 36.0]));
+        assert_eq!(
+            bbox(&wrong, "checkedBounds"),
+            Some(vec![139.1, 35.1, 139.3, 35.2])
+        );
+
+        let undeclared = gpx("");
+        assert_eq!(bbox(&undeclared, "bounds"), None);
+        assert_eq!(
+            bbox(&undeclared, "checkedBounds"),
+            Some(vec![139.1, 35.1, 139.3, 35.2])
+        );
+    }
+
+    #[test]
+    fn test_include_diagnostics() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><fix>2d</fix></trkpt>
+    <trkpt lat="35.1" lon="139.1"><fix>3d</fix><name>&bogus;</name></trkpt>
+    <trkpt lat="35.2" lon="139.2"><fix>3d</fix><extensions><acme:x>1</acme:x></extensions></trkpt>
+    <trkpt lon="139.3"/>
+  </trkseg></trk>
+</gpx>"#;
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"includeDiagnostics": true, "minFixQuality": "3d"}"#).unwrap();
+        let data = crate::parser::parse_gpx_with_options(xml, &opts).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        let report = &fc.foreign_members.as_ref().unwrap()["diagnostics"]["droppedDataReport"];
+        assert_eq!(
+            report,
+            &serde_json::json!({
+                "skippedPoints": 1,
+                "filteredPoints": 1,
+                "ignoredElements": {},
+                "droppedExtensions": { "acme": 1 },
+                "unknownEntities": { "bogus": 1 },
+            })
+        );
+
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        assert!(fc.foreign_members.is_none());
+    }
+
+    #[test]
+    fn test_strip_html() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0">
+    <desc><![CDATA[<p>Hut with <b>water</b></p>]]></desc>
+    <cmt>&lt;i&gt;Closed in winter&lt;/i&gt;</cmt>
+  </wpt>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions = serde_json::from_str(r#"{"stripHtml": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(props["desc"], "Hut with water");
+        assert_eq!(props["cmt"], "Closed in winter");
+
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(props["desc"], "<p>Hut with <b>water</b></p>");
+    }
+
+    #[test]
+    fn test_description_mode_merged() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><cmt>Open daily</cmt><desc>Summit hut</desc></wpt>
+  <trk><desc>Ridge walk</desc><trkseg>
+    <trkpt lat="35.0" lon="139.0"/><trkpt lat="35.1" lon="139.1"/>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"descriptionMode": "merged"}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        let wpt = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(wpt["description"], "Summit hut\nOpen daily");
+        assert!(!wpt.contains_key("desc"));
+        assert!(!wpt.contains_key("cmt"));
+        let trk = fc.features[1].properties.as_ref().unwrap();
+        assert_eq!(trk["description"], "Ridge walk");
+
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        let wpt = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(wpt["desc"], "Summit hut");
+        assert_eq!(wpt["cmt"], "Open daily");
+        assert!(!wpt.contains_key("description"));
+    }
+
+    #[test]
+    fn test_strict_rfc7946() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <metadata><name>Crossing</name></metadata>
+  <trk><trkseg>
+    <trkpt lat="-17.0" lon="179.5"><time>2024-01-01T00:00:00Z</time></trkpt>
+    <trkpt lat="-17.0" lon="-179.5"><time>2024-01-01T01:00:00Z</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"strictRfc7946": true, "includeSummary": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        assert!(fc.foreign_members.is_none());
+        match &fc.features[0].geometry.as_ref().unwrap().value {
+            Value::MultiLineString(lines) => {
+                assert_eq!(lines[0].last().unwrap()[0], 180.0);
+                assert_eq!(lines[1][0][0], -180.0);
+            }
+            _ => panic!("Expected MultiLineString"),
+        }
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert!(!props.contains_key("coordinateProperties"));
+        assert_eq!(
+            props["times"],
+            serde_json::json!([
+                ["2024-01-01T00:00:00Z", null],
+                [null, "2024-01-01T01:00:00Z"]
+            ])
+        );
+    }
+
+    #[test]
+    fn test_split_antimeridian() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="-17.0" lon="179.5"/>
+    <trkpt lat="-17.0" lon="-179.5"/>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        assert!(matches!(
+            fc.features[0].geometry.as_ref().unwrap().value,
+            Value::LineString(_)
+        ));
+
+        let opts: ConvertOptions = serde_json::from_str(r#"{"splitAntimeridian": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+        match &fc.features[0].geometry.as_ref().unwrap().value {
+            Value::MultiLineString(lines) => {
+                assert_eq!(lines.len(), 2);
+                assert_eq!(lines[0].last().unwrap()[..2], [180.0, -17.0]);
+                assert_eq!(lines[1][0][..2], [-180.0, -17.0]);
+            }
+            _ => panic!("Expected MultiLineString"),
+        }
+    }
+
+    #[test]
+    fn test_include_source() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.0">
+  <wpt lat="35.0" lon="139.0"><name>Plain</name></wpt>
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><extensions><power>200</power></extensions></trkpt>
+    <trkpt lat="35.1" lon="139.1"/>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions = serde_json::from_str(r#"{"includeSource": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        let wpt = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(wpt["sourceVersion"], "1.0");
+        assert!(!wpt.contains_key("hadExtensions"));
+        let trk = fc.features[1].properties.as_ref().unwrap();
+        assert_eq!(trk["sourceVersion"], "1.0");
+        assert_eq!(trk["hadExtensions"], true);
+
+        let fc = to_feature_collection(&data, &ConvertOptions::default());
+        let trk = fc.features[1].properties.as_ref().unwrap();
+        assert!(!trk.contains_key("sourceVersion"));
+        assert!(!trk.contains_key("hadExtensions"));
+    }
+
+    #[test]
+    fn test_minimal_output() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <metadata><name>Walk</name></metadata>
+  <wpt lat="35.123456789" lon="139.987654321"><ele>10</ele><name>Start</name></wpt>
+  <trk><name>Walk</name><trkseg>
+    <trkpt lat="35.0" lon="139.0"><ele>10</ele><time>2024-01-01T00:00:00Z</time></trkpt>
+    <trkpt lat="35.000001" lon="139.005"><ele>11</ele><time>2024-01-01T00:01:00Z</time></trkpt>
+    <trkpt lat="35.0" lon="139.01"><ele>12</ele><time>2024-01-01T00:02:00Z</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"minimal": true, "includeSummary": true}"#).unwrap();
+        let fc = to_feature_collection(&data, &opts);
+
+        assert!(fc.foreign_members.is_none());
+        assert_eq!(fc.features.len(), 2);
+        assert!(fc.features.iter().all(|f| f.properties.is_none()));
+        match &fc.features[0].geometry.as_ref().unwrap().value {
+            Value::Point(coords) => assert_eq!(coords, &vec![139.98765, 35.12346]),
+            _ => panic!("Expected Point"),
+        }
+        match &fc.features[1].geometry.as_ref().unwrap().value {
+            Value::LineString(coords) => {
+                assert_eq!(coords, &vec![vec![139.0, 35.0], vec![139.01, 35.0]])
+            }
+            _ => panic!("Expected LineString"),
+        }
+    }
+
+    #[test]
+    fn test_crop_distance() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="0.0" lon="0.0"/>
+    <trkpt lat="0.0" lon="0.01"/>
+    <trkpt lat="0.0" lon="0.02"/>
+    <trkpt lat="0.0" lon="0.03"/>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let opts = ConvertOptions {
+            crop_distance: Some(crate::options::CropDistance {
+                start_meters: 500.0,
+                end_meters: 2000.0,
+            }),
+            ..Default::default()
+        };
+        let fc = to_feature_collection(&data, &opts);
+
+        let geom = fc.features[0].geometry.as_ref().unwrap();
+        match &geom.value {
+            Value::LineString(coords) => {
+                // interpolated start, 0.01, interpolated end
+                assert_eq!(coords.len(), 3);
+                assert!((coords[0][0] - 0.0045).abs() < 1e-4);
+                assert!((coords[1][0] - 0.01).abs() < 1e-10);
+                assert!((coords[2][0] - 0.018).abs() < 1e-4);
+            }
+            _ => panic!("Expected LineString"),
+        }
+    }
 }
@@ -0,0 +1,285 @@
+/// Parse an xsd:dateTime / ISO 8601 timestamp into seconds since the Unix epoch.
+///
+/// Accepts `YYYY-MM-DDThh:mm:ss[.fff][Z|±hh:mm]`. Timestamps without a zone
+/// designator are taken as UTC, which is what GPX prescribes.
+pub fn parse_timestamp(s: &str) -> Option<f64> {
+    parse_timestamp_with_offset(s).map(|(secs, _)| secs)
+}
+
+/// Like [`parse_timestamp`], also returning the zone offset the timestamp
+/// was written with, in seconds east of UTC.
+pub fn parse_timestamp_with_offset(s: &str) -> Option<(f64, i64)> {
+    let s = s.trim();
+    let b = s.as_bytes();
+    if b.len() < 19
+        || !b[..19].is_ascii()
+        || b[4] != b'-'
+        || b[7] != b'-'
+        || !matches!(b[10], b'T' | b't' | b' ')
+    {
+        return None;
+    }
+    if b[13] != b':' || b[16] != b':' {
+        return None;
+    }
+
+    let year: i64 = digits(&s[0..4])?;
+    let month: u32 = digits(&s[5..7])?;
+    let day: u32 = digits(&s[8..10])?;
+    let hour: u32 = digits(&s[11..13])?;
+    let minute: u32 = digits(&s[14..16])?;
+    let second: u32 = digits(&s[17..19])?;
+    if !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &s[19..];
+    let mut fraction = 0.0;
+    if let Some(frac) = rest.strip_prefix('.') {
+        let len = frac.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 {
+            return None;
+        }
+        fraction = format!("0.{}", &frac[..len]).parse().ok()?;
+        rest = &frac[len..];
+    }
+
+    let offset_secs = parse_offset(rest)?;
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86_400 + i64::from(hour * 3600 + minute * 60 + second) - offset_secs;
+    Some((secs as f64 + fraction, offset_secs))
+}
+
+/// Parse a zone designator (`""`, `Z`, `+09:00`, `-0530`) into an offset in seconds.
+pub fn parse_offset(zone: &str) -> Option<i64> {
+    match zone {
+        "" | "Z" | "z" => Some(0),
+        _ => {
+            let sign = match zone.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let body = &zone[1..];
+            if !body.is_ascii() {
+                return None;
+            }
+            let (h, m) = match body.len() {
+                5 if body.as_bytes()[2] == b':' => (&body[0..2], &body[3..5]),
+                4 => (&body[0..2], &body[2..4]),
+                2 => (body, "00"),
+                _ => return None,
+            };
+            let (h, m): (i64, i64) = (digits(h)?, digits(m)?);
+            if h > 23 || m > 59 {
+                return None;
+            }
+            Some(sign * (h * 3600 + m * 60))
+        }
+    }
+}
+
+/// Offsets of IANA zones that don't observe daylight saving time, so a
+/// fixed offset converts every timestamp correctly.
+const FIXED_ZONES: [(&str, i64); 28] = [
+    ("UTC", 0),
+    ("Etc/UTC", 0),
+    ("Africa/Johannesburg", 7_200),
+    ("Africa/Lagos", 3_600),
+    ("Africa/Nairobi", 10_800),
+    ("America/Bogota", -18_000),
+    ("America/Lima", -18_000),
+    ("America/Phoenix", -25_200),
+    ("America/Sao_Paulo", -10_800),
+    ("Asia/Bangkok", 25_200),
+    ("Asia/Dhaka", 21_600),
+    ("Asia/Dubai", 14_400),
+    ("Asia/Ho_Chi_Minh", 25_200),
+    ("Asia/Hong_Kong", 28_800),
+    ("Asia/Jakarta", 25_200),
+    ("Asia/Karachi", 18_000),
+    ("Asia/Kathmandu", 20_700),
+    ("Asia/Kolkata", 19_800),
+    ("Asia/Manila", 28_800),
+    ("Asia/Riyadh", 10_800),
+    ("Asia/Seoul", 32_400),
+    ("Asia/Shanghai", 28_800),
+    ("Asia/Singapore", 28_800),
+    ("Asia/Taipei", 28_800),
+    ("Asia/Tokyo", 32_400),
+    ("Australia/Brisbane", 36_000),
+    ("Europe/Moscow", 10_800),
+    ("Pacific/Honolulu", -36_000),
+];
+
+/// Offset in seconds for a zone given as an offset (`+09:00`, `Z`) or as
+/// the IANA name of a zone without daylight saving time (`Asia/Tokyo`).
+pub fn zone_offset(zone: &str) -> Option<i64> {
+    if zone.starts_with(['+', '-']) || zone.eq_ignore_ascii_case("z") {
+        return parse_offset(zone);
+    }
+    FIXED_ZONES
+        .iter()
+        .find(|(name, _)| *name == zone)
+        .map(|&(_, offset)| offset)
+}
+
+/// Format seconds since the Unix epoch as `YYYY-MM-DDThh:mm:ss[.fff]±hh:mm`
+/// in the zone `offset_secs` east of UTC (`Z` for UTC).
+pub fn format_timestamp(secs: f64, offset_secs: i64) -> String {
+    let millis = (secs * 1000.0).round() as i64;
+    let local = millis.div_euclid(1000) + offset_secs;
+    let (days, time) = (local.div_euclid(86_400), local.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    let mut out = format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    );
+    let fraction = millis.rem_euclid(1000);
+    if fraction != 0 {
+        out.push_str(&format!(".{fraction:03}"));
+    }
+    if offset_secs == 0 {
+        out.push('Z');
+    } else {
+        let sign = if offset_secs < 0 { '-' } else { '+' };
+        let abs = offset_secs.abs();
+        out.push_str(&format!("{sign}{:02}:{:02}", abs / 3600, abs / 60 % 60));
+    }
+    out
+}
+
+fn digits<T: std::str::FromStr>(s: &str) -> Option<T> {
+    if s.bytes().all(|c| c.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (i64::from(month) + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian date for days since 1970-01-01 (inverse of [`days_from_civil`]).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0.0));
+        assert_eq!(
+            parse_timestamp("2025-01-01T00:00:00Z"),
+            Some(1_735_689_600.0)
+        );
+        assert_eq!(
+            parse_timestamp("2025-01-01T09:00:00+09:00"),
+            Some(1_735_689_600.0)
+        );
+        assert_eq!(
+            parse_timestamp("2025-01-01T00:00:00.250Z"),
+            Some(1_735_689_600.25)
+        );
+        // No zone designator → UTC
+        assert_eq!(
+            parse_timestamp("2024-02-29T12:00:00"),
+            Some(1_709_208_000.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp_with_offset() {
+        assert_eq!(
+            parse_timestamp_with_offset("2025-01-01T09:00:00+09:00"),
+            Some((1_735_689_600.0, 32_400))
+        );
+        assert_eq!(
+            parse_timestamp_with_offset("2025-01-01T00:00:00Z"),
+            Some((1_735_689_600.0, 0))
+        );
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(1_735_689_600.0, 0), "2025-01-01T00:00:00Z");
+        assert_eq!(
+            format_timestamp(1_735_689_600.25, 32_400),
+            "2025-01-01T09:00:00.250+09:00"
+        );
+        assert_eq!(
+            format_timestamp(1_709_208_000.0, -18_000),
+            "2024-02-29T07:00:00-05:00"
+        );
+        assert_eq!(zone_offset("+05:30"), Some(19_800));
+        assert_eq!(zone_offset("Asia/Tokyo"), Some(32_400));
+        assert_eq!(zone_offset("Europe/Berlin"), None);
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_point_date_time() {
+        use crate::gpx_types::GpxPoint;
+
+        let mut pt = GpxPoint::new(35.0, 139.0);
+        assert_eq!(pt.date_time(), None);
+        pt.time = Some("2025-01-01T09:00:00.5+09:00".to_string());
+        let dt = pt.date_time().unwrap();
+        assert_eq!(dt.unix_timestamp(), 1_735_689_600);
+        assert_eq!(dt.millisecond(), 500);
+        assert_eq!(dt.offset().whole_hours(), 9);
+        assert_eq!(dt.hour(), 9);
+    }
+
+    #[test]
+    fn test_parse_timestamp_invalid() {
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_timestamp("2025-13-01T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("2023-02-29T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("2025-01-01 garbage"), None);
+        assert_eq!(parse_timestamp("2025-01-01T00:00:00+25:00"), None);
+        assert_eq!(parse_timestamp("2025-01-01T00:00:0é"), None);
+        assert_eq!(parse_timestamp("2025-01-01T00:00:00+aé1"), None);
+    }
+}
@@ -0,0 +1,227 @@
+use geojson::{Feature, FeatureCollection, Geometry, JsonObject, Value};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::converter::point_coords;
+use crate::error::Gpx2GeoJsonError;
+use crate::geo;
+use crate::gpx_types::*;
+use crate::options::{DeviationOptions, DistanceFormula};
+
+/// Compare a recorded track against a planned route.
+///
+/// Returns one LineString Feature per off-route section (including the
+/// on-route points on either side, so the section connects to the route)
+/// and an `onRoutePercentage` foreign member giving the share of trackpoints
+/// within `toleranceMeters` of the reference.
+///
+/// The reference line is built from the routes of `reference`, falling back
+/// to its tracks when it contains no routes. A reference without any points
+/// is an error, as every trackpoint would count as off-route.
+pub fn to_deviation_collection(
+    data: &GpxData,
+    reference: &GpxData,
+    opts: &DeviationOptions,
+) -> Result<FeatureCollection, Gpx2GeoJsonError> {
+    let reference_lines = reference_lines(reference);
+    if reference_lines.is_empty() {
+        return Err(Gpx2GeoJsonError::EmptyReference);
+    }
+    let mut features = Vec::new();
+    let mut total = 0usize;
+    let mut on_route = 0usize;
+
+    for trk in &data.tracks {
+        for seg in &trk.segments {
+            let distances: Vec<f64> = seg
+                .points
+                .iter()
+                .map(|pt| {
+                    reference_lines
+                        .iter()
+                        .map(|line| geo::distance_to_line(pt, line))
+                        .fold(f64::INFINITY, f64::min)
+                })
+                .collect();
+
+            total += distances.len();
+            on_route += distances
+                .iter()
+                .filter(|d| **d <= opts.tolerance_meters)
+                .count();
+
+            let mut i = 0;
+            while i < distances.len() {
+                if distances[i] <= opts.tolerance_meters {
+                    i += 1;
+                    continue;
+                }
+                let start = i;
+                while i < distances.len() && distances[i] > opts.tolerance_meters {
+                    i += 1;
+                }
+                let max_deviation = distances[start..i].iter().copied().fold(0.0, f64::max);
+                let from = start.saturating_sub(1);
+                let to = i.min(distances.len() - 1);
+                features.push(deviation_feature(
+                    trk,
+                    &seg.points[from..=to],
+                    max_deviation,
+                ));
+            }
+        }
+    }
+
+    let percentage = if total > 0 {
+        on_route as f64 / total as f64 * 100.0
+    } else {
+        0.0
+    };
+    let mut foreign_members = JsonObject::new();
+    foreign_members.insert("onRoutePercentage".to_string(), number(percentage));
+
+    Ok(FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: Some(foreign_members),
+    })
+}
+
+fn reference_lines(reference: &GpxData) -> Vec<Vec<&GpxPoint>> {
+    let lines: Vec<Vec<&GpxPoint>> = if reference.routes.is_empty() {
+        reference
+            .tracks
+            .iter()
+            .flat_map(|trk| &trk.segments)
+            .map(|seg| seg.points.iter().collect())
+            .collect()
+    } else {
+        reference
+            .routes
+            .iter()
+            .map(|rte| rte.points.iter().collect())
+            .collect()
+    };
+    lines.into_iter().filter(|line| !line.is_empty()).collect()
+}
+
+fn deviation_feature(trk: &GpxTrack, points: &[GpxPoint], max_deviation: f64) -> Feature {
+    let geometry = if points.len() >= 2 {
+        Value::LineString(points.iter().map(|pt| point_coords(pt, false)).collect())
+    } else {
+        Value::Point(point_coords(&points[0], false))
+    };
+
+    let mut props = Map::new();
+    props.insert(
+        "gpxType".to_string(),
+        JsonValue::String("deviation".to_string()),
+    );
+    if let Some(ref name) = trk.name {
+        props.insert("name".to_string(), JsonValue::String(name.clone()));
+    }
+    props.insert("maxDeviation".to_string(), number(max_deviation));
+    props.insert(
+        "distance".to_string(),
+        number(geo::path_length(points, DistanceFormula::Haversine)),
+    );
+
+    Feature {
+        bbox: None,
+        geometry: Some(Geometry::new(geometry)),
+        id: None,
+        properties: Some(props),
+        foreign_members: None,
+    }
+}
+
+fn number(value: f64) -> JsonValue {
+    serde_json::Number::from_f64(value)
+        .map(JsonValue::Number)
+        .unwrap_or(JsonValue::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_gpx;
+
+    const ROUTE: &str = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <rte>
+    <rtept lat="0.0" lon="0.0"/>
+    <rtept lat="0.0" lon="0.05"/>
+  </rte>
+</gpx>"#;
+
+    #[test]
+    fn test_deviation_sections() {
+        let track = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><name>Race</name><trkseg>
+    <trkpt lat="0.0" lon="0.0"/>
+    <trkpt lat="0.0001" lon="0.01"/>
+    <trkpt lat="0.005" lon="0.02"/>
+    <trkpt lat="0.005" lon="0.03"/>
+    <trkpt lat="0.0" lon="0.04"/>
+    <trkpt lat="0.0" lon="0.05"/>
+  </trkseg></trk>
+</gpx>"#;
+        let fc = to_deviation_collection(
+            &parse_gpx(track).unwrap(),
+            &parse_gpx(ROUTE).unwrap(),
+            &DeviationOptions {
+                tolerance_meters: 50.0,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(fc.features.len(), 1);
+        let props = fc.features[0].properties.as_ref().unwrap();
+        assert_eq!(props["gpxType"], "deviation");
+        assert_eq!(props["name"], "Race");
+        let max = props["maxDeviation"].as_f64().unwrap();
+        assert!((max - 556.0).abs() < 1.0, "got {max}");
+
+        match &fc.features[0].geometry.as_ref().unwrap().value {
+            // 2 off-route points plus their on-route neighbours
+            Value::LineString(coords) => assert_eq!(coords.len(), 4),
+            _ => panic!("Expected LineString"),
+        }
+
+        let members = fc.foreign_members.as_ref().unwrap();
+        let pct = members["onRoutePercentage"].as_f64().unwrap();
+        assert!((pct - 4.0 / 6.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fully_on_route() {
+        let track = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="0.0" lon="0.0"/>
+    <trkpt lat="0.0" lon="0.05"/>
+  </trkseg></trk>
+</gpx>"#;
+        let fc = to_deviation_collection(
+            &parse_gpx(track).unwrap(),
+            &parse_gpx(ROUTE).unwrap(),
+            &DeviationOptions::default(),
+        )
+        .unwrap();
+        assert!(fc.features.is_empty());
+        assert_eq!(
+            fc.foreign_members.as_ref().unwrap()["onRoutePercentage"],
+            100.0
+        );
+    }
+
+    #[test]
+    fn test_empty_reference() {
+        let track = parse_gpx(ROUTE).unwrap();
+        let empty =
+            parse_gpx(r#"<gpx version="1.1"><wpt lat="0.0" lon="0.0"/><rte/></gpx>"#).unwrap();
+        let err =
+            to_deviation_collection(&track, &empty, &DeviationOptions::default()).unwrap_err();
+        assert_eq!(err.code(), "emptyReference");
+    }
+}
@@ -0,0 +1,231 @@
+use std::borrow::Cow;
+
+use crate::error::Gpx2GeoJsonError;
+
+type Result<T> = std::result::Result<T, Gpx2GeoJsonError>;
+
+/// Decode raw file bytes to a string for the parser.
+///
+/// The encoding is taken from a UTF-8 / UTF-16 byte order mark, or, without
+/// one, from how `<?` is encoded (XML 1.0 Appendix F). Otherwise the
+/// `encoding` declared in the XML prolog is used (`Shift_JIS`, `ISO-8859-1`,
+/// ... with the `legacy-encodings` feature). Everything else is read as
+/// UTF-8, which borrows the input instead of copying it.
+pub fn decode(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => decode_utf8(rest),
+        [0xFF, 0xFE, rest @ ..] => decode_utf16(rest, u16::from_le_bytes, "UTF-16LE"),
+        [0xFE, 0xFF, rest @ ..] => decode_utf16(rest, u16::from_be_bytes, "UTF-16BE"),
+        [b'<', 0, b'?', 0, ..] => decode_utf16(bytes, u16::from_le_bytes, "UTF-16LE"),
+        [0, b'<', 0, b'?', ..] => decode_utf16(bytes, u16::from_be_bytes, "UTF-16BE"),
+        _ => match declared_encoding(bytes) {
+            Some(label) => decode_declared(bytes, label),
+            None => decode_utf8(bytes),
+        },
+    }
+}
+
+/// The `encoding` pseudo-attribute of a leading `<?xml ...?>` declaration.
+fn declared_encoding(bytes: &[u8]) -> Option<&str> {
+    let rest = bytes.strip_prefix(b"<?xml")?;
+    let end = rest.windows(2).position(|w| w == b"?>")?;
+    let prolog = std::str::from_utf8(&rest[..end]).ok()?;
+    let value = prolog.split_once("encoding")?.1.trim_start();
+    let value = value.strip_prefix('=')?.trim_start();
+    let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+    let value = &value[1..];
+    Some(value[..value.find(quote)?].trim())
+}
+
+#[cfg(feature = "legacy-encodings")]
+fn decode_declared<'a>(bytes: &'a [u8], label: &str) -> Result<Cow<'a, str>> {
+    let encoding = encoding_rs::Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+        Gpx2GeoJsonError::UnsupportedEncoding {
+            label: label.to_string(),
+        }
+    })?;
+    // A prolog readable as ASCII rules out UTF-16, whatever it declares
+    if encoding == encoding_rs::UTF_8 || !encoding.is_ascii_compatible() {
+        return decode_utf8(bytes);
+    }
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .ok_or(Gpx2GeoJsonError::InvalidEncoding {
+            encoding: encoding.name(),
+        })
+}
+
+#[cfg(not(feature = "legacy-encodings"))]
+fn decode_declared<'a>(bytes: &'a [u8], _label: &str) -> Result<Cow<'a, str>> {
+    decode_utf8(bytes)
+}
+
+/// Decode a base64 string, or a `data:` URL with base64 content such as
+/// `data:application/gpx+xml;base64,...`, to the file bytes.
+///
+/// Both the standard and the URL-safe alphabet are accepted; padding is
+/// optional and whitespace (line-wrapped base64) is skipped.
+pub fn decode_base64(data: &str) -> Result<Vec<u8>> {
+    let invalid = || Gpx2GeoJsonError::InvalidEncoding { encoding: "base64" };
+    let data = data.trim();
+    let data = match data.strip_prefix("data:") {
+        Some(url) => match url.split_once(',') {
+            Some((media_type, content)) if media_type.to_ascii_lowercase().ends_with(";base64") => {
+                content
+            }
+            _ => return Err(invalid()),
+        },
+        None => data,
+    };
+
+    let mut bytes = Vec::with_capacity(data.len() / 4 * 3);
+    let (mut acc, mut bits) = (0u32, 0);
+    let mut padded = false;
+    for c in data.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => {
+                padded = true;
+                continue;
+            }
+            c if c.is_ascii_whitespace() => continue,
+            _ => return Err(invalid()),
+        };
+        if padded {
+            return Err(invalid());
+        }
+        acc = (acc << 6 | u32::from(value)) & 0xFFF;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+    // A single character left over can't hold a byte
+    if bits == 6 {
+        return Err(invalid());
+    }
+    Ok(bytes)
+}
+
+fn decode_utf8(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    std::str::from_utf8(bytes)
+        .map(Cow::Borrowed)
+        .map_err(|_| Gpx2GeoJsonError::InvalidEncoding { encoding: "UTF-8" })
+}
+
+fn decode_utf16(
+    bytes: &[u8],
+    unit: fn([u8; 2]) -> u16,
+    encoding: &'static str,
+) -> Result<Cow<'static, str>> {
+    let invalid = || Gpx2GeoJsonError::InvalidEncoding { encoding };
+    if !bytes.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .collect::<std::result::Result<String, _>>()
+        .map(Cow::Owned)
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(s: &str, big_endian: bool) -> Vec<u8> {
+        s.encode_utf16()
+            .flat_map(|u| {
+                if big_endian {
+                    u.to_be_bytes()
+                } else {
+                    u.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode() {
+        let xml = r#"<?xml version="1.0"?><gpx><wpt lat="1" lon="2"><name>東京</name></wpt></gpx>"#;
+
+        assert!(matches!(decode(xml.as_bytes()).unwrap(), Cow::Borrowed(s) if s == xml));
+        let with_bom = [&[0xEF, 0xBB, 0xBF], xml.as_bytes()].concat();
+        assert_eq!(decode(&with_bom).unwrap(), xml);
+
+        let le = [vec![0xFF, 0xFE], utf16(xml, false)].concat();
+        assert_eq!(decode(&le).unwrap(), xml);
+        let be = [vec![0xFE, 0xFF], utf16(xml, true)].concat();
+        assert_eq!(decode(&be).unwrap(), xml);
+        // No BOM: detected from the encoding of "<?"
+        assert_eq!(decode(&utf16(xml, false)).unwrap(), xml);
+        assert_eq!(decode(&utf16(xml, true)).unwrap(), xml);
+    }
+
+    #[test]
+    fn test_declared_encoding() {
+        assert_eq!(
+            declared_encoding(br#"<?xml version="1.0" encoding="Shift_JIS"?><gpx/>"#),
+            Some("Shift_JIS")
+        );
+        assert_eq!(
+            declared_encoding(b"<?xml version='1.0' encoding = 'ISO-8859-1' ?>"),
+            Some("ISO-8859-1")
+        );
+        assert_eq!(declared_encoding(br#"<?xml version="1.0"?>"#), None);
+        assert_eq!(declared_encoding(b"<gpx/>"), None);
+    }
+
+    #[cfg(feature = "legacy-encodings")]
+    #[test]
+    fn test_decode_legacy_encodings() {
+        let xml = r#"<?xml version="1.0" encoding="Shift_JIS"?><gpx><wpt lat="1" lon="2"><name>東京</name></wpt></gpx>"#;
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode(xml);
+        assert_eq!(decode(&sjis).unwrap(), xml);
+
+        let latin1 = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><name>Z\xFCrich</name>";
+        assert!(decode(latin1).unwrap().ends_with("<name>Zürich</name>"));
+
+        // UTF-8 and UTF-16 labels read as UTF-8
+        let utf8 = r#"<?xml version="1.0" encoding="utf8"?><name>東京</name>"#;
+        assert_eq!(decode(utf8.as_bytes()).unwrap(), utf8);
+        let utf16 = r#"<?xml version="1.0" encoding="UTF-16"?><name>x</name>"#;
+        assert_eq!(decode(utf16.as_bytes()).unwrap(), utf16);
+
+        assert!(decode(br#"<?xml version="1.0" encoding="x-unknown"?><gpx/>"#).is_err());
+    }
+
+    #[test]
+    fn test_decode_base64() {
+        let xml = b"<gpx version=\"1.1\"/>";
+        assert_eq!(decode_base64("PGdweCB2ZXJzaW9uPSIxLjEiLz4=").unwrap(), xml);
+        assert_eq!(
+            decode_base64(" PGdweCB2ZXJz\naW9uPSIxLjEiLz4 ").unwrap(),
+            xml
+        );
+        assert_eq!(
+            decode_base64("data:application/gpx+xml;base64,PGdweCB2ZXJzaW9uPSIxLjEiLz4=").unwrap(),
+            xml
+        );
+        // URL-safe alphabet
+        assert_eq!(decode_base64("-_8").unwrap(), [0xFB, 0xFF]);
+
+        assert!(decode_base64("PGd*").is_err());
+        assert!(decode_base64("PGdwe").is_err());
+        assert!(decode_base64("PG==dw").is_err());
+        assert!(decode_base64("data:application/gpx+xml,<gpx/>").is_err());
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(decode(&[b'<', 0xFF, b'>']).is_err());
+        // Odd length and an unpaired surrogate
+        assert!(decode(&[0xFF, 0xFE, b'<']).is_err());
+        assert!(decode(&[0xFF, 0xFE, 0x00, 0xD8]).is_err());
+    }
+}
@@ -14,6 +14,32 @@ pub enum Gpx2GeoJsonError {
         value: String,
     },
     FloatParse(ParseFloatError),
+    LimitExceeded {
+        limit: &'static str,
+        max: usize,
+    },
+    InvalidEncoding {
+        encoding: &'static str,
+    },
+    UnsupportedEncoding {
+        label: String,
+    },
+    Validation {
+        position: u64,
+        message: String,
+    },
+    InvalidOutput {
+        feature: usize,
+        message: String,
+    },
+    InvalidArchive {
+        message: String,
+    },
+    Fetch {
+        url: String,
+        status: u16,
+    },
+    EmptyReference,
 }
 
 impl std::fmt::Display for Gpx2GeoJsonError {
@@ -32,12 +58,121 @@ impl std::fmt::Display for Gpx2GeoJsonError {
                 "Invalid value '{value}' for attribute '{attribute}' on <{element}>"
             ),
             Self::FloatParse(e) => write!(f, "Float parse error: {e}"),
+            Self::LimitExceeded { limit, max } => {
+                write!(f, "Limit exceeded: {limit} (max {max})")
+            }
+            Self::InvalidEncoding { encoding } => write!(f, "Input is not valid {encoding}"),
+            Self::UnsupportedEncoding { label } => write!(f, "Unsupported encoding '{label}'"),
+            Self::Validation { position, message } => {
+                write!(f, "Invalid GPX at byte {position}: {message}")
+            }
+            Self::InvalidOutput { feature, message } => {
+                write!(f, "Invalid geometry in output feature {feature}: {message}")
+            }
+            Self::InvalidArchive { message } => write!(f, "Invalid ZIP archive: {message}"),
+            Self::Fetch { url, status } => write!(f, "Fetching {url} failed with HTTP {status}"),
+            Self::EmptyReference => write!(f, "Reference GPX has no route or track points"),
         }
     }
 }
 
 impl std::error::Error for Gpx2GeoJsonError {}
 
+impl Gpx2GeoJsonError {
+    /// Stable identifier of the kind of error, for JS callers to branch on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::XmlParse(_) => "xmlParse",
+            Self::MissingAttribute { .. } => "missingAttribute",
+            Self::InvalidAttribute { .. } => "invalidAttribute",
+            Self::FloatParse(_) => "floatParse",
+            Self::LimitExceeded { .. } => "limitExceeded",
+            Self::InvalidEncoding { .. } => "invalidEncoding",
+            Self::UnsupportedEncoding { .. } => "unsupportedEncoding",
+            Self::Validation { .. } => "validation",
+            Self::InvalidOutput { .. } => "invalidOutput",
+            Self::InvalidArchive { .. } => "invalidArchive",
+            Self::Fetch { .. } => "fetch",
+            Self::EmptyReference => "emptyReference",
+        }
+    }
+
+    /// The GPX element the error names, if any.
+    pub fn element(&self) -> Option<&'static str> {
+        match self {
+            Self::MissingAttribute { element, .. } | Self::InvalidAttribute { element, .. } => {
+                Some(element)
+            }
+            _ => None,
+        }
+    }
+
+    /// A JS `Error` with the message plus `code`, and `element`, `line` and
+    /// `column` where known.
+    fn to_js_error(&self, element: Option<&str>, location: Option<(usize, usize)>) -> JsValue {
+        let error = js_sys::Error::new(&self.to_string());
+        let mut props: Vec<(&str, JsValue)> = vec![("code", self.code().into())];
+        if let Some(element) = self.element().or(element) {
+            props.push(("element", element.into()));
+        }
+        if let Some((line, column)) = location {
+            props.push(("line", (line as u32).into()));
+            props.push(("column", (column as u32).into()));
+        }
+        for (key, value) in props {
+            // Setting a property on a fresh Error object can't fail
+            let _ = js_sys::Reflect::set(&error, &key.into(), &value);
+        }
+        error.into()
+    }
+}
+
+/// A parse error with the byte offset of the XML event being read when it
+/// occurred (for an error inside a point, the start of the point).
+#[derive(Debug)]
+pub struct LocatedError {
+    pub error: Gpx2GeoJsonError,
+    pub position: u64,
+}
+
+impl LocatedError {
+    /// Convert to a JS `Error`, with the line, column and element name
+    /// looked up in the parsed `text`.
+    pub fn into_js(self, text: &str) -> JsValue {
+        let position = (self.position as usize).min(text.len());
+        let element = element_at(text, position);
+        let location = line_column(text, position);
+        self.error.to_js_error(element, Some(location))
+    }
+}
+
+/// 1-based line and column (in characters) of byte `position` in `text`.
+pub fn line_column(text: &str, position: usize) -> (usize, usize) {
+    let before = &text.as_bytes()[..position];
+    let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+    let line_start = before
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    let column = String::from_utf8_lossy(&before[line_start..])
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+/// Local name of the element whose tag starts at byte `position`, if any.
+fn element_at(text: &str, position: usize) -> Option<&str> {
+    let tag = text.get(position..)?.strip_prefix('<')?;
+    let tag = tag.strip_prefix('/').unwrap_or(tag);
+    let end = tag
+        .find(|c: char| c.is_whitespace() || matches!(c, '>' | '/'))
+        .unwrap_or(tag.len());
+    let name = &tag[..end];
+    let local = name.rsplit(':').next()?;
+    (!local.is_empty() && !local.starts_with(['!', '?'])).then_some(local)
+}
+
 impl From<quick_xml::Error> for Gpx2GeoJsonError {
     fn from(e: quick_xml::Error) -> Self {
         Self::XmlParse(e)
@@ -52,6 +187,33 @@ impl From<ParseFloatError> for Gpx2GeoJsonError {
 
 impl From<Gpx2GeoJsonError> for JsValue {
     fn from(e: Gpx2GeoJsonError) -> Self {
-        JsValue::from_str(&e.to_string())
+        e.to_js_error(None, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_location() {
+        let xml = "<gpx>\n  <wpt lat=\"x\"/>\n  <trk><é:trkpt/></trk>\n</gpx>";
+        let wpt = xml.find("<wpt").unwrap();
+        assert_eq!(line_column(xml, wpt), (2, 3));
+        assert_eq!(element_at(xml, wpt), Some("wpt"));
+        let trkpt = xml.find("<é:trkpt").unwrap();
+        assert_eq!(line_column(xml, trkpt + "<é:trkpt".len()), (3, 16));
+        assert_eq!(element_at(xml, trkpt), Some("trkpt"));
+        assert_eq!(element_at(xml, xml.find("</gpx>").unwrap()), Some("gpx"));
+        assert_eq!(element_at(xml, wpt + 1), None);
+
+        let error = Gpx2GeoJsonError::MissingAttribute {
+            element: "trkpt",
+            attribute: "lat",
+        };
+        assert_eq!(
+            (error.code(), error.element()),
+            ("missingAttribute", Some("trkpt"))
+        );
     }
 }
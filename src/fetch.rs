@@ -0,0 +1,71 @@
+use js_sys::{Promise, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::error::Gpx2GeoJsonError;
+
+// The parts of the Fetch and Streams APIs used here, so the module doesn't
+// need web-sys. `fetch` is the global of browsers, workers and Node 18+.
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = fetch)]
+    fn global_fetch(url: &str) -> Promise;
+
+    type Response;
+    #[wasm_bindgen(method, getter)]
+    fn ok(this: &Response) -> bool;
+    #[wasm_bindgen(method, getter)]
+    fn status(this: &Response) -> u16;
+    #[wasm_bindgen(method, getter)]
+    fn body(this: &Response) -> Option<ReadableStream>;
+
+    /// A WHATWG `ReadableStream` of `Uint8Array` chunks, e.g. a fetch
+    /// response body or `Blob.stream()`.
+    #[wasm_bindgen(typescript_type = "ReadableStream<Uint8Array>")]
+    pub type ReadableStream;
+    #[wasm_bindgen(method, js_name = getReader)]
+    fn get_reader(this: &ReadableStream) -> ReadableStreamReader;
+
+    type ReadableStreamReader;
+    #[wasm_bindgen(method)]
+    fn read(this: &ReadableStreamReader) -> Promise;
+}
+
+/// Download `url`, passing each chunk of the response body to `on_chunk`
+/// as it arrives.
+///
+/// A network failure rejects with the `TypeError` from `fetch`; a response
+/// that isn't 2xx fails with `Fetch`.
+pub async fn fetch_chunks(
+    url: &str,
+    on_chunk: impl FnMut(&[u8]) -> Result<(), JsValue>,
+) -> Result<(), JsValue> {
+    let response: Response = JsFuture::from(global_fetch(url)).await?.unchecked_into();
+    if !response.ok() {
+        return Err(Gpx2GeoJsonError::Fetch {
+            url: url.to_string(),
+            status: response.status(),
+        }
+        .into());
+    }
+    match response.body() {
+        Some(body) => read_chunks(&body, on_chunk).await,
+        None => Ok(()),
+    }
+}
+
+/// Read `stream` to the end, passing each chunk to `on_chunk`.
+pub async fn read_chunks(
+    stream: &ReadableStream,
+    mut on_chunk: impl FnMut(&[u8]) -> Result<(), JsValue>,
+) -> Result<(), JsValue> {
+    let reader = stream.get_reader();
+    loop {
+        let result = JsFuture::from(reader.read()).await?;
+        if Reflect::get(&result, &"done".into())?.is_truthy() {
+            return Ok(());
+        }
+        let chunk = Uint8Array::new(&Reflect::get(&result, &"value".into())?);
+        on_chunk(&chunk.to_vec())?;
+    }
+}
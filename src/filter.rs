@@ -0,0 +1,384 @@
+use crate::geo;
+use crate::gpx_types::*;
+use crate::options::{ConvertOptions, CoordinateTransform, FixQuality};
+
+/// Whether any point filter is enabled in `opts`.
+pub fn is_enabled(opts: &ConvertOptions) -> bool {
+    opts.min_fix_quality.is_some() || opts.max_hdop.is_some()
+}
+
+/// Return a copy of `data` without the points failing the quality thresholds.
+///
+/// Points that don't report a <fix> or <hdop> are kept, so files from
+/// devices that omit these fields are not emptied by the filter.
+pub fn filter_points(data: &GpxData, opts: &ConvertOptions) -> GpxData {
+    let mut dropped = 0usize;
+    let mut keep = |pts: &[GpxPoint]| -> Vec<GpxPoint> {
+        let kept: Vec<GpxPoint> = pts
+            .iter()
+            .filter(|pt| passes_quality(pt, opts))
+            .cloned()
+            .collect();
+        dropped += pts.len() - kept.len();
+        kept
+    };
+
+    let waypoints = keep(&data.waypoints);
+    let routes = data
+        .routes
+        .iter()
+        .map(|rte| GpxRoute {
+            points: keep(&rte.points),
+            ..rte.clone()
+        })
+        .collect();
+    let tracks = data
+        .tracks
+        .iter()
+        .map(|trk| GpxTrack {
+            segments: trk
+                .segments
+                .iter()
+                .map(|seg| GpxSegment {
+                    points: keep(&seg.points),
+                    extensions: seg.extensions.clone(),
+                })
+                .collect(),
+            ..trk.clone()
+        })
+        .collect();
+
+    log::info!("Dropped {dropped} points below the fix-quality thresholds");
+    GpxData {
+        version: data.version.clone(),
+        metadata: data.metadata.clone(),
+        waypoints,
+        routes,
+        tracks,
+        dropped: DroppedData {
+            filtered_points: data.dropped.filtered_points + dropped,
+            ..data.dropped.clone()
+        },
+    }
+}
+
+/// Return a copy of `data` with every route and track segment simplified
+/// to within `tolerance_m` meters (see [`geo::simplify`]).
+pub fn simplify_lines(data: &GpxData, tolerance_m: f64) -> GpxData {
+    let routes = data
+        .routes
+        .iter()
+        .map(|rte| GpxRoute {
+            points: geo::simplify(&rte.points, tolerance_m),
+            ..rte.clone()
+        })
+        .collect();
+    let tracks = data
+        .tracks
+        .iter()
+        .map(|trk| GpxTrack {
+            segments: trk
+                .segments
+                .iter()
+                .map(|seg| GpxSegment {
+                    points: geo::simplify(&seg.points, tolerance_m),
+                    extensions: seg.extensions.clone(),
+                })
+                .collect(),
+            ..trk.clone()
+        })
+        .collect();
+
+    GpxData {
+        version: data.version.clone(),
+        metadata: data.metadata.clone(),
+        waypoints: data.waypoints.clone(),
+        routes,
+        tracks,
+        dropped: data.dropped.clone(),
+    }
+}
+
+/// Return a copy of `data` with every point moved by `transform`. The
+/// metadata bounds become the box around their transformed corners.
+pub fn transform_coordinates(data: &GpxData, transform: &CoordinateTransform) -> GpxData {
+    let apply = |pts: &[GpxPoint]| -> Vec<GpxPoint> {
+        pts.iter()
+            .map(|pt| {
+                let (lon, lat) = transform_lon_lat(pt.lon, pt.lat, transform);
+                GpxPoint {
+                    lat,
+                    lon,
+                    ele: pt.ele.map(|ele| ele + transform.d_ele),
+                    ..pt.clone()
+                }
+            })
+            .collect()
+    };
+    let routes = data
+        .routes
+        .iter()
+        .map(|rte| GpxRoute {
+            points: apply(&rte.points),
+            ..rte.clone()
+        })
+        .collect();
+    let tracks = data
+        .tracks
+        .iter()
+        .map(|trk| GpxTrack {
+            segments: trk
+                .segments
+                .iter()
+                .map(|seg| GpxSegment {
+                    points: apply(&seg.points),
+                    extensions: seg.extensions.clone(),
+                })
+                .collect(),
+            ..trk.clone()
+        })
+        .collect();
+    let metadata = data.metadata.as_ref().map(|metadata| GpxMetadata {
+        bounds: metadata.bounds.as_ref().map(|b| {
+            let corners = [
+                (b.min_lon, b.min_lat),
+                (b.min_lon, b.max_lat),
+                (b.max_lon, b.min_lat),
+                (b.max_lon, b.max_lat),
+            ]
+            .map(|(lon, lat)| transform_lon_lat(lon, lat, transform));
+            GpxBounds {
+                min_lat: corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min),
+                min_lon: corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min),
+                max_lat: corners
+                    .iter()
+                    .map(|c| c.1)
+                    .fold(f64::NEG_INFINITY, f64::max),
+                max_lon: corners
+                    .iter()
+                    .map(|c| c.0)
+                    .fold(f64::NEG_INFINITY, f64::max),
+            }
+        }),
+        ..metadata.clone()
+    });
+
+    GpxData {
+        version: data.version.clone(),
+        metadata,
+        waypoints: apply(&data.waypoints),
+        routes,
+        tracks,
+        dropped: data.dropped.clone(),
+    }
+}
+
+fn transform_lon_lat(lon: f64, lat: f64, transform: &CoordinateTransform) -> (f64, f64) {
+    let (lon, lat) = match transform.matrix {
+        Some([a, b, c, d, e, f]) => (a * lon + b * lat + c, d * lon + e * lat + f),
+        None => (lon, lat),
+    };
+    (lon + transform.d_lon, lat + transform.d_lat)
+}
+
+/// The waypoints within `max_m` meters of a track segment or route, with
+/// their indices in `data.waypoints`.
+///
+/// Files without any track or route keep all their waypoints. Lines whose
+/// bounding box, grown by `max_m`, doesn't contain a waypoint are skipped
+/// without measuring, so large POI sets stay cheap to filter.
+pub fn waypoints_near_lines(data: &GpxData, max_m: f64) -> Vec<(usize, &GpxPoint)> {
+    let lines: Vec<Vec<&GpxPoint>> = data
+        .tracks
+        .iter()
+        .flat_map(|trk| &trk.segments)
+        .map(|seg| seg.points.iter().collect())
+        .chain(data.routes.iter().map(|rte| rte.points.iter().collect()))
+        .filter(|line: &Vec<&GpxPoint>| !line.is_empty())
+        .collect();
+    if lines.is_empty() {
+        return data.waypoints.iter().enumerate().collect();
+    }
+
+    let boxes: Vec<[f64; 4]> = lines.iter().map(|line| grown_bbox(line, max_m)).collect();
+    data.waypoints
+        .iter()
+        .enumerate()
+        .filter(|(_, wpt)| {
+            lines.iter().zip(&boxes).any(|(line, bbox)| {
+                let [min_lon, min_lat, max_lon, max_lat] = *bbox;
+                (min_lon..=max_lon).contains(&wpt.lon)
+                    && (min_lat..=max_lat).contains(&wpt.lat)
+                    && geo::distance_to_line(wpt, line) <= max_m
+            })
+        })
+        .collect()
+}
+
+/// `[min_lon, min_lat, max_lon, max_lat]` of `line`, grown by `margin_m` meters.
+fn grown_bbox(line: &[&GpxPoint], margin_m: f64) -> [f64; 4] {
+    let mut bbox = [
+        f64::INFINITY,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NEG_INFINITY,
+    ];
+    for pt in line {
+        bbox = [
+            bbox[0].min(pt.lon),
+            bbox[1].min(pt.lat),
+            bbox[2].max(pt.lon),
+            bbox[3].max(pt.lat),
+        ];
+    }
+    // Longitude degrees shrink towards the poles; use the widest latitude
+    let max_abs_lat = bbox[1].abs().max(bbox[3].abs()).min(89.0);
+    let lat_margin = margin_m / geo::METERS_PER_DEGREE;
+    let lon_margin = lat_margin / max_abs_lat.to_radians().cos();
+    [
+        bbox[0] - lon_margin,
+        bbox[1] - lat_margin,
+        bbox[2] + lon_margin,
+        bbox[3] + lat_margin,
+    ]
+}
+
+/// Number of points `filter_points` would remove.
+pub fn count_filtered(data: &GpxData, opts: &ConvertOptions) -> usize {
+    let waypoints = data.waypoints.iter();
+    let route_points = data.routes.iter().flat_map(|rte| &rte.points);
+    let track_points = data
+        .tracks
+        .iter()
+        .flat_map(|trk| &trk.segments)
+        .flat_map(|seg| &seg.points);
+    waypoints
+        .chain(route_points)
+        .chain(track_points)
+        .filter(|pt| !passes_quality(pt, opts))
+        .count()
+}
+
+/// Indices into `data.waypoints` of the waypoints `filter_points` keeps.
+pub fn kept_waypoint_indices(data: &GpxData, opts: &ConvertOptions) -> Vec<usize> {
+    data.waypoints
+        .iter()
+        .enumerate()
+        .filter(|(_, wpt)| passes_quality(wpt, opts))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn passes_quality(pt: &GpxPoint, opts: &ConvertOptions) -> bool {
+    let fix_ok = match (opts.min_fix_quality, pt.fix.as_deref()) {
+        (Some(min), Some(fix)) => FixQuality::from_gpx(fix).is_none_or(|q| q >= min),
+        _ => true,
+    };
+    let hdop_ok = match (opts.max_hdop, pt.hdop) {
+        (Some(max), Some(hdop)) => hdop <= max,
+        _ => true,
+    };
+    fix_ok && hdop_ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_gpx;
+
+    const XML: &str = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><fix>3d</fix><hdop>0.9</hdop></trkpt>
+    <trkpt lat="35.1" lon="139.1"><fix>2d</fix><hdop>1.5</hdop></trkpt>
+    <trkpt lat="35.2" lon="139.2"><fix>dgps</fix><hdop>6.0</hdop></trkpt>
+    <trkpt lat="35.3" lon="139.3"/>
+  </trkseg></trk>
+</gpx>"#;
+
+    #[test]
+    fn test_min_fix_quality() {
+        let data = parse_gpx(XML).unwrap();
+        let opts = ConvertOptions {
+            min_fix_quality: Some(FixQuality::ThreeD),
+            ..Default::default()
+        };
+        let filtered = filter_points(&data, &opts);
+        let pts = &filtered.tracks[0].segments[0].points;
+        // 2d fix dropped; point without <fix> kept
+        assert_eq!(pts.len(), 3);
+        assert_eq!(pts[1].fix.as_deref(), Some("dgps"));
+    }
+
+    #[test]
+    fn test_max_hdop() {
+        let data = parse_gpx(XML).unwrap();
+        let opts = ConvertOptions {
+            max_hdop: Some(2.0),
+            ..Default::default()
+        };
+        let filtered = filter_points(&data, &opts);
+        let pts = &filtered.tracks[0].segments[0].points;
+        assert_eq!(pts.len(), 3);
+        assert!(pts.iter().all(|pt| pt.hdop.is_none_or(|h| h <= 2.0)));
+    }
+
+    #[test]
+    fn test_transform_coordinates() {
+        let xml = r#"<gpx>
+  <metadata><bounds minlat="35" minlon="139" maxlat="36" maxlon="140"/></metadata>
+  <wpt lat="35.5" lon="139.5"><ele>10</ele></wpt>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/></trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let offset = CoordinateTransform {
+            d_lat: 0.003,
+            d_lon: -0.003,
+            d_ele: 5.0,
+            matrix: None,
+        };
+        let moved = transform_coordinates(&data, &offset);
+        let wpt = &moved.waypoints[0];
+        assert_eq!((wpt.lat, wpt.lon, wpt.ele), (35.503, 139.497, Some(15.0)));
+        let trkpt = &moved.tracks[0].segments[0].points[0];
+        assert_eq!((trkpt.lat, trkpt.lon, trkpt.ele), (35.003, 138.997, None));
+
+        // Swap lon and lat, then shift
+        let swap = CoordinateTransform {
+            d_lon: 1.0,
+            matrix: Some([0.0, 1.0, 0.0, 1.0, 0.0, 0.0]),
+            ..Default::default()
+        };
+        let moved = transform_coordinates(&data, &swap);
+        let wpt = &moved.waypoints[0];
+        assert_eq!((wpt.lat, wpt.lon), (139.5, 36.5));
+        let bounds = moved.metadata.unwrap().bounds.unwrap();
+        assert_eq!((bounds.min_lat, bounds.max_lat), (139.0, 140.0));
+        assert_eq!((bounds.min_lon, bounds.max_lon), (36.0, 37.0));
+    }
+
+    #[test]
+    fn test_waypoints_near_lines() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="35.0005" lon="139.05"><name>On track</name></wpt>
+  <wpt lat="35.1" lon="139.05"><name>Far away</name></wpt>
+  <wpt lat="36.0" lon="140.0003"><name>Near route</name></wpt>
+  <rte><rtept lat="36.0" lon="140.0"/><rtept lat="36.1" lon="140.0"/></rte>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="35.0" lon="139.1"/></trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let names = |max_m| -> Vec<&str> {
+            waypoints_near_lines(&data, max_m)
+                .iter()
+                .filter_map(|(_, wpt)| wpt.name.as_deref())
+                .collect()
+        };
+        // ~56 m north of the track, ~27 m east of the route
+        assert_eq!(names(100.0), vec!["On track", "Near route"]);
+        assert_eq!(names(40.0), vec!["Near route"]);
+
+        let only_waypoints = parse_gpx(r#"<gpx><wpt lat="1" lon="2"/></gpx>"#).unwrap();
+        assert_eq!(waypoints_near_lines(&only_waypoints, 10.0).len(), 1);
+    }
+}
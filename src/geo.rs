@@ -0,0 +1,321 @@
+use crate::gpx_types::*;
+use crate::options::DistanceFormula;
+
+/// Mean Earth radius in meters (IUGG).
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Length of one degree of latitude (or of longitude at the equator) in meters.
+pub const METERS_PER_DEGREE: f64 = EARTH_RADIUS_M * std::f64::consts::PI / 180.0;
+
+/// Great-circle distance between two points in meters (haversine formula).
+pub fn haversine_distance(a: &GpxPoint, b: &GpxPoint) -> f64 {
+    let (lat1, lat2) = (a.lat.to_radians(), b.lat.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (b.lon - a.lon).to_radians();
+
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * h.sqrt().asin()
+}
+
+/// Distance between two points in meters, measured with `formula`.
+pub fn distance(a: &GpxPoint, b: &GpxPoint, formula: DistanceFormula) -> f64 {
+    match formula {
+        DistanceFormula::Haversine => haversine_distance(a, b),
+        DistanceFormula::Geodesic => geodesic_distance(a, b),
+    }
+}
+
+/// Distance on the WGS84 ellipsoid in meters (Karney's algorithm).
+#[cfg(feature = "geodesic")]
+pub fn geodesic_distance(a: &GpxPoint, b: &GpxPoint) -> f64 {
+    use geographiclib_rs::{Geodesic, InverseGeodesic};
+
+    Geodesic::wgs84().inverse(a.lat, a.lon, b.lat, b.lon)
+}
+
+/// Without the `geodesic` feature, geodesic distances fall back to haversine.
+#[cfg(not(feature = "geodesic"))]
+pub fn geodesic_distance(a: &GpxPoint, b: &GpxPoint) -> f64 {
+    haversine_distance(a, b)
+}
+
+/// Linearly interpolate a point at fraction `t` (0.0..=1.0) between `a` and `b`.
+/// Only the position and elevation are interpolated; metadata is left empty.
+pub fn interpolate(a: &GpxPoint, b: &GpxPoint, t: f64) -> GpxPoint {
+    let mut pt = GpxPoint::new(a.lat + (b.lat - a.lat) * t, a.lon + (b.lon - a.lon) * t);
+    pt.ele = match (a.ele, b.ele) {
+        (Some(ea), Some(eb)) => Some(ea + (eb - ea) * t),
+        _ => None,
+    };
+    pt
+}
+
+/// Total length of a point sequence in meters.
+pub fn path_length(points: &[GpxPoint], formula: DistanceFormula) -> f64 {
+    points
+        .windows(2)
+        .map(|w| distance(&w[0], &w[1], formula))
+        .sum()
+}
+
+/// Index of the point in `points` closest to `target`, or `None` if `points` is empty.
+pub fn nearest_point_index(points: &[&GpxPoint], target: &GpxPoint) -> Option<usize> {
+    points
+        .iter()
+        .map(|pt| haversine_distance(pt, target))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+/// Shortest distance in meters from `pt` to the polyline through `line`.
+///
+/// Uses a local equirectangular projection around `pt`, which is accurate
+/// for the short distances involved in route-matching.
+pub fn distance_to_line(pt: &GpxPoint, line: &[&GpxPoint]) -> f64 {
+    if line.len() == 1 {
+        return haversine_distance(pt, line[0]);
+    }
+
+    let m_per_deg = EARTH_RADIUS_M.to_radians();
+    let cos_lat = pt.lat.to_radians().cos();
+    let project = |p: &GpxPoint| {
+        (
+            (p.lon - pt.lon) * m_per_deg * cos_lat,
+            (p.lat - pt.lat) * m_per_deg,
+        )
+    };
+
+    line.windows(2)
+        .map(|w| {
+            let (ax, ay) = project(w[0]);
+            let (bx, by) = project(w[1]);
+            let (dx, dy) = (bx - ax, by - ay);
+            let len2 = dx * dx + dy * dy;
+            let t = if len2 > 0.0 {
+                (-(ax * dx + ay * dy) / len2).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            (ax + t * dx).hypot(ay + t * dy)
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Douglas-Peucker simplification of a point sequence.
+///
+/// Drops every point lying within `tolerance_m` meters of the line between
+/// the points kept around it. The first and last point are always kept.
+pub fn simplify(points: &[GpxPoint], tolerance_m: f64) -> Vec<GpxPoint> {
+    if points.len() <= 2 {
+        return points.to_vec();
+    }
+
+    let last = points.len() - 1;
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[last] = true;
+    let mut stack = vec![(0, last)];
+    while let Some((start, end)) = stack.pop() {
+        let line = [&points[start], &points[end]];
+        let farthest = (start + 1..end)
+            .map(|i| (i, distance_to_line(&points[i], &line)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, dist)) = farthest
+            && dist > tolerance_m
+        {
+            keep[i] = true;
+            stack.push((start, i));
+            stack.push((i, end));
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter(|(_, k)| *k)
+        .map(|(pt, _)| pt.clone())
+        .collect()
+}
+
+/// Keep only the part of a track between `start_m` and `end_m` meters along the line.
+///
+/// Distance accumulates across segments (gaps between segments are not counted).
+/// Cut points falling between two trackpoints are interpolated.
+pub fn crop_track_by_distance(
+    trk: &GpxTrack,
+    start_m: f64,
+    end_m: f64,
+    formula: DistanceFormula,
+) -> GpxTrack {
+    let mut offset = 0.0;
+    let segments = trk
+        .segments
+        .iter()
+        .map(|seg| {
+            let (points, length) = crop_points(&seg.points, offset, start_m, end_m, formula);
+            offset += length;
+            GpxSegment {
+                points,
+                extensions: seg.extensions.clone(),
+            }
+        })
+        .collect();
+
+    GpxTrack {
+        segments,
+        ..trk.clone()
+    }
+}
+
+/// Crop a point sequence whose first point lies `offset` meters along the track.
+/// Returns the kept points and the total length of the sequence.
+fn crop_points(
+    points: &[GpxPoint],
+    offset: f64,
+    start_m: f64,
+    end_m: f64,
+    formula: DistanceFormula,
+) -> (Vec<GpxPoint>, f64) {
+    let mut kept = Vec::new();
+    let mut dist = offset;
+
+    for (i, pt) in points.iter().enumerate() {
+        if i > 0 {
+            let prev = &points[i - 1];
+            let len = distance(prev, pt, formula);
+            let next = dist + len;
+            if len > 0.0 {
+                if dist < start_m && next > start_m {
+                    kept.push(interpolate(prev, pt, (start_m - dist) / len));
+                }
+                if dist < end_m && next > end_m {
+                    kept.push(interpolate(prev, pt, (end_m - dist) / len));
+                }
+            }
+            dist = next;
+        }
+        if dist >= start_m && dist <= end_m {
+            kept.push(pt.clone());
+        }
+    }
+
+    (kept, dist - offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_haversine_distance() {
+        // One degree of latitude ≈ 111.2 km
+        let a = GpxPoint::new(35.0, 139.0);
+        let b = GpxPoint::new(36.0, 139.0);
+        let d = haversine_distance(&a, &b);
+        assert!((d - 111_195.0).abs() < 10.0, "got {d}");
+    }
+
+    #[cfg(feature = "geodesic")]
+    #[test]
+    fn test_geodesic_distance() {
+        // One degree of longitude on the WGS84 equator is 111,319.49 m,
+        // about 0.1% more than on the mean-radius sphere
+        let a = GpxPoint::new(0.0, 0.0);
+        let b = GpxPoint::new(0.0, 1.0);
+        let d = distance(&a, &b, DistanceFormula::Geodesic);
+        assert!((d - 111_319.491).abs() < 0.01, "got {d}");
+        assert!(distance(&a, &b, DistanceFormula::Haversine) < d - 100.0);
+    }
+
+    #[test]
+    fn test_nearest_point_index() {
+        let pts = [
+            GpxPoint::new(35.0, 139.0),
+            GpxPoint::new(35.5, 139.5),
+            GpxPoint::new(36.0, 140.0),
+        ];
+        let refs: Vec<&GpxPoint> = pts.iter().collect();
+        let target = GpxPoint::new(35.6, 139.4);
+        assert_eq!(nearest_point_index(&refs, &target), Some(1));
+        assert_eq!(nearest_point_index(&[], &target), None);
+    }
+
+    #[test]
+    fn test_distance_to_line() {
+        let a = GpxPoint::new(0.0, 0.0);
+        let b = GpxPoint::new(0.0, 0.02);
+        // ~111 m north of the middle of the line
+        let pt = GpxPoint::new(0.001, 0.01);
+        let d = distance_to_line(&pt, &[&a, &b]);
+        assert!((d - 111.2).abs() < 0.5, "got {d}");
+
+        // Beyond the end of the line → distance to the endpoint
+        let pt = GpxPoint::new(0.0, 0.03);
+        let d = distance_to_line(&pt, &[&a, &b]);
+        assert!((d - 1112.0).abs() < 1.0, "got {d}");
+    }
+
+    #[test]
+    fn test_simplify() {
+        let pts = vec![
+            GpxPoint::new(0.0, 0.0),
+            // ~1 m off the straight line → dropped
+            GpxPoint::new(0.00001, 0.005),
+            GpxPoint::new(0.0, 0.01),
+            // ~111 m off → kept
+            GpxPoint::new(0.001, 0.015),
+            GpxPoint::new(0.0, 0.02),
+        ];
+        let simplified = simplify(&pts, 5.0);
+        let lons: Vec<f64> = simplified.iter().map(|pt| pt.lon).collect();
+        assert_eq!(lons, vec![0.0, 0.01, 0.015, 0.02]);
+        assert_eq!(simplify(&pts[..2], 5.0).len(), 2);
+    }
+
+    #[test]
+    fn test_crop_interpolates_cut_points() {
+        let mut a = GpxPoint::new(0.0, 0.0);
+        a.ele = Some(0.0);
+        let mut b = GpxPoint::new(0.0, 0.01);
+        b.ele = Some(100.0);
+        let len = haversine_distance(&a, &b);
+
+        let trk = GpxTrack {
+            segments: vec![GpxSegment {
+                points: vec![a, b],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let cropped =
+            crop_track_by_distance(&trk, len * 0.25, len * 0.75, DistanceFormula::Haversine);
+        let pts = &cropped.segments[0].points;
+
+        assert_eq!(pts.len(), 2);
+        assert!((pts[0].lon - 0.0025).abs() < 1e-9);
+        assert!((pts[1].lon - 0.0075).abs() < 1e-9);
+        assert!((pts[0].ele.unwrap() - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_crop_spans_segments() {
+        let trk = GpxTrack {
+            segments: vec![
+                GpxSegment {
+                    points: vec![GpxPoint::new(0.0, 0.0), GpxPoint::new(0.0, 0.01)],
+                    ..Default::default()
+                },
+                GpxSegment {
+                    points: vec![GpxPoint::new(1.0, 0.0), GpxPoint::new(1.0, 0.01)],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        // First segment is ~1112 m long; start cropping past its end
+        let cropped = crop_track_by_distance(&trk, 1500.0, 10_000.0, DistanceFormula::Haversine);
+        assert!(cropped.segments[0].points.is_empty());
+        assert_eq!(cropped.segments[1].points.len(), 2);
+    }
+}
@@ -1,25 +1,155 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::datetime;
+
 /// Parsed GPX data containing all waypoints, routes, and tracks.
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GpxData {
+    /// `version` attribute of the root <gpx> element ("1.0", "1.1").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<GpxMetadata>,
     pub waypoints: Vec<GpxPoint>,
     pub routes: Vec<GpxRoute>,
     pub tracks: Vec<GpxTrack>,
+    /// What did not make it into the data, counted when `includeDiagnostics` is on.
+    #[serde(skip)]
+    pub dropped: DroppedData,
+}
+
+/// Counts of the input that was read past without being kept.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DroppedData {
+    /// Points skipped for a missing or invalid lat/lon.
+    pub skipped_points: usize,
+    /// Points removed by the `minFixQuality` / `maxHdop` filters.
+    pub filtered_points: usize,
+    /// Elements skipped unread, by local name.
+    pub ignored_elements: BTreeMap<String, usize>,
+    /// Extension elements not carried into the output, by namespace prefix
+    /// (`""` for unprefixed elements).
+    pub dropped_extensions: BTreeMap<String, usize>,
+    /// References to undeclared entities, which expand to nothing, by name.
+    pub unknown_entities: BTreeMap<String, usize>,
+}
+
+/// The document-level <metadata> element.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpxMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keywords: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<GpxBounds>,
+}
+
+/// The <bounds> element of <metadata>.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpxBounds {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
 }
 
 /// A single GPX point (used for wpt, rtept, trkpt).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GpxPoint {
     pub lat: f64,
     pub lon: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ele: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cmt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub desc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub src: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sym: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub point_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub link: Option<GpxLink>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sat: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdop: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vdop: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pdop: Option<f64>,
+    /// Magnetic variation in degrees.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub magvar: Option<f64>,
+    /// Height of the geoid above the WGS84 ellipsoid, in meters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geoid_height: Option<f64>,
+    /// ID of the DGPS station used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dgps_id: Option<u32>,
+    /// Seconds since the last DGPS update.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_of_dgps_data: Option<f64>,
+    /// Speed in m/s (GPX 1.0 `<speed>`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+    /// Heading in degrees (GPX 1.0 `<course>`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub course: Option<f64>,
+    /// Heart rate in bpm (Garmin TrackPointExtension / Cluetrust gpxdata `hr`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heart_rate: Option<u32>,
+    /// Cadence in rpm (Garmin TrackPointExtension `cad`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cadence: Option<u32>,
+    /// Ambient temperature in °C (Garmin `atemp`, Cluetrust `temp`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    /// Cumulative distance in meters reported by the device (Cluetrust `distance`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<f64>,
+    /// Power in watts (bare `<power>` extension written by Strava and cycling computers).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power: Option<u32>,
+    /// Garmin `<gpxx:rpt>` shape points following this route point, as (lat, lon).
+    /// Only read when `expandRouteShapePoints` is on.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shape_points: Vec<(f64, f64)>,
+    /// Garmin `<gpxx:WaypointExtension>` details (waypoints only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub garmin_waypoint: Option<Box<GarminWaypoint>>,
+    /// Text of point child elements requested via `extraPointElements`, and of
+    /// extension elements matched by `extensionMappings` (keyed by property name).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
+    /// Numeric extension values found by `autoExtensions`, keyed by local name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub extension_values: BTreeMap<String, f64>,
+    /// Children of <extensions>, kept when `includeExtensions` is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<XmlElement>>,
+    /// Whether the element had an <extensions> child.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub had_extensions: bool,
 }
 
 impl GpxPoint {
@@ -36,46 +166,209 @@ impl GpxPoint {
             sym: None,
             point_type: None,
             link: None,
+            fix: None,
+            sat: None,
+            hdop: None,
+            vdop: None,
+            pdop: None,
+            magvar: None,
+            geoid_height: None,
+            dgps_id: None,
+            age_of_dgps_data: None,
+            speed: None,
+            course: None,
+            heart_rate: None,
+            cadence: None,
+            temperature: None,
+            distance: None,
+            power: None,
+            shape_points: Vec::new(),
+            garmin_waypoint: None,
+            extra: BTreeMap::new(),
+            extension_values: BTreeMap::new(),
+            extensions: None,
+            had_extensions: false,
         }
     }
+
+    /// `time` as seconds since the Unix epoch, or `None` if absent or unparsable.
+    pub fn timestamp(&self) -> Option<f64> {
+        datetime::parse_timestamp(self.time.as_deref()?)
+    }
+
+    /// `time` as an `OffsetDateTime` in the zone offset it was written with.
+    /// Sub-second precision is kept to the microsecond.
+    #[cfg(feature = "time")]
+    pub fn date_time(&self) -> Option<time::OffsetDateTime> {
+        let (secs, offset) = datetime::parse_timestamp_with_offset(self.time.as_deref()?)?;
+        let micros = (secs * 1e6).round() as i128;
+        let offset = time::UtcOffset::from_whole_seconds(offset as i32).ok()?;
+        let utc = time::OffsetDateTime::from_unix_timestamp_nanos(micros * 1000).ok()?;
+        Some(utc.to_offset(offset))
+    }
+}
+
+/// POI details from a Garmin `<gpxx:WaypointExtension>` (BaseCamp).
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GarminWaypoint {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<GarminAddress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    /// Proximity alarm radius in meters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proximity: Option<f64>,
+    /// Water depth in meters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<f64>,
+}
+
+/// The `<gpxx:Address>` of a Garmin waypoint.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GarminAddress {
+    /// `<gpxx:StreetAddress>` lines, joined with ", ".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
 }
 
-/// A GPX link element.
-#[derive(Debug, Clone)]
+/// A generic XML element captured from an <extensions> subtree.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XmlElement {
+    /// Local name (namespace prefix stripped).
+    pub name: String,
+    /// Namespace prefix as written in the document (`gpxtpx`), if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    pub attributes: Vec<(String, String)>,
+    /// Trimmed text content.
+    pub text: String,
+    pub children: Vec<XmlElement>,
+}
+
+/// A GPX link element (GPX 1.1 <link>, or GPX 1.0 <url>/<urlname>).
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GpxLink {
     pub href: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub link_type: Option<String>,
 }
 
 /// A GPX route (<rte>).
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GpxRoute {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cmt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub desc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub src: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub link: Option<GpxLink>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub number: Option<u32>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub route_type: Option<String>,
     pub points: Vec<GpxPoint>,
+    /// Garmin `<gpxx:DisplayColor>` name from <extensions>.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_color: Option<String>,
+    /// Children of <extensions>, kept when `includeExtensions` is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<XmlElement>>,
+    /// Whether the element had an <extensions> child.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub had_extensions: bool,
+    /// Text of extension elements matched by `extensionMappings`, keyed by property name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
 }
 
 /// A GPX track (<trk>).
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GpxTrack {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cmt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub desc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub src: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub link: Option<GpxLink>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub number: Option<u32>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub track_type: Option<String>,
     pub segments: Vec<GpxSegment>,
+    /// Garmin `<gpxx:DisplayColor>` name from <extensions>.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_color: Option<String>,
+    /// Children of <extensions>, kept when `includeExtensions` is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<XmlElement>>,
+    /// Whether the element had an <extensions> child.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub had_extensions: bool,
+    /// Text of extension elements matched by `extensionMappings`, keyed by property name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
 }
 
 /// A GPX track segment (<trkseg>).
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GpxSegment {
     pub points: Vec<GpxPoint>,
+    /// Children of <extensions> (e.g. lap triggers), kept when `includeExtensions` is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<XmlElement>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::ConvertOptions;
+    use crate::parser;
+
+    #[test]
+    fn test_serialize_gpx_data() {
+        let xml = r#"<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><name>Start</name><type>Flag</type></wpt>
+  <trk><name>Run</name><trkseg><trkpt lat="35.1" lon="139.1"><ele>12.5</ele></trkpt></trkseg></trk>
+</gpx>"#;
+        let data = parser::parse_gpx_with_options(xml, &ConvertOptions::default()).unwrap();
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "version": "1.1",
+                "waypoints": [{ "lat": 35.0, "lon": 139.0, "name": "Start", "type": "Flag" }],
+                "routes": [],
+                "tracks": [{
+                    "name": "Run",
+                    "segments": [{ "points": [{ "lat": 35.1, "lon": 139.1, "ele": 12.5 }] }]
+                }]
+            })
+        );
+    }
 }
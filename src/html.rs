@@ -0,0 +1,200 @@
+/// Best-effort conversion of an HTML fragment to plain text.
+///
+/// Tags are removed and their text kept; `<br>` and block-level tags become
+/// line breaks, `<script>` / `<style>` contents are dropped, and the common
+/// named and numeric character references are decoded. A `<` that does not
+/// start a tag (e.g. `a < b`) is kept as is.
+pub fn strip_html(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('<') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let starts_tag = after
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        let Some(end) = after.find('>').filter(|_| starts_tag) else {
+            out.push('<');
+            rest = after;
+            continue;
+        };
+
+        let tag = &after[..end];
+        rest = &after[end + 1..];
+        let name = tag_name(tag);
+        if !tag.starts_with('/') && matches!(name.as_str(), "script" | "style") {
+            // Skip to the matching close tag
+            let close = format!("</{name}");
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(i) => rest[i..].find('>').map_or("", |j| &rest[i + j + 1..]),
+                None => "",
+            };
+        } else if is_line_break(&name) && !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    out.push_str(rest);
+
+    decode_entities(out.trim())
+}
+
+/// Lowercased element name of a tag body (`/p`, `br /`, `a href=".."`).
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn is_line_break(name: &str) -> bool {
+    matches!(
+        name,
+        "br" | "p" | "div" | "li" | "tr" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+    )
+}
+
+/// Decode `&amp;`-style named references and `&#..;` numeric references.
+/// Unknown references are left untouched.
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(pos) = rest.find('&') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let decoded = after.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let name = &after[..end];
+            let c = match name {
+                "nbsp" => ' ',
+                _ if name.starts_with('#') => {
+                    let code = match name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => name.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                    };
+                    code.and_then(char::from_u32)?
+                }
+                _ => named_entity(name)?,
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Names of the HTML entities for U+00A0 to U+00FF, in code point order.
+const LATIN1_ENTITIES: [&str; 96] = [
+    "nbsp", "iexcl", "cent", "pound", "curren", "yen", "brvbar", "sect", "uml", "copy", "ordf",
+    "laquo", "not", "shy", "reg", "macr", "deg", "plusmn", "sup2", "sup3", "acute", "micro",
+    "para", "middot", "cedil", "sup1", "ordm", "raquo", "frac14", "frac12", "frac34", "iquest",
+    "Agrave", "Aacute", "Acirc", "Atilde", "Auml", "Aring", "AElig", "Ccedil", "Egrave", "Eacute",
+    "Ecirc", "Euml", "Igrave", "Iacute", "Icirc", "Iuml", "ETH", "Ntilde", "Ograve", "Oacute",
+    "Ocirc", "Otilde", "Ouml", "times", "Oslash", "Ugrave", "Uacute", "Ucirc", "Uuml", "Yacute",
+    "THORN", "szlig", "agrave", "aacute", "acirc", "atilde", "auml", "aring", "aelig", "ccedil",
+    "egrave", "eacute", "ecirc", "euml", "igrave", "iacute", "icirc", "iuml", "eth", "ntilde",
+    "ograve", "oacute", "ocirc", "otilde", "ouml", "divide", "oslash", "ugrave", "uacute", "ucirc",
+    "uuml", "yacute", "thorn", "yuml",
+];
+
+/// The character of an HTML named entity such as `eacute`, `deg` or `mdash`.
+///
+/// Covers the XML entities, all of Latin-1, and the punctuation, symbols
+/// and arrows common in descriptions written by web tools.
+pub fn named_entity(name: &str) -> Option<char> {
+    if let Some(i) = LATIN1_ENTITIES.iter().position(|&entity| entity == name) {
+        return char::from_u32(0xA0 + i as u32);
+    }
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "OElig" => 'Œ',
+        "oelig" => 'œ',
+        "Scaron" => 'Š',
+        "scaron" => 'š',
+        "Yuml" => 'Ÿ',
+        "fnof" => 'ƒ',
+        "circ" => 'ˆ',
+        "tilde" => '˜',
+        "ensp" => '\u{2002}',
+        "emsp" => '\u{2003}',
+        "thinsp" => '\u{2009}',
+        "zwnj" => '\u{200C}',
+        "zwj" => '\u{200D}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "sbquo" => '‚',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "bdquo" => '„',
+        "dagger" => '†',
+        "Dagger" => '‡',
+        "bull" => '•',
+        "hellip" => '…',
+        "permil" => '‰',
+        "prime" => '′',
+        "Prime" => '″',
+        "lsaquo" => '‹',
+        "rsaquo" => '›',
+        "euro" => '€',
+        "trade" => '™',
+        "larr" => '←',
+        "uarr" => '↑',
+        "rarr" => '→',
+        "darr" => '↓',
+        "harr" => '↔',
+        "minus" => '−',
+        "le" => '≤',
+        "ge" => '≥',
+        "ne" => '≠',
+        "asymp" => '≈',
+        "infin" => '∞',
+        _ => return None,
+    };
+    Some(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_html() {
+        assert_eq!(
+            strip_html("<p>Climb to the <b>summit</b></p><p>Then descend</p>"),
+            "Climb to the summit\nThen descend"
+        );
+        assert_eq!(strip_html("Line 1<br/>Line 2"), "Line 1\nLine 2");
+        assert_eq!(
+            strip_html(r#"<a href="https://example.com">Link</a>"#),
+            "Link"
+        );
+        assert_eq!(
+            strip_html("<style>p { color: red }</style>Text<script>alert(1)</script>"),
+            "Text"
+        );
+        assert_eq!(strip_html("a < b &amp;&nbsp;c &#8594; d"), "a < b & c → d");
+        assert_eq!(strip_html("Plain text"), "Plain text");
+        assert_eq!(
+            strip_html("Tom &amp Jerry &unknown;"),
+            "Tom &amp Jerry &unknown;"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use quick_xml::NsReader;
+use quick_xml::events::{BytesStart, Event};
+use serde::Serialize;
+
+use crate::config;
+use crate::error::Gpx2GeoJsonError;
+use crate::parser::{check_element_limits, gpx_name};
+
+type Result<T> = std::result::Result<T, Gpx2GeoJsonError>;
+
+/// Result of `getGpxInfo`: what a file picker shows before converting.
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpxInfo {
+    /// The `version` attribute of the <gpx> root.
+    pub version: Option<String>,
+    /// The `creator` attribute of the <gpx> root, e.g. the device or app.
+    pub creator: Option<String>,
+    pub has_metadata: bool,
+    pub track_count: usize,
+    pub route_count: usize,
+    pub waypoint_count: usize,
+}
+
+/// Summarize a GPX document from the <gpx> root and its direct children.
+///
+/// The contents of each child are skipped unparsed, and the scan stops at
+/// the end of the root, so this is much cheaper than a conversion. The
+/// counts include elements a conversion would drop, e.g. an empty <trk>.
+pub fn gpx_info(xml: &str) -> Result<GpxInfo> {
+    let mut reader = NsReader::from_str(xml);
+    let limits = config::limits();
+    let mut info = GpxInfo::default();
+    let mut in_gpx = false;
+
+    loop {
+        let (e, empty) = match reader.read_event()? {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(_) if in_gpx => break,
+            Event::Eof => break,
+            _ => continue,
+        };
+        check_element_limits(&limits, &reader, &e)?;
+        match gpx_name(&reader, &e) {
+            b"gpx" if !in_gpx => {
+                info.version = attribute(&e, b"version");
+                info.creator = attribute(&e, b"creator");
+                if empty {
+                    break;
+                }
+                in_gpx = true;
+                continue;
+            }
+            b"metadata" if in_gpx => info.has_metadata = true,
+            b"trk" if in_gpx => info.track_count += 1,
+            b"rte" if in_gpx => info.route_count += 1,
+            b"wpt" if in_gpx => info.waypoint_count += 1,
+            _ => {}
+        }
+        if !empty {
+            reader.read_to_end(e.name())?;
+        }
+    }
+    Ok(info)
+}
+
+fn attribute(e: &BytesStart<'_>, name: &[u8]) -> Option<String> {
+    let attr = e
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.local_name().as_ref() == name)?;
+    Some(match attr.unescape_value() {
+        Ok(value) => value.into_owned(),
+        Err(_) => String::from_utf8_lossy(&attr.value).into_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpx_info() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" creator="Garmin Connect &amp; Co">
+  <metadata><name>Ride</name></metadata>
+  <wpt lat="35.0" lon="139.0"><name>A</name></wpt>
+  <wpt lat="35.1" lon="139.1"/>
+  <rte><rtept lat="35.0" lon="139.0"/></rte>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/></trkseg></trk>
+  <trk/>
+</gpx>"#;
+        assert_eq!(
+            gpx_info(xml).unwrap(),
+            GpxInfo {
+                version: Some("1.1".to_string()),
+                creator: Some("Garmin Connect & Co".to_string()),
+                has_metadata: true,
+                track_count: 2,
+                route_count: 1,
+                waypoint_count: 2,
+            }
+        );
+
+        // Nested elements with the same names don't count
+        let info = gpx_info("<gpx><extensions><trk/><wpt/></extensions></gpx>").unwrap();
+        assert_eq!((info.track_count, info.waypoint_count), (0, 0));
+        assert!(gpx_info("<gpx><trk></gpx>").is_err());
+    }
+}
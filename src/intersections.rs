@@ -0,0 +1,229 @@
+use geojson::{Feature, Geometry, Value};
+use serde_json::{Map, Value as JsonValue};
+
+/// For `detectSelfIntersections`: add a `selfIntersections` count to each
+/// track Feature in `features` and return a "selfIntersection" Point
+/// Feature for every place where a track crosses itself.
+pub fn self_intersection_features(features: &mut [Feature]) -> Vec<Feature> {
+    let mut points = Vec::new();
+    for feature in features {
+        let lines = match feature.geometry.as_ref().map(|g| &g.value) {
+            Some(Value::LineString(line)) => vec![line.as_slice()],
+            Some(Value::MultiLineString(lines)) => lines.iter().map(Vec::as_slice).collect(),
+            _ => continue,
+        };
+        let Some(props) = feature.properties.as_mut() else {
+            continue;
+        };
+        if props.get("gpxType").and_then(JsonValue::as_str) != Some("track") {
+            continue;
+        }
+
+        let crossings = self_intersections(&lines);
+        props.insert("selfIntersections".to_string(), crossings.len().into());
+        let name = props.get("name").cloned();
+        for [lon, lat] in crossings {
+            let mut point_props = Map::new();
+            point_props.insert(
+                "gpxType".to_string(),
+                JsonValue::String("selfIntersection".to_string()),
+            );
+            if let Some(name) = &name {
+                point_props.insert("track".to_string(), name.clone());
+            }
+            points.push(Feature {
+                bbox: None,
+                geometry: Some(Geometry::new(Value::Point(vec![lon, lat]))),
+                id: None,
+                properties: Some(point_props),
+                foreign_members: None,
+            });
+        }
+    }
+    points
+}
+
+/// `[lon, lat]` of every point where two edges of `lines` properly cross.
+///
+/// Edges that only touch (at a shared vertex, or running along each other,
+/// as on an out-and-back route) don't count. Coordinates are treated as
+/// planar, which is accurate enough for the short edges of a track. Edges
+/// are bucketed in a uniform grid about one edge long per cell, so only
+/// edges sharing a cell are compared, whatever direction the track runs in.
+pub fn self_intersections(lines: &[&[Vec<f64>]]) -> Vec<[f64; 2]> {
+    let edges: Vec<[[f64; 2]; 2]> = lines
+        .iter()
+        .flat_map(|line| line.windows(2))
+        .map(|w| [[w[0][0], w[0][1]], [w[1][0], w[1][1]]])
+        .collect();
+    let Some(grid) = Grid::new(&edges) else {
+        return Vec::new();
+    };
+    let ranges: Vec<CellRange> = edges.iter().map(|e| grid.range(e)).collect();
+    let mut cells: Vec<Vec<usize>> = vec![Vec::new(); grid.cols * grid.rows];
+    for (i, range) in ranges.iter().enumerate() {
+        for row in range.rows[0]..=range.rows[1] {
+            for col in range.cols[0]..=range.cols[1] {
+                cells[row * grid.cols + col].push(i);
+            }
+        }
+    }
+
+    let mut crossings = Vec::new();
+    for (cell, members) in cells.iter().enumerate() {
+        for (k, &i) in members.iter().enumerate() {
+            for &j in &members[k + 1..] {
+                let Some(point) = crossing(&edges[i], &edges[j]) else {
+                    continue;
+                };
+                // A pair sharing several cells is reported only in the cell
+                // of its crossing point
+                let shared = ranges[i].intersect(&ranges[j]);
+                let col = grid.col(point[0]).clamp(shared.cols[0], shared.cols[1]);
+                let row = grid.row(point[1]).clamp(shared.rows[0], shared.rows[1]);
+                if row * grid.cols + col == cell {
+                    crossings.push(point);
+                }
+            }
+        }
+    }
+    crossings
+}
+
+/// A uniform grid over the extent of a set of edges.
+struct Grid {
+    min: [f64; 2],
+    cell: f64,
+    cols: usize,
+    rows: usize,
+}
+
+/// The first and last column and row of the cells an edge's extent covers.
+struct CellRange {
+    cols: [usize; 2],
+    rows: [usize; 2],
+}
+
+impl CellRange {
+    fn intersect(&self, other: &CellRange) -> CellRange {
+        CellRange {
+            cols: [
+                self.cols[0].max(other.cols[0]),
+                self.cols[1].min(other.cols[1]),
+            ],
+            rows: [
+                self.rows[0].max(other.rows[0]),
+                self.rows[1].min(other.rows[1]),
+            ],
+        }
+    }
+}
+
+impl Grid {
+    /// Cells the size of the average edge, but no more cells than edges
+    /// for the area covered. `None` with fewer than 2 edges.
+    fn new(edges: &[[[f64; 2]; 2]]) -> Option<Self> {
+        if edges.len() < 2 {
+            return None;
+        }
+        let (mut min, mut max) = ([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]);
+        for p in edges.iter().flatten() {
+            for k in 0..2 {
+                min[k] = min[k].min(p[k]);
+                max[k] = max[k].max(p[k]);
+            }
+        }
+        let n = edges.len() as f64;
+        let mean_extent = edges
+            .iter()
+            .map(|e| (e[1][0] - e[0][0]).abs().max((e[1][1] - e[0][1]).abs()))
+            .sum::<f64>()
+            / n;
+        let (width, height) = (max[0] - min[0], max[1] - min[1]);
+        let cell = mean_extent
+            .max((width * height / n).sqrt())
+            .max(f64::MIN_POSITIVE);
+        let count = |size: f64| ((size / cell) as usize).saturating_add(1).min(edges.len());
+        Some(Self {
+            min,
+            cell,
+            cols: count(width),
+            rows: count(height),
+        })
+    }
+
+    fn col(&self, x: f64) -> usize {
+        (((x - self.min[0]) / self.cell) as usize).min(self.cols - 1)
+    }
+
+    fn row(&self, y: f64) -> usize {
+        (((y - self.min[1]) / self.cell) as usize).min(self.rows - 1)
+    }
+
+    fn range(&self, e: &[[f64; 2]; 2]) -> CellRange {
+        let cols = [self.col(e[0][0]), self.col(e[1][0])];
+        let rows = [self.row(e[0][1]), self.row(e[1][1])];
+        CellRange {
+            cols: [cols[0].min(cols[1]), cols[0].max(cols[1])],
+            rows: [rows[0].min(rows[1]), rows[0].max(rows[1])],
+        }
+    }
+}
+
+/// The point where edges `a` and `b` cross, if their interiors intersect.
+fn crossing(a: &[[f64; 2]; 2], b: &[[f64; 2]; 2]) -> Option<[f64; 2]> {
+    let orient = |p: [f64; 2], q: [f64; 2], r: [f64; 2]| {
+        (q[0] - p[0]) * (r[1] - p[1]) - (q[1] - p[1]) * (r[0] - p[0])
+    };
+    let (d1, d2) = (orient(a[0], a[1], b[0]), orient(a[0], a[1], b[1]));
+    let (d3, d4) = (orient(b[0], b[1], a[0]), orient(b[0], b[1], a[1]));
+    if d1 * d2 >= 0.0 || d3 * d4 >= 0.0 {
+        return None;
+    }
+    let t = d3 / (d3 - d4);
+    Some([
+        a[0][0] + (a[1][0] - a[0][0]) * t,
+        a[0][1] + (a[1][1] - a[0][1]) * t,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(coords: &[[f64; 2]]) -> Vec<Vec<f64>> {
+        coords.iter().map(|c| c.to_vec()).collect()
+    }
+
+    #[test]
+    fn test_self_intersections() {
+        // A figure eight crossing at (1, 1)
+        let eight = line(&[[0.0, 0.0], [2.0, 2.0], [2.0, 0.0], [0.0, 2.0]]);
+        assert_eq!(self_intersections(&[&eight]), vec![[1.0, 1.0]]);
+
+        // Out and back along the same path, and a closed loop
+        let out_and_back = line(&[[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [1.0, 0.0], [0.0, 0.0]]);
+        assert!(self_intersections(&[&out_and_back]).is_empty());
+        let square = line(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0], [0.0, 0.0]]);
+        assert!(self_intersections(&[&square]).is_empty());
+
+        // Segments of a MultiLineString crossing each other
+        let a = line(&[[0.0, 0.0], [2.0, 0.0]]);
+        let b = line(&[[1.0, -1.0], [1.0, 1.0]]);
+        assert_eq!(self_intersections(&[&a, &b]), vec![[1.0, 0.0]]);
+    }
+
+    #[test]
+    fn test_self_intersections_meridian_track() {
+        // 50 km north along a meridian, then back zigzagging across the
+        // outbound line once per edge
+        let n = 50_000;
+        let step = 1e-5;
+        let mut track: Vec<Vec<f64>> = (0..n).map(|i| vec![0.0, i as f64 * step]).collect();
+        track.extend((0..n).rev().map(|k| {
+            let lon = if k % 2 == 0 { 1e-4 } else { -3e-4 };
+            vec![lon, (k as f64 + 0.5) * step]
+        }));
+        assert_eq!(self_intersections(&[&track]).len(), n - 1);
+    }
+}
@@ -1,40 +1,389 @@
+pub mod activity;
+#[cfg(feature = "zip")]
+pub mod archive;
+pub mod blob;
+#[cfg(feature = "gzip")]
+pub mod compress;
+pub mod config;
 pub mod converter;
+pub mod datetime;
+pub mod deviation;
+pub mod encoding;
 pub mod error;
+pub mod fetch;
+pub mod filter;
+pub mod geo;
 pub mod gpx_types;
+pub mod html;
+pub mod info;
+pub mod intersections;
+pub mod logging;
 pub mod options;
 pub mod parser;
+pub mod preview;
+pub mod report;
+pub mod rfc7946;
+pub mod stats;
+pub mod style;
+pub mod summary;
+pub mod validate;
+pub mod viewport;
 
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value as JsonValue};
 use wasm_bindgen::prelude::*;
 
+use geojson::{FeatureCollection, GeoJson};
+
+use crate::config::InitConfig;
 use crate::error::Gpx2GeoJsonError;
-use crate::options::ConvertOptions;
+use crate::gpx_types::GpxData;
+use crate::options::{ConvertOptions, DeviationOptions, PreviewOptions, ViewportOptions};
+
+/// Configure the module once at startup: panic hook, log level, default
+/// options and resource limits. Calling it is optional; the first API call
+/// otherwise applies the defaults (panic hook on, logging off, no limits).
+#[wasm_bindgen]
+pub fn init(config: JsValue) -> Result<(), JsValue> {
+    let config: InitConfig = parse_options(config)?;
+    if let Some(level) = &config.log_level {
+        init_logging(level)?;
+    }
+    config::init(config);
+    Ok(())
+}
 
 /// Convert GPX string to GeoJSON, returned as a JS object.
 #[wasm_bindgen(js_name = gpxToGeoJson)]
 pub fn gpx_to_geojson(gpx_string: &str, options: JsValue) -> Result<JsValue, JsValue> {
-    console_error_panic_hook::set_once();
+    config::ensure_init();
 
-    let opts = parse_options(options)?;
-    let gpx_data = parser::parse_gpx(gpx_string).map_err(Gpx2GeoJsonError::from)?;
-    let fc = converter::to_feature_collection(&gpx_data, &opts);
-    serde_wasm_bindgen::to_value(&fc).map_err(|e| JsValue::from_str(&e.to_string()))
+    let opts = parse_convert_options(options)?;
+    config::check_input_size(gpx_string.len())?;
+    let gpx_data = parse(gpx_string, &opts)?;
+    let output = convert(&gpx_data, &opts)?;
+    serde_wasm_bindgen::to_value(&output).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Convert GPX to one FeatureCollection per element type:
+/// `{ waypoints, routes, tracks }`.
+#[wasm_bindgen(js_name = gpxToGeoJsonByType)]
+pub fn gpx_to_geojson_by_type(gpx_string: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    let opts = parse_convert_options(options)?;
+    config::check_input_size(gpx_string.len())?;
+    let gpx_data = parse(gpx_string, &opts)?;
+    let by_type = converter::to_feature_collections_by_type(&gpx_data, &opts);
+    for fc in [&by_type.waypoints, &by_type.routes, &by_type.tracks] {
+        check_output(fc, &opts)?;
+    }
+    serde_wasm_bindgen::to_value(&by_type).map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
 /// Convert GPX string to GeoJSON, returned as a JSON string.
 #[wasm_bindgen(js_name = gpxToGeoJsonString)]
 pub fn gpx_to_geojson_string(gpx_string: &str, options: JsValue) -> Result<String, JsValue> {
-    console_error_panic_hook::set_once();
+    config::ensure_init();
 
-    let opts = parse_options(options)?;
-    let gpx_data = parser::parse_gpx(gpx_string).map_err(Gpx2GeoJsonError::from)?;
-    let fc = converter::to_feature_collection(&gpx_data, &opts);
-    serde_json::to_string(&fc).map_err(|e| JsValue::from_str(&e.to_string()))
+    let opts = parse_convert_options(options)?;
+    config::check_input_size(gpx_string.len())?;
+    let gpx_data = parse(gpx_string, &opts)?;
+    let output = convert(&gpx_data, &opts)?;
+    serde_json::to_string(&output).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Convert GPX string to GeoJSON, returned as gzip-compressed JSON bytes
+/// (a `Uint8Array`) ready to upload without a separate compression step.
+#[cfg(feature = "gzip")]
+#[wasm_bindgen(js_name = gpxToGeoJsonGzip)]
+pub fn gpx_to_geojson_gzip(gpx_string: &str, options: JsValue) -> Result<Vec<u8>, JsValue> {
+    config::ensure_init();
+
+    let opts = parse_convert_options(options)?;
+    config::check_input_size(gpx_string.len())?;
+    let gpx_data = parse(gpx_string, &opts)?;
+    let output = convert(&gpx_data, &opts)?;
+    compress::to_gzip_json(&output).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Convert every `.gpx` file in a ZIP archive (e.g. a bulk activity export)
+/// into one FeatureCollection, returned as a JS object. Each feature gets a
+/// `sourceFile` property with its path in the archive.
+#[cfg(feature = "zip")]
+#[wasm_bindgen(js_name = gpxZipToGeoJson)]
+pub fn gpx_zip_to_geojson(bytes: &[u8], options: JsValue) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    let opts = parse_convert_options(options)?;
+    config::check_input_size(bytes.len())?;
+    let fc = archive::zip_to_feature_collection(bytes, &opts)?;
+    check_output(&fc, &opts)?;
+    serde_wasm_bindgen::to_value(&converter::into_output(fc, &opts))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Convert GPX string to GeoJSON like `gpxToGeoJson`, returned as
+/// `{ geojson, warnings }` with a warning for each kind of input that was
+/// skipped or lost, e.g. points without a valid lat/lon.
+#[wasm_bindgen(js_name = gpxToGeoJsonWithReport)]
+pub fn gpx_to_geojson_with_report(gpx_string: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    let opts = parse_convert_options(options)?;
+    config::check_input_size(gpx_string.len())?;
+    let counting = ConvertOptions {
+        include_diagnostics: true,
+        ..opts.clone()
+    };
+    let gpx_data = parse(gpx_string, &counting)?;
+    let filtered_points = if filter::is_enabled(&opts) {
+        filter::count_filtered(&gpx_data, &opts)
+    } else {
+        0
+    };
+    let report = report::ConversionReport {
+        geojson: convert(&gpx_data, &opts)?,
+        warnings: report::conversion_warnings(&gpx_data.dropped, filtered_points),
+    };
+    serde_wasm_bindgen::to_value(&report).map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
-fn parse_options(options: JsValue) -> Result<ConvertOptions, JsValue> {
+/// Convert the raw bytes of a GPX file (e.g. a `FileReader` ArrayBuffer) to
+/// GeoJSON, returned as a JS object. UTF-8 and UTF-16 are detected from the
+/// byte order mark, so callers don't need to decode the file to a JS string.
+#[wasm_bindgen(js_name = gpxBytesToGeoJson)]
+pub fn gpx_bytes_to_geojson(bytes: &[u8], options: JsValue) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    let opts = parse_convert_options(options)?;
+    config::check_input_size(bytes.len())?;
+    let gpx_string = encoding::decode(bytes)?;
+    let gpx_data = parse(&gpx_string, &opts)?;
+    let output = convert(&gpx_data, &opts)?;
+    serde_wasm_bindgen::to_value(&output).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Convert a `File` or `Blob` (e.g. from `<input type="file">`) to GeoJSON,
+/// resolving to a JS object. The contents are read like `gpxBytesToGeoJson`,
+/// without going through a JS string.
+#[wasm_bindgen(js_name = gpxFileToGeoJson)]
+pub async fn gpx_file_to_geojson(file: blob::Blob, options: JsValue) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    let bytes = blob::read_blob(&file).await?;
+    gpx_bytes_to_geojson(&bytes, options)
+}
+
+/// Convert a base64-encoded GPX file, or a `data:` URL with base64 content
+/// (as handed over by mobile webviews and the clipboard), to GeoJSON,
+/// returned as a JS object. The decoded bytes are read like `gpxBytesToGeoJson`.
+#[wasm_bindgen(js_name = gpxBase64ToGeoJson)]
+pub fn gpx_base64_to_geojson(data: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    let opts = parse_convert_options(options)?;
+    config::check_input_size(data.len() / 4 * 3)?;
+    let bytes = encoding::decode_base64(data)?;
+    let gpx_string = encoding::decode(&bytes)?;
+    let gpx_data = parse(&gpx_string, &opts)?;
+    let output = convert(&gpx_data, &opts)?;
+    serde_wasm_bindgen::to_value(&output).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Convert a GPX file fed in chunks, e.g. from `File.stream()`, without
+/// holding the whole document in memory. Chunks are UTF-8 bytes and may be
+/// split anywhere; `finish()` returns the GeoJSON like `gpxToGeoJson`.
+#[wasm_bindgen]
+pub struct GpxStreamParser {
+    parser: parser::StreamParser,
+    opts: ConvertOptions,
+    received: usize,
+}
+
+#[wasm_bindgen]
+impl GpxStreamParser {
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: JsValue) -> Result<GpxStreamParser, JsValue> {
+        config::ensure_init();
+
+        let opts = parse_convert_options(options)?;
+        Ok(Self {
+            parser: parser::StreamParser::new(opts.clone()),
+            opts,
+            received: 0,
+        })
+    }
+
+    /// Parse the next chunk of the file.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), JsValue> {
+        self.received += chunk.len();
+        config::check_input_size(self.received)?;
+        self.parser.push(chunk)?;
+        Ok(())
+    }
+
+    /// Parse the rest of the input and return the GeoJSON as a JS object.
+    pub fn finish(self) -> Result<JsValue, JsValue> {
+        let gpx_data = self.parser.finish()?;
+        let output = convert(&gpx_data, &self.opts)?;
+        serde_wasm_bindgen::to_value(&output).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+}
+
+/// Download a GPX file with `fetch()` and convert it, resolving to the
+/// GeoJSON as a JS object. The response body is parsed as it arrives, like
+/// `GpxStreamParser`, so it must be UTF-8.
+#[wasm_bindgen(js_name = gpxUrlToGeoJson)]
+pub async fn gpx_url_to_geojson(url: String, options: JsValue) -> Result<JsValue, JsValue> {
+    let mut parser = GpxStreamParser::new(options)?;
+    fetch::fetch_chunks(&url, |chunk| parser.push(chunk)).await?;
+    parser.finish()
+}
+
+/// Convert a `ReadableStream` of UTF-8 bytes (a fetch response body in a
+/// service worker, `Blob.stream()`, ...) chunk by chunk, resolving to the
+/// GeoJSON as a JS object. The stream is read like `GpxStreamParser`.
+#[wasm_bindgen(js_name = gpxStreamToGeoJson)]
+pub async fn gpx_stream_to_geojson(
+    stream: fetch::ReadableStream,
+    options: JsValue,
+) -> Result<JsValue, JsValue> {
+    let mut parser = GpxStreamParser::new(options)?;
+    fetch::read_chunks(&stream, |chunk| parser.push(chunk)).await?;
+    parser.finish()
+}
+
+/// Route parse/convert tracing to `console.debug/info/warn/error`.
+///
+/// `level` is one of "off", "error", "warn", "info", "debug" or "trace".
+#[wasm_bindgen(js_name = initLogging)]
+pub fn init_logging(level: &str) -> Result<(), JsValue> {
+    let filter = logging::parse_level(level)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown log level '{level}'")))?;
+    logging::init(filter);
+    Ok(())
+}
+
+/// Compare a recorded track against a planned route, returning off-route
+/// sections as Features and `onRoutePercentage` on the FeatureCollection.
+#[wasm_bindgen(js_name = gpxDeviation)]
+pub fn gpx_deviation(
+    track_gpx: &str,
+    route_gpx: &str,
+    options: JsValue,
+) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    let opts: DeviationOptions = parse_options(options)?;
+    config::check_input_size(track_gpx.len())?;
+    config::check_input_size(route_gpx.len())?;
+    let track = parse(track_gpx, &ConvertOptions::default())?;
+    let route = parse(route_gpx, &ConvertOptions::default())?;
+    let fc = deviation::to_deviation_collection(&track, &route, &opts)?;
+    serde_wasm_bindgen::to_value(&fc).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Build a single downsampled LineString Feature for list thumbnails,
+/// without running the full conversion.
+#[wasm_bindgen(js_name = gpxPreview)]
+pub fn gpx_preview(gpx_string: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    let opts: PreviewOptions = parse_options(options)?;
+    config::check_input_size(gpx_string.len())?;
+    let gpx_data = parse(gpx_string, &ConvertOptions::default())?;
+    let feature = preview::to_preview_feature(&gpx_data, &opts);
+    serde_wasm_bindgen::to_value(&feature).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Compute the Web Mercator center/zoom/scale that fits the converted
+/// features into an image of the requested pixel size. Returns `null` for
+/// files without any features.
+#[wasm_bindgen(js_name = gpxViewport)]
+pub fn gpx_viewport(gpx_string: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    let opts: ViewportOptions = parse_options(options)?;
+    config::check_input_size(gpx_string.len())?;
+    let gpx_data = parse(gpx_string, &ConvertOptions::default())?;
+    let fc = converter::to_feature_collection(&gpx_data, &ConvertOptions::default());
+    let viewport = viewport::fit_viewport(&fc.features, &opts);
+    serde_wasm_bindgen::to_value(&viewport).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Read the version, creator and element counts of a GPX string from its
+/// top-level elements, without parsing points.
+#[wasm_bindgen(js_name = getGpxInfo)]
+pub fn get_gpx_info(gpx_string: &str) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    config::check_input_size(gpx_string.len())?;
+    let info = info::gpx_info(gpx_string)?;
+    serde_wasm_bindgen::to_value(&info).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Check a GPX string without converting it: errors (XML and GPX 1.1),
+/// warnings, element counts and version.
+#[wasm_bindgen(js_name = validateGpx)]
+pub fn validate_gpx(gpx_string: &str) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    config::check_input_size(gpx_string.len())?;
+    let report = validate::validate_gpx(gpx_string);
+    serde_wasm_bindgen::to_value(&report).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Parse GPX without converting it, returning the waypoints, routes and
+/// tracks as plain JSON objects. Options that affect parsing (`types`,
+/// `includeExtensions`, filters, ...) apply.
+#[wasm_bindgen(js_name = gpxToJson)]
+pub fn gpx_to_json(gpx_string: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    config::ensure_init();
+
+    let opts = parse_convert_options(options)?;
+    config::check_input_size(gpx_string.len())?;
+    let gpx_data = parse(gpx_string, &opts)?;
+    // json_compatible: `extra` and `extensionValues` become objects, not Maps
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    serde::Serialize::serialize(&gpx_data, &serializer)
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Parse GPX, locating errors in `xml` for the JS error object.
+fn parse(xml: &str, opts: &ConvertOptions) -> Result<GpxData, JsValue> {
+    parser::parse_gpx_located(xml, opts).map_err(|e| e.into_js(xml))
+}
+
+/// Convert parsed GPX, checking the result when `validateOutput` is set.
+fn convert(data: &GpxData, opts: &ConvertOptions) -> Result<GeoJson, Gpx2GeoJsonError> {
+    let fc = converter::to_feature_collection(data, opts);
+    check_output(&fc, opts)?;
+    Ok(converter::into_output(fc, opts))
+}
+
+fn check_output(fc: &FeatureCollection, opts: &ConvertOptions) -> Result<(), Gpx2GeoJsonError> {
+    match opts.validate_output.and_then(|v| v.mode()) {
+        Some(mode) => validate::check_output(fc, mode),
+        None => Ok(()),
+    }
+}
+
+fn parse_options<T: DeserializeOwned + Default>(options: JsValue) -> Result<T, JsValue> {
     if options.is_undefined() || options.is_null() {
-        Ok(ConvertOptions::default())
+        Ok(T::default())
     } else {
         serde_wasm_bindgen::from_value(options).map_err(|e| JsValue::from_str(&e.to_string()))
     }
 }
+
+/// Parse ConvertOptions, filling unset fields from the `init()` defaults.
+fn parse_convert_options(options: JsValue) -> Result<ConvertOptions, JsValue> {
+    let overrides: JsonValue = if options.is_undefined() || options.is_null() {
+        JsonValue::Object(Map::new())
+    } else {
+        serde_wasm_bindgen::from_value(options).map_err(|e| JsValue::from_str(&e.to_string()))?
+    };
+    serde_json::from_value(config::with_default_options(overrides))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
@@ -1,5 +1,9 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 
+use crate::datetime;
+
 /// Options for GPX to GeoJSON conversion.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -20,9 +24,213 @@ pub struct ConvertOptions {
     #[serde(default)]
     pub types: Option<Vec<GpxElementType>>,
 
-    /// Join track segments into a single MultiLineString (default: false)
+    /// How many Features tracks become: "feature-per-segment", "feature-per-track" or "feature-per-file" (default: "feature-per-segment")
+    #[serde(default)]
+    pub track_mode: Option<TrackMode>,
+
+    /// Deprecated alias for `trackMode: "feature-per-track"` (default: false)
     #[serde(default)]
     pub join_track_segments: bool,
+
+    /// Deprecated alias for `trackMode: "feature-per-file"` (default: false)
+    #[serde(default)]
+    pub join_tracks: bool,
+
+    /// Keep only the part of each track between two distances along the line (default: none)
+    #[serde(default)]
+    pub crop_distance: Option<CropDistance>,
+
+    /// Extract the part of each track between two named waypoints as an extra Feature (default: none)
+    #[serde(default)]
+    pub section: Option<SectionOptions>,
+
+    /// Expand entities declared in the DOCTYPE internal subset (default: false)
+    #[serde(default)]
+    pub resolve_dtd_entities: bool,
+
+    /// Drop points whose <fix> is worse than this (default: none)
+    #[serde(default)]
+    pub min_fix_quality: Option<FixQuality>,
+
+    /// Drop points whose <hdop> is greater than this (default: none)
+    #[serde(default)]
+    pub max_hdop: Option<f64>,
+
+    /// Include per-point satellite counts and HDOP in coordinateProperties (default: false)
+    #[serde(default)]
+    pub include_fix_quality: bool,
+
+    /// Add a `summary` foreign member with totals across all features (default: false)
+    #[serde(default)]
+    pub include_summary: bool,
+
+    /// Point child elements to capture into coordinateProperties by name (default: none)
+    #[serde(default)]
+    pub extra_point_elements: Vec<String>,
+
+    /// Geometry used for routes (default: lineString)
+    #[serde(default)]
+    pub route_mode: RouteMode,
+
+    /// Split tracks and routes into separately numbered activities at recording gaps (default: none)
+    #[serde(default)]
+    pub split_activities: Option<ActivitySplitOptions>,
+
+    /// Copy <extensions> of waypoints, routes and tracks into properties.extensions (default: false)
+    #[serde(default)]
+    pub include_extensions: bool,
+
+    /// Color each track/route feature from a palette: `true` for the built-in one (default: none)
+    #[serde(default)]
+    pub auto_color: Option<AutoColor>,
+
+    /// Smallest possible output: geometries only, 2D, rounded and simplified (default: false)
+    #[serde(default)]
+    pub minimal: bool,
+
+    /// Tag features with sourceVersion and hadExtensions (default: false)
+    #[serde(default)]
+    pub include_source: bool,
+
+    /// Strict RFC 7946 output: no foreign members, antimeridian cutting (default: false)
+    #[serde(default)]
+    pub strict_rfc7946: bool,
+
+    /// Whether desc and cmt stay separate or merge into `description` (default: separate)
+    #[serde(default)]
+    pub description_mode: DescriptionMode,
+
+    /// Add a `geometryHash` property for change detection across re-conversions (default: false)
+    #[serde(default)]
+    pub include_geometry_hash: bool,
+
+    /// Read at most this many waypoints/routes/tracks, stopping the parse early (default: none)
+    #[serde(default)]
+    pub limit: Option<LimitOptions>,
+
+    /// Insert Garmin <gpxx:rpt> shape points between route points (default: false)
+    #[serde(default)]
+    pub expand_route_shape_points: bool,
+
+    /// Remove HTML tags from desc / cmt, keeping their text (default: false)
+    #[serde(default)]
+    pub strip_html: bool,
+
+    /// Extension elements to extract, as `{ "ns:tag" | "tag": "propertyName" }` (default: none)
+    #[serde(default)]
+    pub extension_mappings: BTreeMap<String, String>,
+
+    /// Collect every numeric point extension into a coordinateProperties array named after its tag (default: false)
+    #[serde(default)]
+    pub auto_extensions: bool,
+
+    /// Add a `diagnostics` foreign member with a `droppedDataReport` of what was lost (default: false)
+    #[serde(default)]
+    pub include_diagnostics: bool,
+
+    /// Add statistics (extent, duration, average speed, elevation range, steepest gradient) to track and route properties (default: false)
+    #[serde(default)]
+    pub include_stats: bool,
+
+    /// Distance over which `maxGradient` is measured, to suppress elevation noise (default: 100)
+    #[serde(default = "default_gradient_window_meters")]
+    pub gradient_window_meters: f64,
+
+    /// Drop waypoints farther than this many meters from every track and route (default: none)
+    #[serde(default)]
+    pub waypoints_near_tracks_only: Option<f64>,
+
+    /// Shift (or affine-transform) every coordinate before conversion (default: none)
+    #[serde(default)]
+    pub transform: Option<CoordinateTransform>,
+
+    /// Fail on GPX 1.1 violations (coordinate ranges, dateTime, element order) instead of skipping (default: false)
+    #[serde(default)]
+    pub strict: bool,
+
+    /// Formula for reported distances: "haversine" or "geodesic" (default: haversine)
+    #[serde(default)]
+    pub distance_formula: DistanceFormula,
+
+    /// Count the places where each track crosses itself and emit them as Point features (default: false)
+    #[serde(default)]
+    pub detect_self_intersections: bool,
+
+    /// Tag features with waypointIndex, routeIndex, trackIndex and segmentIndex (default: false)
+    #[serde(default)]
+    pub include_source_index: bool,
+
+    /// Check the output geometries before returning them (default: none)
+    #[serde(default)]
+    pub validate_output: Option<ValidateOutput>,
+
+    /// Fill FeatureCollection.bbox from the <bounds> in <metadata>: "bounds" or "checkedBounds" (default: none)
+    #[serde(default)]
+    pub bbox: Option<BboxSource>,
+
+    /// Accept lat/lon/ele values with surrounding whitespace, a leading '+' or a decimal comma (default: false)
+    #[serde(default)]
+    pub lenient_numbers: bool,
+
+    /// Trim name/desc/cmt/src and collapse whitespace runs inside them to one space (default: true)
+    #[serde(default = "default_true")]
+    pub trim_text: bool,
+
+    /// Treat <ele> values outside [min, max] meters as missing, e.g. [-500, 9000] (default: none)
+    #[serde(default)]
+    pub elevation_range: Option<[f64; 2]>,
+
+    /// How Feature.id is filled: "none", "index", "name" or "hash" (default: "none")
+    #[serde(default)]
+    pub feature_id: FeatureIdMode,
+
+    /// Where elevations go: "coordinate", "property" (coordinateProperties.elevations) or "both" (default: "coordinate")
+    #[serde(default)]
+    pub elevation_mode: ElevationMode,
+
+    /// Names of the sensor coordinateProperties arrays: "default" or "togeojson" (`cads`, `atemps`) (default: "default")
+    #[serde(default)]
+    pub sensor_naming: SensorNaming,
+
+    /// What is emitted: "features", "points" for one Point Feature per rtept/trkpt, or "geometries" for a bare GeometryCollection (default: "features")
+    #[serde(default)]
+    pub output: OutputMode,
+
+    /// Emit all waypoints as one MultiPoint Feature, with names/syms/descs in coordinateProperties (default: false)
+    #[serde(default)]
+    pub waypoints_as_multi_point: bool,
+
+    /// Give each track Feature a GeometryCollection of its line, start and end points and nearby waypoints (default: false)
+    #[serde(default)]
+    pub track_geometry_collection: bool,
+
+    /// Reproduce another converter's output conventions: "togeojson" (default: none)
+    #[serde(default)]
+    pub compat: Option<Compat>,
+
+    /// Format of the `time` property, coordinateProperties.times, the metadata time and summary.timeRange: "iso", "epochMs" or "epochS" (default: "iso")
+    #[serde(default)]
+    pub time_format: TimeFormat,
+
+    /// Rewrite ISO timestamps in properties and foreign members in this zone: an offset like "+09:00" or an IANA name without daylight saving time (default: none)
+    #[serde(default)]
+    pub timezone: Option<Timezone>,
+
+    /// Keep only these Feature properties, e.g. ["name", "time"] (default: all)
+    #[serde(default)]
+    pub include_properties: Option<Vec<String>>,
+
+    /// Drop these Feature properties, e.g. ["desc", "cmt"] (default: none)
+    #[serde(default)]
+    pub exclude_properties: Vec<String>,
+
+    /// Name of the property holding the element type, or null to leave it out (default: "gpxType")
+    #[serde(default = "default_gpx_type_key")]
+    pub gpx_type_key: Option<String>,
+
+    /// Cut lines where they cross the antimeridian into MultiLineStrings, as `strictRfc7946` does (default: false)
+    #[serde(default)]
+    pub split_antimeridian: bool,
 }
 
 impl Default for ConvertOptions {
@@ -32,7 +240,58 @@ impl Default for ConvertOptions {
             include_time: true,
             include_metadata: true,
             types: None,
+            track_mode: None,
             join_track_segments: false,
+            join_tracks: false,
+            crop_distance: None,
+            section: None,
+            resolve_dtd_entities: false,
+            min_fix_quality: None,
+            max_hdop: None,
+            include_fix_quality: false,
+            include_summary: false,
+            extra_point_elements: Vec::new(),
+            route_mode: RouteMode::default(),
+            split_activities: None,
+            include_extensions: false,
+            auto_color: None,
+            minimal: false,
+            include_source: false,
+            strict_rfc7946: false,
+            description_mode: DescriptionMode::default(),
+            include_geometry_hash: false,
+            limit: None,
+            expand_route_shape_points: false,
+            strip_html: false,
+            extension_mappings: BTreeMap::new(),
+            auto_extensions: false,
+            include_diagnostics: false,
+            include_stats: false,
+            gradient_window_meters: default_gradient_window_meters(),
+            waypoints_near_tracks_only: None,
+            transform: None,
+            strict: false,
+            distance_formula: DistanceFormula::default(),
+            detect_self_intersections: false,
+            include_source_index: false,
+            validate_output: None,
+            bbox: None,
+            lenient_numbers: false,
+            trim_text: true,
+            elevation_range: None,
+            feature_id: FeatureIdMode::default(),
+            elevation_mode: ElevationMode::default(),
+            sensor_naming: SensorNaming::default(),
+            output: OutputMode::default(),
+            waypoints_as_multi_point: false,
+            track_geometry_collection: false,
+            compat: None,
+            time_format: TimeFormat::default(),
+            timezone: None,
+            include_properties: None,
+            exclude_properties: Vec::new(),
+            gpx_type_key: default_gpx_type_key(),
+            split_antimeridian: false,
         }
     }
 }
@@ -44,9 +303,30 @@ impl ConvertOptions {
             Some(types) => types.contains(&element_type),
         }
     }
+
+    /// `trackMode`, falling back to the deprecated `joinTracks` and
+    /// `joinTrackSegments` booleans when it isn't set.
+    pub fn track_mode(&self) -> TrackMode {
+        match self.track_mode {
+            Some(mode) => mode,
+            None if self.join_tracks => TrackMode::FeaturePerFile,
+            None if self.join_track_segments => TrackMode::FeaturePerTrack,
+            None => TrackMode::FeaturePerSegment,
+        }
+    }
+
+    /// Whether elevations go in the 3rd coordinate value.
+    pub fn elevation_in_coordinates(&self) -> bool {
+        self.include_elevation && self.elevation_mode != ElevationMode::Property
+    }
+
+    /// Whether line features get a `coordinateProperties.elevations` array.
+    pub fn elevation_in_properties(&self) -> bool {
+        self.include_elevation && self.elevation_mode != ElevationMode::Coordinate
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum GpxElementType {
     Waypoint,
@@ -54,6 +334,386 @@ pub enum GpxElementType {
     Track,
 }
 
+/// How routes are turned into geometries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RouteMode {
+    /// One LineString per route.
+    #[default]
+    LineString,
+    /// One MultiPoint per route, with per-rtept names/syms/descs in coordinateProperties.
+    MultiPoint,
+}
+
+/// How the <desc> and <cmt> of an element are emitted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DescriptionMode {
+    /// Separate `desc` and `cmt` properties.
+    #[default]
+    Separate,
+    /// A single `description` property: desc and cmt joined by a newline (togeojson style).
+    Merged,
+}
+
+/// GPX <fix> values, ordered from worst to best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixQuality {
+    None,
+    #[serde(rename = "2d")]
+    TwoD,
+    #[serde(rename = "3d")]
+    ThreeD,
+    Dgps,
+    Pps,
+}
+
+impl FixQuality {
+    /// Parse the text content of a GPX <fix> element.
+    pub fn from_gpx(value: &str) -> Option<Self> {
+        match value.trim() {
+            "none" => Some(Self::None),
+            "2d" => Some(Self::TwoD),
+            "3d" => Some(Self::ThreeD),
+            "dgps" => Some(Self::Dgps),
+            "pps" => Some(Self::Pps),
+            _ => None,
+        }
+    }
+}
+
+/// How reported distances (summary, stats, sections, activities, crop) are measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DistanceFormula {
+    /// Great circle on a sphere: fast, within about 0.5% of the true distance.
+    #[default]
+    Haversine,
+    /// Shortest path on the WGS84 ellipsoid (Karney's algorithm).
+    Geodesic,
+}
+
+/// Distance range along a track, in meters from its first point.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CropDistance {
+    pub start_meters: f64,
+    pub end_meters: f64,
+}
+
+/// `transform` setting, e.g. to correct a known datum offset or to
+/// anonymize a track by translation.
+///
+/// `matrix` `[a, b, c, d, e, f]` maps `(lon, lat)` to
+/// `(a*lon + b*lat + c, d*lon + e*lat + f)`; the offsets are added after it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoordinateTransform {
+    #[serde(default)]
+    pub d_lat: f64,
+    #[serde(default)]
+    pub d_lon: f64,
+    /// Added to elevations, in meters; points without one are left without.
+    #[serde(default)]
+    pub d_ele: f64,
+    #[serde(default)]
+    pub matrix: Option<[f64; 6]>,
+}
+
+/// `autoColor` setting: a flag for the default palette, or an explicit palette.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum AutoColor {
+    Enabled(bool),
+    Palette(Vec<String>),
+}
+
+/// `validateOutput` setting: `true` or "error" fails the conversion on an
+/// invalid geometry, "warn" only logs it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum ValidateOutput {
+    Enabled(bool),
+    Mode(ValidationMode),
+}
+
+impl ValidateOutput {
+    pub fn mode(self) -> Option<ValidationMode> {
+        match self {
+            Self::Enabled(true) => Some(ValidationMode::Error),
+            Self::Enabled(false) => None,
+            Self::Mode(mode) => Some(mode),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationMode {
+    Error,
+    Warn,
+}
+
+/// `bbox` setting: where FeatureCollection.bbox comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BboxSource {
+    /// The declared <bounds>, as is; no bbox if there are none.
+    Bounds,
+    /// The declared <bounds> if they contain every output coordinate,
+    /// otherwise the extent of the output coordinates.
+    CheckedBounds,
+}
+
+/// `elevationMode` setting: where the elevations of line points go.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ElevationMode {
+    /// The 3rd coordinate value.
+    #[default]
+    Coordinate,
+    /// A `coordinateProperties.elevations` array, keeping coordinates 2D.
+    Property,
+    /// Both.
+    Both,
+}
+
+/// `sensorNaming` setting: names of the per-point sensor arrays.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SensorNaming {
+    /// `heartRates`, `cadences`, `temperatures`, `powers`.
+    #[default]
+    Default,
+    /// `heartRates`, `cads`, `atemps`, `powers`, as mapbox/togeojson names them.
+    Togeojson,
+}
+
+/// `trackMode` setting: how tracks are grouped into Features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrackMode {
+    /// A LineString Feature per segment.
+    FeaturePerSegment,
+    /// A Feature per track: a MultiLineString if it has several segments.
+    FeaturePerTrack,
+    /// A single Feature for every track in the file, with the track names joined.
+    FeaturePerFile,
+}
+
+/// `timeFormat` setting: how timestamps are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeFormat {
+    /// The timestamp text as it appears in the file.
+    #[default]
+    Iso,
+    /// Milliseconds since the Unix epoch, as a number.
+    EpochMs,
+    /// Seconds since the Unix epoch, as a number.
+    EpochS,
+}
+
+/// `timezone` setting, resolved to a fixed offset when the options are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Timezone {
+    /// Seconds east of UTC.
+    pub offset_secs: i64,
+}
+
+impl TryFrom<String> for Timezone {
+    type Error = String;
+
+    fn try_from(zone: String) -> Result<Self, Self::Error> {
+        match datetime::zone_offset(&zone) {
+            Some(offset_secs) => Ok(Self { offset_secs }),
+            None => Err(format!(
+                "Unknown timezone '{zone}': use an offset like \"+09:00\" or a zone without daylight saving time"
+            )),
+        }
+    }
+}
+
+/// `compat` setting: whose output conventions to follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Compat {
+    /// @tmcw/togeojson: a Feature per track, `_gpxType` "wpt"/"rte"/"trk",
+    /// a `links` array, `cads`/`atemps` and no foreign members.
+    Togeojson,
+}
+
+/// `output` setting: the shape of the converted features.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputMode {
+    /// A LineString (or MultiLineString) per route and track.
+    #[default]
+    Features,
+    /// A "routePoint"/"trackPoint" Point Feature per rtept/trkpt, with the
+    /// per-point values as scalar properties.
+    Points,
+    /// A GeometryCollection of the feature geometries, without properties.
+    Geometries,
+}
+
+/// `featureId` setting: what goes into Feature.id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FeatureIdMode {
+    /// No id.
+    #[default]
+    None,
+    /// The feature's position in the output, as a number.
+    Index,
+    /// The `name` property; features without a name get no id.
+    Name,
+    /// The `geometryHash` of the feature's geometry.
+    Hash,
+}
+
+/// Thresholds for splitting a track or route into activities.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivitySplitOptions {
+    /// Minimum time between two points that starts a new activity, in seconds (default: 1800)
+    #[serde(default = "default_min_gap_seconds")]
+    pub min_gap_seconds: f64,
+
+    /// Minimum distance between the same two points, in meters (default: 0)
+    #[serde(default)]
+    pub min_gap_meters: f64,
+}
+
+impl Default for ActivitySplitOptions {
+    fn default() -> Self {
+        Self {
+            min_gap_seconds: default_min_gap_seconds(),
+            min_gap_meters: 0.0,
+        }
+    }
+}
+
+fn default_min_gap_seconds() -> f64 {
+    1800.0
+}
+
+/// Maximum number of elements of each type to read.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LimitOptions {
+    #[serde(default)]
+    pub waypoints: Option<usize>,
+    #[serde(default)]
+    pub routes: Option<usize>,
+    #[serde(default)]
+    pub tracks: Option<usize>,
+}
+
+impl LimitOptions {
+    pub fn for_type(&self, element_type: GpxElementType) -> Option<usize> {
+        match element_type {
+            GpxElementType::Waypoint => self.waypoints,
+            GpxElementType::Route => self.routes,
+            GpxElementType::Track => self.tracks,
+        }
+    }
+}
+
+/// Names of the waypoints delimiting a track section.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SectionOptions {
+    pub from: String,
+    pub to: String,
+}
+
+/// Options for comparing a recorded track against a planned route.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviationOptions {
+    /// Maximum distance from the route still counted as on-route, in meters (default: 50)
+    #[serde(default = "default_tolerance_meters")]
+    pub tolerance_meters: f64,
+}
+
+impl Default for DeviationOptions {
+    fn default() -> Self {
+        Self {
+            tolerance_meters: default_tolerance_meters(),
+        }
+    }
+}
+
+fn default_tolerance_meters() -> f64 {
+    50.0
+}
+
+/// Options for building a thumbnail preview line.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewOptions {
+    /// Maximum number of points in the preview line (default: 100)
+    #[serde(default = "default_max_points")]
+    pub max_points: usize,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            max_points: default_max_points(),
+        }
+    }
+}
+
+fn default_max_points() -> usize {
+    100
+}
+
+/// Options for fitting a file into a static map image.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewportOptions {
+    /// Image width in pixels (default: 512)
+    #[serde(default = "default_image_size")]
+    pub width: u32,
+
+    /// Image height in pixels (default: 512)
+    #[serde(default = "default_image_size")]
+    pub height: u32,
+
+    /// Margin kept free on each side, in pixels (default: 0)
+    #[serde(default)]
+    pub padding: u32,
+
+    /// Also return the features projected to image pixels (default: false)
+    #[serde(default)]
+    pub include_pixels: bool,
+}
+
+impl Default for ViewportOptions {
+    fn default() -> Self {
+        Self {
+            width: default_image_size(),
+            height: default_image_size(),
+            padding: 0,
+            include_pixels: false,
+        }
+    }
+}
+
+fn default_image_size() -> u32 {
+    512
+}
+
+fn default_gradient_window_meters() -> f64 {
+    100.0
+}
+
+fn default_gpx_type_key() -> Option<String> {
+    Some("gpxType".to_string())
+}
+
 fn default_true() -> bool {
     true
 }
@@ -1,56 +1,898 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use quick_xml::NsReader;
+use quick_xml::events::attributes::Attribute;
 use quick_xml::events::{BytesStart, Event};
-use quick_xml::Reader;
+use quick_xml::name::{Namespace, ResolveResult};
 
-use crate::error::Gpx2GeoJsonError;
+use crate::config::{self, Limits};
+use crate::datetime;
+use crate::error::{Gpx2GeoJsonError, LocatedError};
 use crate::gpx_types::*;
+use crate::html;
+use crate::options::{ConvertOptions, GpxElementType};
 
 type Result<T> = std::result::Result<T, Gpx2GeoJsonError>;
 
-/// Parse a GPX XML string into GpxData.
+/// Maximum nesting captured from an <extensions> subtree; deeper elements are dropped.
+const MAX_EXTENSION_DEPTH: usize = 32;
+
+/// Local names of point extension values stored as typed GpxPoint fields
+/// (Garmin TrackPointExtension, Cluetrust gpxdata and the bare Strava `power`).
+const POINT_EXTENSION_FIELDS: &[&[u8]] = &[b"hr", b"cad", b"atemp", b"temp", b"distance", b"power"];
+
+/// Children only Garmin TrackPointExtension v2 has; their generic names are
+/// only trusted inside a `TrackPointExtension`.
+const TRACK_POINT_V2_FIELDS: &[&[u8]] = &[b"speed", b"course"];
+
+/// Children of <gpx> in the order GPX 1.1 requires, for `strict`.
+const GPX_CHILD_ORDER: &[&[u8]] = &[b"metadata", b"wpt", b"rte", b"trk", b"extensions"];
+
+/// Children of a point (wptType) in the order GPX 1.1 requires, for `strict`.
+const POINT_CHILD_ORDER: &[&[u8]] = &[
+    b"ele",
+    b"time",
+    b"magvar",
+    b"geoidheight",
+    b"name",
+    b"cmt",
+    b"desc",
+    b"src",
+    b"link",
+    b"sym",
+    b"type",
+    b"fix",
+    b"sat",
+    b"hdop",
+    b"vdop",
+    b"pdop",
+    b"ageofdgpsdata",
+    b"dgpsid",
+    b"extensions",
+];
+
+/// State shared by the element parsers for one document.
+struct ParseContext<'o> {
+    opts: &'o ConvertOptions,
+    /// Entity expansion and element limits, from `init()`.
+    limits: &'o Limits,
+    /// Entities declared in the DOCTYPE internal subset.
+    entities: HashMap<String, String>,
+    /// Bytes produced by entity expansion so far.
+    expanded_bytes: usize,
+    /// What was read past without being kept, for `includeDiagnostics`.
+    dropped: DroppedData,
+    /// Bytes of the document before the reader's input (earlier stream chunks).
+    offset: u64,
+    /// Byte offset of the event being handled, for `strict` errors.
+    position: u64,
+}
+
+impl ParseContext<'_> {
+    /// Whether the text of a point child element should be kept in `GpxPoint::extra`.
+    fn wants_extra(&self, local_name: &[u8]) -> bool {
+        self.opts
+            .extra_point_elements
+            .iter()
+            .any(|name| name.as_bytes() == local_name)
+    }
+
+    /// Property name that `extensionMappings` assigns to an extension element,
+    /// matched by qualified name (`gpxtpx:hr`) or, for keys without a prefix,
+    /// by local name.
+    fn mapped_name(&self, qualified: &[u8], local_name: &[u8]) -> Option<String> {
+        self.opts
+            .extension_mappings
+            .iter()
+            .find(|(key, _)| {
+                let key = key.as_bytes();
+                key == qualified || (!key.contains(&b':') && key == local_name)
+            })
+            .map(|(_, name)| name.clone())
+    }
+
+    /// `mapped_name` for an element of a captured tree.
+    fn mapped_element(&self, el: &XmlElement) -> Option<String> {
+        let qualified = match &el.prefix {
+            Some(prefix) => format!("{prefix}:{}", el.name),
+            None => el.name.clone(),
+        };
+        self.mapped_name(qualified.as_bytes(), el.name.as_bytes())
+    }
+
+    /// Store the text of every mapped element in a captured tree into `extra`.
+    fn apply_extension_mappings(
+        &self,
+        elements: &[XmlElement],
+        extra: &mut BTreeMap<String, String>,
+    ) {
+        if self.opts.extension_mappings.is_empty() {
+            return;
+        }
+        for el in elements {
+            if let Some(name) = self.mapped_element(el) {
+                extra.insert(name, el.text.clone());
+            }
+            self.apply_extension_mappings(&el.children, extra);
+        }
+    }
+
+    /// Store every numeric leaf of a captured tree in `values`, by local name.
+    /// Values the parser already models, or that `extensionMappings` claims, are skipped.
+    fn collect_numeric_extensions(
+        &self,
+        elements: &[XmlElement],
+        values: &mut BTreeMap<String, f64>,
+    ) {
+        for el in elements {
+            if !el.children.is_empty() {
+                self.collect_numeric_extensions(&el.children, values);
+                continue;
+            }
+            let name = el.name.as_bytes();
+            if POINT_EXTENSION_FIELDS.contains(&name)
+                || TRACK_POINT_V2_FIELDS.contains(&name)
+                || self.mapped_element(el).is_some()
+            {
+                continue;
+            }
+            if let Ok(value) = el.text.trim().parse::<f64>() {
+                values.insert(el.name.clone(), value);
+            }
+        }
+    }
+
+    /// Fail if `e` is nested deeper than `maxDepth` or has more than
+    /// `maxAttributes` attributes. Must be called before the reader moves past `e`.
+    fn check_element(&self, reader: &NsReader<&[u8]>, e: &BytesStart<'_>) -> Result<()> {
+        check_element_limits(self.limits, reader, e)
+    }
+
+    /// Skip an element the parser doesn't read, counting it for the report.
+    fn skip_element(&mut self, reader: &mut NsReader<&[u8]>, e: &BytesStart<'_>) -> Result<()> {
+        self.note_ignored(e);
+        reader
+            .read_to_end(e.name())
+            .map_err(Gpx2GeoJsonError::XmlParse)?;
+        Ok(())
+    }
+
+    /// Count an ignored element by local name.
+    fn note_ignored(&mut self, e: &BytesStart<'_>) {
+        if self.opts.include_diagnostics {
+            let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+            *self.dropped.ignored_elements.entry(name).or_default() += 1;
+        }
+    }
+
+    fn note_skipped_point(&mut self) {
+        if self.opts.include_diagnostics {
+            self.dropped.skipped_points += 1;
+        }
+    }
+
+    fn note_unknown_entity(&mut self, name: &str) {
+        if self.opts.include_diagnostics {
+            *self
+                .dropped
+                .unknown_entities
+                .entry(name.to_string())
+                .or_default() += 1;
+        }
+    }
+
+    /// Count the elements of a captured <extensions> tree that are lost, by
+    /// namespace prefix. `kept` elements are not descended into; of the rest,
+    /// every leaf counts. Nothing is lost when `includeExtensions` keeps the tree.
+    fn note_dropped_extensions(
+        &mut self,
+        elements: &[XmlElement],
+        kept: &dyn Fn(&Self, &XmlElement) -> bool,
+    ) {
+        if !self.opts.include_diagnostics || self.opts.include_extensions {
+            return;
+        }
+        for el in elements {
+            if kept(self, el) {
+                continue;
+            }
+            if el.children.is_empty() {
+                let prefix = el.prefix.clone().unwrap_or_default();
+                *self.dropped.dropped_extensions.entry(prefix).or_default() += 1;
+            } else {
+                self.note_dropped_extensions(&el.children, kept);
+            }
+        }
+    }
+
+    /// A `strict` mode error at the current event.
+    fn invalid(&self, message: String) -> Gpx2GeoJsonError {
+        Gpx2GeoJsonError::Validation {
+            position: self.position,
+            message,
+        }
+    }
+
+    /// In `strict` mode, fail unless `time` is a valid xsd:dateTime.
+    fn check_time(&self, time: &str) -> Result<()> {
+        if self.opts.strict && datetime::parse_timestamp(time).is_none() {
+            return Err(self.invalid(format!("invalid dateTime '{time}' in <time>")));
+        }
+        Ok(())
+    }
+
+    /// In `strict` mode, fail if `child` comes after a sibling that `order`
+    /// puts later. `last` is the position in `order` of the latest sibling;
+    /// elements not in `order` are not checked.
+    fn check_order(
+        &self,
+        order: &[&[u8]],
+        last: &mut Option<usize>,
+        child: &[u8],
+        parent: &[u8],
+    ) -> Result<()> {
+        if !self.opts.strict {
+            return Ok(());
+        }
+        let Some(rank) = order.iter().position(|name| *name == child) else {
+            return Ok(());
+        };
+        match *last {
+            Some(prev) if rank < prev => Err(self.invalid(format!(
+                "<{}> must come before <{}> in <{}>",
+                String::from_utf8_lossy(child),
+                String::from_utf8_lossy(order[prev]),
+                String::from_utf8_lossy(parent)
+            ))),
+            _ => {
+                *last = Some(rank);
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether `count` elements of `element_type` already reach its `limit`.
+    fn limit_reached(&self, element_type: GpxElementType, count: usize) -> bool {
+        self.opts
+            .limit
+            .as_ref()
+            .and_then(|limit| limit.for_type(element_type))
+            .is_some_and(|max| count >= max)
+    }
+
+    /// Whether elements of `element_type` are read at all: types excluded by
+    /// `types` are skipped, unless another option uses them (waypoints for
+    /// `section` and `trackGeometryCollection`, lines for
+    /// `waypointsNearTracksOnly`).
+    fn needed(&self, element_type: GpxElementType) -> bool {
+        let opts = self.opts;
+        opts.should_include(element_type)
+            || match element_type {
+                GpxElementType::Waypoint => {
+                    opts.section.is_some() || opts.track_geometry_collection
+                }
+                GpxElementType::Route | GpxElementType::Track => {
+                    opts.waypoints_near_tracks_only.is_some()
+                        && opts.should_include(GpxElementType::Waypoint)
+                }
+            }
+    }
+
+    /// Whether no further elements are needed: every type has reached its
+    /// limit or isn't `needed`.
+    fn all_done(&self, data: &GpxData) -> bool {
+        let done = |element_type, count| {
+            !self.needed(element_type) || self.limit_reached(element_type, count)
+        };
+        self.opts.limit.is_some()
+            && done(GpxElementType::Waypoint, data.waypoints.len())
+            && done(GpxElementType::Route, data.routes.len())
+            && done(GpxElementType::Track, data.tracks.len())
+    }
+}
+
+/// Parse a GPX XML string into GpxData using default options.
 pub fn parse_gpx(xml: &str) -> Result<GpxData> {
-    let mut reader = Reader::from_str(xml);
-    let mut data = GpxData::default();
+    parse_gpx_with_options(xml, &ConvertOptions::default())
+}
+
+/// Parse a GPX XML string into GpxData.
+/// Only parser-level options (e.g. `resolve_dtd_entities`) are used here.
+pub fn parse_gpx_with_options(xml: &str, opts: &ConvertOptions) -> Result<GpxData> {
+    parse_gpx_located(xml, opts).map_err(|e| e.error)
+}
+
+/// Like [`parse_gpx_with_options`], with the byte offset of the failing
+/// element on error.
+pub fn parse_gpx_located(
+    xml: &str,
+    opts: &ConvertOptions,
+) -> std::result::Result<GpxData, LocatedError> {
+    let limits = config::limits();
+    let mut ctx = ParseContext {
+        opts,
+        limits: &limits,
+        entities: HashMap::new(),
+        expanded_bytes: 0,
+        dropped: DroppedData::default(),
+        offset: 0,
+        position: 0,
+    };
+    let mut state = ParseState::default();
+    let mut reader = NsReader::from_str(xml);
+    if let Err(error) = parse_events(&mut reader, xml.len(), &mut state, &mut ctx, true) {
+        return Err(LocatedError {
+            error,
+            position: ctx.position,
+        });
+    }
+    Ok(state.finish(ctx.dropped))
+}
+
+/// Incremental GPX parser for input that arrives in chunks (e.g. a File stream).
+///
+/// Every [`push`](Self::push) parses as far as the bytes received so far
+/// allow; only the unparsed tail, at most one incomplete point or child
+/// element, is kept. The input must be UTF-8.
+pub struct StreamParser {
+    opts: ConvertOptions,
+    limits: Limits,
+    state: ParseState,
+    entities: HashMap<String, String>,
+    expanded_bytes: usize,
+    dropped: DroppedData,
+    pending: Vec<u8>,
+    /// Bytes parsed and dropped from `pending`.
+    drained: u64,
+}
+
+impl StreamParser {
+    pub fn new(opts: ConvertOptions) -> Self {
+        Self {
+            opts,
+            limits: config::limits(),
+            state: ParseState::default(),
+            entities: HashMap::new(),
+            expanded_bytes: 0,
+            dropped: DroppedData::default(),
+            pending: Vec::new(),
+            drained: 0,
+        }
+    }
+
+    /// Feed the next chunk of the document. Chunks may split the input anywhere,
+    /// including inside a tag or a multi-byte character.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<()> {
+        if self.state.done {
+            return Ok(());
+        }
+        self.pending.extend_from_slice(chunk);
+        let consumed = self.parse_pending(false)?;
+        self.pending.drain(..consumed);
+        self.drained += consumed as u64;
+        Ok(())
+    }
+
+    /// Parse whatever is left and return the data. Elements left open by a
+    /// truncated document are kept, as with [`parse_gpx_with_options`].
+    pub fn finish(mut self) -> Result<GpxData> {
+        if !self.state.done {
+            self.parse_pending(true)?;
+        }
+        Ok(self.state.finish(self.dropped))
+    }
+
+    fn parse_pending(&mut self, is_final: bool) -> Result<usize> {
+        let mut ctx = ParseContext {
+            opts: &self.opts,
+            limits: &self.limits,
+            entities: std::mem::take(&mut self.entities),
+            expanded_bytes: self.expanded_bytes,
+            dropped: std::mem::take(&mut self.dropped),
+            offset: self.drained,
+            position: self.drained,
+        };
+        // Replay the start tags of the elements opened in earlier chunks, so
+        // that the namespaces they declare are in scope again
+        let mut input: Vec<u8> = self
+            .state
+            .open
+            .iter()
+            .flat_map(|el| el.tag.clone())
+            .collect();
+        let replayed = input.len();
+        input.extend_from_slice(&self.pending);
+        ctx.offset -= replayed as u64;
+        let mut reader = NsReader::from_reader(input.as_slice());
+        for _ in &self.state.open {
+            reader.read_event()?;
+        }
+        let result = parse_events(
+            &mut reader,
+            input.len(),
+            &mut self.state,
+            &mut ctx,
+            is_final,
+        );
+        self.entities = ctx.entities;
+        self.expanded_bytes = ctx.expanded_bytes;
+        self.dropped = ctx.dropped;
+        result.map(|consumed| consumed - replayed)
+    }
+}
+
+/// Parse state that persists between chunks.
+#[derive(Default)]
+struct ParseState {
+    data: GpxData,
+    /// The elements the parser is inside of, innermost last.
+    open: Vec<OpenElement>,
+    /// Set once the `limit`s are reached; later input is ignored.
+    done: bool,
+    /// Position in `GPX_CHILD_ORDER` of the latest child of <gpx>, for `strict`.
+    last_gpx_child: Option<usize>,
+    /// Whether a <gpx> root was seen. Without one, the input is read as a
+    /// fragment: bare <trkseg>, <trkpt> and <rtept> elements are kept too.
+    gpx_root: bool,
+    /// Bare <trkseg>s of a fragment, and its bare <trkpt>s as one more segment.
+    fragment_track: GpxTrack,
+    fragment_points: GpxSegment,
+    /// Bare <rtept>s of a fragment.
+    fragment_route: GpxRoute,
+}
+
+impl ParseState {
+    /// Close the elements a truncated document left open and return the data.
+    fn finish(mut self, dropped: DroppedData) -> GpxData {
+        while let Some(element) = self.open.pop() {
+            self.close(element.frame);
+        }
+        if !self.fragment_points.points.is_empty() {
+            self.fragment_track.segments.push(self.fragment_points);
+        }
+        if !self.fragment_track.segments.is_empty() {
+            self.data.tracks.push(self.fragment_track);
+        }
+        if !self.fragment_route.points.is_empty() {
+            self.data.routes.push(self.fragment_route);
+        }
+        log::debug!(
+            "Parsed {} waypoints, {} routes, {} tracks",
+            self.data.waypoints.len(),
+            self.data.routes.len(),
+            self.data.tracks.len()
+        );
+        self.data.dropped = dropped;
+        self.data
+    }
+
+    /// Add a closed element to its parent.
+    fn close(&mut self, frame: Frame) {
+        match frame {
+            Frame::Metadata(metadata) => {
+                // Concatenated documents: the first <metadata> wins
+                self.data.metadata.get_or_insert(metadata);
+            }
+            Frame::Route(route) => self.data.routes.push(route),
+            Frame::Track(track) => self.data.tracks.push(track),
+            Frame::Segment(segment) if !segment.points.is_empty() => match self.open.last_mut() {
+                Some(OpenElement {
+                    frame: Frame::Track(track),
+                    ..
+                }) => track.segments.push(segment),
+                _ => self.fragment_track.segments.push(segment),
+            },
+            Frame::Segment(_) => {}
+            Frame::Document | Frame::Skip => {}
+        }
+    }
+
+    /// Add a completely parsed point, read from an element with `local_name`,
+    /// to the innermost open element.
+    fn add_point(&mut self, local_name: &[u8], pt: GpxPoint) {
+        match self.open.last_mut().map(|el| &mut el.frame) {
+            Some(Frame::Route(route)) => route.points.push(pt),
+            Some(Frame::Segment(segment)) => segment.points.push(pt),
+            _ => match local_name {
+                b"trkpt" => self.fragment_points.points.push(pt),
+                b"rtept" => self.fragment_route.points.push(pt),
+                _ => self.data.waypoints.push(pt),
+            },
+        }
+    }
+}
+
+/// An open element and how its children are read.
+struct OpenElement {
+    /// Qualified name, checked against the end tag.
+    name: Vec<u8>,
+    /// The whole start tag, replayed by `StreamParser` to restore the
+    /// namespace declarations in scope.
+    tag: Vec<u8>,
+    frame: Frame,
+}
+
+enum Frame {
+    /// <gpx>, or any other element whose children are read like top-level ones.
+    Document,
+    Metadata(GpxMetadata),
+    Route(GpxRoute),
+    Track(GpxTrack),
+    Segment(GpxSegment),
+    /// An element skipped with all its content (past its `limit`).
+    Skip,
+}
+
+/// Parse the events of `reader`, which reads `len` bytes, into `state`.
+/// Returns the number of bytes fully parsed.
+///
+/// Container elements (<gpx>, <rte>, <trk>, <trkseg>) are tracked in
+/// `state.open`; their children are parsed whole. Unless `is_final`, a
+/// child or an XML error that reaches the end of the input may just be cut
+/// off: parsing stops before it, to be retried when more input has arrived.
+/// Fields it set are simply set again then; points are only added once
+/// complete. An error before the end of the input is returned right away.
+fn parse_events(
+    reader: &mut NsReader<&[u8]>,
+    len: usize,
+    state: &mut ParseState,
+    ctx: &mut ParseContext<'_>,
+    is_final: bool,
+) -> Result<usize> {
+    let mut consumed = reader.buffer_position() as usize;
 
     loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) => match e.local_name().as_ref() {
-                b"wpt" => {
-                    if let Some(pt) = parse_point(&e, &mut reader)? {
-                        data.waypoints.push(pt);
-                    }
+        if ctx.all_done(&state.data) {
+            log::debug!("Element limits reached, stopping early");
+            state.done = true;
+            return Ok(len);
+        }
+        ctx.position = ctx.offset + consumed as u64;
+        let event = match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(event) => event,
+            Err(e) => {
+                let e = Gpx2GeoJsonError::XmlParse(e);
+                if !is_final && cut_off(&e, reader, len) {
+                    break;
                 }
-                b"rte" => data.routes.push(parse_route(&mut reader)?),
-                b"trk" => data.tracks.push(parse_track(&mut reader)?),
-                _ => {}
-            },
-            Ok(Event::Empty(e)) => {
-                if e.local_name().as_ref() == b"wpt" {
-                    if let Ok((lat, lon)) = parse_lat_lon(&e) {
-                        data.waypoints.push(GpxPoint::new(lat, lon));
+                return Err(e);
+            }
+        };
+        if let Event::Start(e) | Event::Empty(e) = &event {
+            ctx.check_element(reader, e)?;
+            check_gpx_child_order(gpx_name(reader, e), state, ctx)?;
+        }
+        match event {
+            Event::Start(e) => {
+                if let Some(frame) = open_frame(&e, gpx_name(reader, &e), state, ctx) {
+                    state.open.push(OpenElement {
+                        name: e.name().as_ref().to_vec(),
+                        tag: [b"<", &*e, b">"].concat(),
+                        frame,
+                    });
+                } else {
+                    let dropped =
+                        (!is_final && ctx.opts.include_diagnostics).then(|| ctx.dropped.clone());
+                    let expanded_bytes = ctx.expanded_bytes;
+                    let parsed = parse_child(&e, reader, state, ctx);
+                    let complete = is_final || (reader.buffer_position() as usize) < len;
+                    match parsed {
+                        Ok(pt) if complete => {
+                            if let Some(pt) = pt {
+                                state.add_point(e.local_name().as_ref(), pt);
+                            }
+                        }
+                        Err(e) if is_final || !cut_off(&e, reader, len) => return Err(e),
+                        _ => {
+                            if let Some(dropped) = dropped {
+                                ctx.dropped = dropped;
+                            }
+                            ctx.expanded_bytes = expanded_bytes;
+                            break;
+                        }
                     }
                 }
             }
-            Ok(Event::Eof) => break,
-            Err(e) => return Err(Gpx2GeoJsonError::XmlParse(e)),
+            Event::Empty(e) => parse_empty(&e, gpx_name(reader, &e), state, ctx)?,
+            Event::End(e) => {
+                // A stray end tag outside any element is ignored
+                let Some(element) = state.open.pop() else {
+                    consumed = reader.buffer_position() as usize;
+                    continue;
+                };
+                if element.name != e.name().as_ref() {
+                    return Err(Gpx2GeoJsonError::XmlParse(quick_xml::Error::IllFormed(
+                        quick_xml::errors::IllFormedError::MismatchedEndTag {
+                            expected: String::from_utf8_lossy(&element.name).into_owned(),
+                            found: String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+                        },
+                    )));
+                }
+                state.close(element.frame);
+            }
+            Event::DocType(e) if ctx.opts.resolve_dtd_entities => {
+                let doctype = std::str::from_utf8(e.as_ref()).unwrap_or_default();
+                ctx.entities = parse_internal_entities(doctype);
+            }
+            _ => {}
+        }
+        consumed = reader.buffer_position() as usize;
+    }
+
+    Ok(consumed)
+}
+
+/// Whether `e` may only be due to the input ending early: a syntax error,
+/// which quick-xml reports for markup the input ends inside of, or any error
+/// once the reader has reached the end.
+fn cut_off(e: &Gpx2GeoJsonError, reader: &NsReader<&[u8]>, len: usize) -> bool {
+    matches!(e, Gpx2GeoJsonError::XmlParse(quick_xml::Error::Syntax(_)))
+        || reader.buffer_position() as usize >= len
+}
+
+/// The frame for a start tag whose children are read one event at a time,
+/// or `None` if the element is parsed whole by `parse_child`.
+fn open_frame(
+    e: &BytesStart<'_>,
+    name: &[u8],
+    state: &mut ParseState,
+    ctx: &mut ParseContext<'_>,
+) -> Option<Frame> {
+    match state.open.last().map(|el| &el.frame) {
+        None | Some(Frame::Document) => match name {
+            b"gpx" => {
+                read_version(e, &mut state.data);
+                state.gpx_root = true;
+                Some(Frame::Document)
+            }
+            b"wpt" | b"rte" | b"trk" if !ctx.needed(element_type(e)) => Some(Frame::Skip),
+            b"wpt" | b"rte" | b"trk"
+                if ctx.limit_reached(element_type(e), count(&state.data, e)) =>
+            {
+                ctx.note_ignored(e);
+                Some(Frame::Skip)
+            }
+            b"wpt" => None,
+            b"metadata" => Some(Frame::Metadata(GpxMetadata::default())),
+            b"rte" => Some(Frame::Route(GpxRoute::default())),
+            b"trk" => Some(Frame::Track(GpxTrack::default())),
+            b"trkseg" if !state.gpx_root => Some(Frame::Segment(GpxSegment::default())),
+            b"trkpt" | b"rtept" if !state.gpx_root => None,
+            _ => Some(Frame::Document),
+        },
+        Some(Frame::Track(_)) if name == b"trkseg" => Some(Frame::Segment(GpxSegment::default())),
+        Some(Frame::Skip) => Some(Frame::Skip),
+        Some(_) => None,
+    }
+}
+
+/// `strict`: check the order of a start or empty tag at document level. Each
+/// <gpx> root (concatenated documents) starts the order afresh.
+fn check_gpx_child_order(
+    name: &[u8],
+    state: &mut ParseState,
+    ctx: &ParseContext<'_>,
+) -> Result<()> {
+    if !matches!(
+        state.open.last().map(|el| &el.frame),
+        None | Some(Frame::Document)
+    ) {
+        return Ok(());
+    }
+    if name == b"gpx" {
+        state.last_gpx_child = None;
+        return Ok(());
+    }
+    ctx.check_order(GPX_CHILD_ORDER, &mut state.last_gpx_child, name, b"gpx")
+}
+
+/// Parse a child element of the innermost open element, up to its end tag.
+/// Points are returned rather than added, so that a child cut off by the end
+/// of a chunk can be parsed again; other children only set fields.
+fn parse_child<'a>(
+    e: &BytesStart<'a>,
+    reader: &mut NsReader<&'a [u8]>,
+    state: &mut ParseState,
+    ctx: &mut ParseContext<'_>,
+) -> Result<Option<GpxPoint>> {
+    match state.open.last_mut().map(|el| &mut el.frame) {
+        Some(Frame::Metadata(metadata)) => {
+            parse_metadata_child(e, reader, ctx, metadata)?;
+            Ok(None)
+        }
+        Some(Frame::Route(route)) => parse_route_child(e, reader, ctx, route),
+        Some(Frame::Track(track)) => {
+            parse_track_child(e, reader, ctx, track)?;
+            Ok(None)
+        }
+        Some(Frame::Segment(segment)) => parse_segment_child(e, reader, ctx, segment),
+        _ => parse_point(e, reader, ctx),
+    }
+}
+
+/// Handle an empty-element tag (`<x/>`) inside the innermost open element.
+fn parse_empty(
+    e: &BytesStart<'_>,
+    name: &[u8],
+    state: &mut ParseState,
+    ctx: &mut ParseContext<'_>,
+) -> Result<()> {
+    match state.open.last_mut().map(|el| &mut el.frame) {
+        None | Some(Frame::Document) => match name {
+            b"gpx" => {
+                read_version(e, &mut state.data);
+                state.gpx_root = true;
+            }
+            b"wpt"
+                if ctx.needed(GpxElementType::Waypoint)
+                    && !ctx.limit_reached(GpxElementType::Waypoint, state.data.waypoints.len()) =>
+            {
+                if let Some(pt) = parse_empty_point(e, ctx)? {
+                    state.data.waypoints.push(pt);
+                }
+            }
+            b"trkpt" | b"rtept" if !state.gpx_root => {
+                if let Some(pt) = parse_empty_point(e, ctx)? {
+                    state.add_point(name, pt);
+                }
+            }
             _ => {}
+        },
+        Some(Frame::Metadata(metadata)) if name == b"bounds" => {
+            metadata.bounds = parse_bounds(e);
         }
+        Some(Frame::Route(_)) if name == b"rtept" => {
+            if let Some(pt) = parse_empty_point(e, ctx)? {
+                state.add_point(name, pt);
+            }
+        }
+        Some(Frame::Segment(_)) if name == b"trkpt" => {
+            if let Some(pt) = parse_empty_point(e, ctx)? {
+                state.add_point(name, pt);
+            }
+        }
+        Some(Frame::Skip) => {}
+        Some(_) => ctx.note_ignored(e),
     }
+    Ok(())
+}
 
-    Ok(data)
+/// A point without children (`<trkpt lat=".." lon=".."/>`), or `None` if
+/// its lat/lon are missing or invalid.
+fn parse_empty_point(e: &BytesStart<'_>, ctx: &mut ParseContext<'_>) -> Result<Option<GpxPoint>> {
+    Ok(point_lat_lon(e, ctx)?.map(|(lat, lon)| GpxPoint::new(lat, lon)))
+}
+
+/// The lat/lon of a point, or `None` (with a warning) if they are missing or
+/// invalid. In `strict` mode that is an error, as are coordinates out of range.
+fn point_lat_lon(e: &BytesStart<'_>, ctx: &mut ParseContext<'_>) -> Result<Option<(f64, f64)>> {
+    let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+    match parse_lat_lon(e, ctx.opts.lenient_numbers) {
+        Ok((lat, _)) if ctx.opts.strict && !(-90.0..=90.0).contains(&lat) => {
+            Err(ctx.invalid(format!("lat {lat} on <{name}> is outside [-90, 90]")))
+        }
+        Ok((_, lon)) if ctx.opts.strict && !(-180.0..180.0).contains(&lon) => {
+            Err(ctx.invalid(format!("lon {lon} on <{name}> is outside [-180, 180)")))
+        }
+        Ok(coords) => Ok(Some(coords)),
+        Err(err) if ctx.opts.strict => Err(ctx.invalid(format!("<{name}>: {err}"))),
+        Err(err) => {
+            log::warn!("Skipping <{name}>: {err}");
+            ctx.note_skipped_point();
+            Ok(None)
+        }
+    }
+}
+
+/// The local name of `e` if it is a GPX element, i.e. in the GPX 1.0 or 1.1
+/// namespace or in none; otherwise an empty name, which matches no GPX
+/// element. Must be called before the reader moves past `e`.
+pub(crate) fn gpx_name<'e>(reader: &NsReader<&[u8]>, e: &'e BytesStart<'_>) -> &'e [u8] {
+    match reader.resolver().resolve_element(e.name()) {
+        (ResolveResult::Unbound, local) => local.into_inner(),
+        (ResolveResult::Bound(Namespace(uri)), local) if is_gpx_namespace(uri) => {
+            local.into_inner()
+        }
+        _ => b"",
+    }
+}
+
+/// `http://www.topografix.com/GPX/1/1` or `.../1/0`, also accepting the
+/// https and trailing-slash variants some writers produce.
+fn is_gpx_namespace(uri: &[u8]) -> bool {
+    let uri = uri
+        .strip_prefix(b"http://")
+        .or_else(|| uri.strip_prefix(b"https://"))
+        .unwrap_or(uri);
+    let uri = uri.strip_suffix(b"/").unwrap_or(uri);
+    uri == b"www.topografix.com/GPX/1/1" || uri == b"www.topografix.com/GPX/1/0"
+}
+
+/// `maxDepth` and `maxAttributes` for a start or empty tag just read.
+///
+/// Deep nesting grows the reader's namespace scopes, and the attribute
+/// iterator checks for duplicates in quadratic time, so both are checked
+/// before anything else looks at the element.
+pub(crate) fn check_element_limits(
+    limits: &Limits,
+    reader: &NsReader<&[u8]>,
+    e: &BytesStart<'_>,
+) -> Result<()> {
+    if usize::from(reader.resolver().level()) > limits.max_depth {
+        return Err(Gpx2GeoJsonError::LimitExceeded {
+            limit: "maxDepth",
+            max: limits.max_depth,
+        });
+    }
+    let mut attributes = e.attributes();
+    let count = attributes
+        .with_checks(false)
+        .take(limits.max_attributes + 1)
+        .count();
+    if count > limits.max_attributes {
+        return Err(Gpx2GeoJsonError::LimitExceeded {
+            limit: "maxAttributes",
+            max: limits.max_attributes,
+        });
+    }
+    Ok(())
+}
+
+/// The element type of a top-level <wpt>, <rte> or <trk> start tag.
+fn element_type(e: &BytesStart<'_>) -> GpxElementType {
+    match e.local_name().as_ref() {
+        b"wpt" => GpxElementType::Waypoint,
+        b"rte" => GpxElementType::Route,
+        _ => GpxElementType::Track,
+    }
+}
+
+/// Number of elements already read of the type started by `e`.
+fn count(data: &GpxData, e: &BytesStart<'_>) -> usize {
+    match element_type(e) {
+        GpxElementType::Waypoint => data.waypoints.len(),
+        GpxElementType::Route => data.routes.len(),
+        GpxElementType::Track => data.tracks.len(),
+    }
+}
+
+/// Record the `version` attribute of a root <gpx> element.
+/// Concatenated documents: the first version wins.
+fn read_version(e: &BytesStart<'_>, data: &mut GpxData) {
+    if data.version.is_some() {
+        return;
+    }
+    data.version = e
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.local_name().as_ref() == b"version")
+        .map(|attr| attribute_value(&attr).into_owned());
+}
+
+/// The value of `attr` with character references and the predefined
+/// entities (`&amp;`, `&#38;`) decoded, as in element text. A value with
+/// any other entity reference is returned as is.
+fn attribute_value<'a>(attr: &'a Attribute<'_>) -> Cow<'a, str> {
+    match attr.unescape_value() {
+        Ok(value) => value,
+        Err(_) => String::from_utf8_lossy(&attr.value),
+    }
 }
 
 /// Parse lat/lon attributes from a point element's start tag.
-fn parse_lat_lon(e: &BytesStart<'_>) -> Result<(f64, f64)> {
+fn parse_lat_lon(e: &BytesStart<'_>, lenient: bool) -> Result<(f64, f64)> {
     let mut lat: Option<f64> = None;
     let mut lon: Option<f64> = None;
 
     for attr_result in e.attributes() {
         let attr = attr_result.map_err(|e| Gpx2GeoJsonError::XmlParse(e.into()))?;
         let key = attr.key.local_name();
-        let val = std::str::from_utf8(&attr.value).unwrap_or_default();
+        let val = attribute_value(&attr);
         match key.as_ref() {
             b"lat" => {
-                lat = Some(val.parse::<f64>().map_err(|_| {
+                lat = Some(parse_decimal(&val, lenient).ok_or_else(|| {
                     Gpx2GeoJsonError::InvalidAttribute {
                         element: "point",
                         attribute: "lat",
@@ -59,7 +901,7 @@ fn parse_lat_lon(e: &BytesStart<'_>) -> Result<(f64, f64)> {
                 })?);
             }
             b"lon" => {
-                lon = Some(val.parse::<f64>().map_err(|_| {
+                lon = Some(parse_decimal(&val, lenient).ok_or_else(|| {
                     Gpx2GeoJsonError::InvalidAttribute {
                         element: "point",
                         attribute: "lon",
@@ -83,66 +925,176 @@ fn parse_lat_lon(e: &BytesStart<'_>) -> Result<(f64, f64)> {
     Ok((lat, lon))
 }
 
+/// Parse an `xsd:decimal` value. With `lenientNumbers`, surrounding
+/// whitespace, a leading `+` and a comma as the decimal separator
+/// (`35,6762`) are accepted too.
+fn parse_decimal(text: &str, lenient: bool) -> Option<f64> {
+    if !lenient {
+        return text.parse().ok();
+    }
+    let text = text.trim();
+    let text = text.strip_prefix('+').unwrap_or(text);
+    if !text.contains('.') && text.matches(',').count() == 1 {
+        return text.replace(',', ".").parse().ok();
+    }
+    text.parse().ok()
+}
+
 /// Parse a point element (wpt, rtept, trkpt) and its children.
 /// Called after receiving Event::Start for the point element.
 fn parse_point<'a>(
     start: &BytesStart<'a>,
-    reader: &mut Reader<&'a [u8]>,
+    reader: &mut NsReader<&'a [u8]>,
+    ctx: &mut ParseContext<'_>,
 ) -> Result<Option<GpxPoint>> {
-    let (lat, lon) = match parse_lat_lon(start) {
-        Ok(coords) => coords,
-        Err(_) => {
-            // Skip this point if lat/lon are missing or invalid
-            reader
-                .read_to_end(start.name())
-                .map_err(Gpx2GeoJsonError::XmlParse)?;
-            return Ok(None);
-        }
+    let Some((lat, lon)) = point_lat_lon(start, ctx)? else {
+        // Skip this point if lat/lon are missing or invalid
+        reader
+            .read_to_end(start.name())
+            .map_err(Gpx2GeoJsonError::XmlParse)?;
+        return Ok(None);
     };
 
     let mut point = GpxPoint::new(lat, lon);
     let end_name = start.name().0.to_vec(); // own the end tag name for comparison
+    let is_waypoint = start.local_name().as_ref() == b"wpt";
+    let mut last_child = None;
 
     loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) => match e.local_name().as_ref() {
+        let event = reader.read_event();
+        if let Ok(Event::Start(e) | Event::Empty(e)) = &event {
+            ctx.check_element(reader, e)?;
+            ctx.check_order(
+                POINT_CHILD_ORDER,
+                &mut last_child,
+                gpx_name(reader, e),
+                start.local_name().as_ref(),
+            )?;
+        }
+        match event {
+            Ok(Event::Start(e)) => match gpx_name(reader, &e) {
                 b"ele" => {
                     let text = reader
                         .read_text(e.name())
                         .map_err(Gpx2GeoJsonError::XmlParse)?;
-                    point.ele = text.parse::<f64>().ok();
+                    point.ele = parse_decimal(&text, ctx.opts.lenient_numbers);
+                    if ctx.opts.strict && point.ele.is_none() {
+                        return Err(ctx.invalid(format!("invalid decimal '{text}' in <ele>")));
+                    }
+                    if let (Some(ele), Some([min, max])) = (point.ele, ctx.opts.elevation_range)
+                        && !(min..=max).contains(&ele)
+                    {
+                        log::debug!("Dropping elevation {ele} outside [{min}, {max}]");
+                        point.ele = None;
+                    }
                 }
                 b"time" => {
-                    point.time = Some(read_text_owned(reader, &e)?);
+                    let time = read_text_owned(reader, &e, ctx)?;
+                    ctx.check_time(&time)?;
+                    point.time = Some(time);
                 }
                 b"name" => {
-                    point.name = Some(read_text_owned(reader, &e)?);
+                    point.name = Some(read_text_field(reader, &e, ctx)?);
                 }
                 b"cmt" => {
-                    point.cmt = Some(read_text_owned(reader, &e)?);
+                    point.cmt = Some(read_text_field(reader, &e, ctx)?);
                 }
                 b"desc" => {
-                    point.desc = Some(read_text_owned(reader, &e)?);
+                    point.desc = Some(read_text_field(reader, &e, ctx)?);
                 }
                 b"src" => {
-                    point.src = Some(read_text_owned(reader, &e)?);
+                    point.src = Some(read_text_field(reader, &e, ctx)?);
                 }
                 b"sym" => {
-                    point.sym = Some(read_text_owned(reader, &e)?);
+                    point.sym = Some(read_text_owned(reader, &e, ctx)?);
                 }
                 b"type" => {
-                    point.point_type = Some(read_text_owned(reader, &e)?);
+                    point.point_type = Some(read_text_owned(reader, &e, ctx)?);
                 }
                 b"link" => {
-                    point.link = Some(parse_link(&e, reader)?);
+                    point.link = Some(parse_link(&e, reader, ctx)?);
                 }
-                _ => {
-                    // Skip unknown/extensions elements
-                    reader
-                        .read_to_end(e.name())
-                        .map_err(Gpx2GeoJsonError::XmlParse)?;
+                b"url" => {
+                    point.link.get_or_insert_default().href = read_text_owned(reader, &e, ctx)?;
+                }
+                b"urlname" => {
+                    point.link.get_or_insert_default().text =
+                        Some(read_text_owned(reader, &e, ctx)?);
+                }
+                b"fix" => {
+                    point.fix = Some(read_text_owned(reader, &e, ctx)?);
+                }
+                b"sat" => {
+                    point.sat = read_text_owned(reader, &e, ctx)?.trim().parse().ok();
+                }
+                b"hdop" => {
+                    point.hdop = read_text_owned(reader, &e, ctx)?.trim().parse().ok();
+                }
+                b"vdop" => {
+                    point.vdop = read_text_owned(reader, &e, ctx)?.trim().parse().ok();
                 }
+                b"pdop" => {
+                    point.pdop = read_text_owned(reader, &e, ctx)?.trim().parse().ok();
+                }
+                b"magvar" => {
+                    point.magvar = read_text_owned(reader, &e, ctx)?.trim().parse().ok();
+                }
+                b"geoidheight" => {
+                    point.geoid_height = read_text_owned(reader, &e, ctx)?.trim().parse().ok();
+                }
+                b"dgpsid" => {
+                    point.dgps_id = read_text_owned(reader, &e, ctx)?.trim().parse().ok();
+                }
+                b"ageofdgpsdata" => {
+                    point.age_of_dgps_data = read_text_owned(reader, &e, ctx)?.trim().parse().ok();
+                }
+                b"speed" => {
+                    point.speed = read_text_owned(reader, &e, ctx)?.trim().parse().ok();
+                }
+                b"course" => {
+                    point.course = read_text_owned(reader, &e, ctx)?.trim().parse().ok();
+                }
+                // Waypoints always need the tree for Garmin WaypointExtension
+                b"extensions"
+                    if ctx.opts.include_extensions
+                        || ctx.opts.auto_extensions
+                        || ctx.opts.include_diagnostics
+                        || is_waypoint =>
+                {
+                    let extensions = read_element(reader, &e, ctx, 0)?.children;
+                    ctx.note_dropped_extensions(&extensions, &|ctx, el| {
+                        point_extension_kept(ctx, el, is_waypoint)
+                    });
+                    apply_point_extensions(&mut point, &extensions);
+                    ctx.apply_extension_mappings(&extensions, &mut point.extra);
+                    if ctx.opts.auto_extensions {
+                        ctx.collect_numeric_extensions(&extensions, &mut point.extension_values);
+                    }
+                    if ctx.opts.expand_route_shape_points {
+                        collect_shape_points(&extensions, &mut point.shape_points);
+                    }
+                    if is_waypoint {
+                        point.garmin_waypoint = parse_garmin_waypoint(&extensions).map(Box::new);
+                    }
+                    if ctx.opts.include_extensions {
+                        point.extensions = Some(extensions);
+                    }
+                    point.had_extensions = true;
+                }
+                b"extensions" => {
+                    parse_point_extensions(reader, &mut point, ctx)?;
+                    point.had_extensions = true;
+                }
+                // Matched by local name in any namespace: vendors put their
+                // own prefixed elements here too
+                _ if ctx.wants_extra(e.local_name().as_ref()) => {
+                    let key = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                    let text = read_text_owned(reader, &e, ctx)?;
+                    point.extra.insert(key, text);
+                }
+                _ => ctx.skip_element(reader, &e)?,
             },
+            Ok(Event::Empty(e)) => ctx.note_ignored(&e),
             Ok(Event::End(e)) if e.name().0 == end_name.as_slice() => break,
             Ok(Event::Eof) => break,
             Err(e) => return Err(Gpx2GeoJsonError::XmlParse(e)),
@@ -153,19 +1105,302 @@ fn parse_point<'a>(
     Ok(Some(point))
 }
 
+/// Parse the <extensions> of a point, picking out known vendor values at any depth
+/// (e.g. `gpxtpx:TrackPointExtension/gpxtpx:hr`). Unknown elements are descended into.
+fn parse_point_extensions(
+    reader: &mut NsReader<&[u8]>,
+    point: &mut GpxPoint,
+    ctx: &mut ParseContext<'_>,
+) -> Result<()> {
+    let mut in_track_point_extension = false;
+    loop {
+        let event = reader.read_event();
+        if let Ok(Event::Start(e) | Event::Empty(e)) = &event {
+            ctx.check_element(reader, e)?;
+        }
+        match event {
+            Ok(Event::Start(e))
+                if let Some(name) = ctx.mapped_name(e.name().as_ref(), e.local_name().as_ref()) =>
+            {
+                let text = read_text_owned(reader, &e, ctx)?;
+                apply_point_extension(point, e.local_name().as_ref(), &text);
+                point.extra.insert(name, text);
+            }
+            Ok(Event::Start(e)) if e.local_name().as_ref() == b"TrackPointExtension" => {
+                in_track_point_extension = true;
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"TrackPointExtension" => {
+                in_track_point_extension = false;
+            }
+            Ok(Event::Start(e)) if POINT_EXTENSION_FIELDS.contains(&e.local_name().as_ref()) => {
+                let text = read_text_owned(reader, &e, ctx)?;
+                apply_point_extension(point, e.local_name().as_ref(), &text);
+            }
+            Ok(Event::Start(e))
+                if in_track_point_extension
+                    && TRACK_POINT_V2_FIELDS.contains(&e.local_name().as_ref()) =>
+            {
+                let text = read_text_owned(reader, &e, ctx)?;
+                apply_track_point_v2(point, e.local_name().as_ref(), &text);
+            }
+            Ok(Event::Start(e) | Event::Empty(e))
+                if ctx.opts.expand_route_shape_points && e.local_name().as_ref() == b"rpt" =>
+            {
+                match parse_lat_lon(&e, ctx.opts.lenient_numbers) {
+                    Ok(coords) => point.shape_points.push(coords),
+                    Err(err) => log::warn!("Skipping <rpt>: {err}"),
+                }
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"extensions" => break,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(Gpx2GeoJsonError::XmlParse(e)),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a point extension element makes it into the output, for the report.
+fn point_extension_kept(ctx: &ParseContext<'_>, el: &XmlElement, is_waypoint: bool) -> bool {
+    let name = el.name.as_bytes();
+    POINT_EXTENSION_FIELDS.contains(&name)
+        || TRACK_POINT_V2_FIELDS.contains(&name)
+        || ctx.mapped_element(el).is_some()
+        || (ctx.opts.auto_extensions && el.text.trim().parse::<f64>().is_ok())
+        || (ctx.opts.expand_route_shape_points && el.name == "rpt")
+        || (is_waypoint && el.name == "WaypointExtension")
+}
+
+/// Set typed GpxPoint fields from a captured <extensions> tree, at any depth.
+fn apply_point_extensions(point: &mut GpxPoint, elements: &[XmlElement]) {
+    for el in elements {
+        if el.name == "TrackPointExtension" {
+            for child in &el.children {
+                apply_track_point_v2(point, child.name.as_bytes(), &child.text);
+            }
+        }
+        if el.children.is_empty() {
+            apply_point_extension(point, el.name.as_bytes(), &el.text);
+        } else {
+            apply_point_extensions(point, &el.children);
+        }
+    }
+}
+
+/// Store a TrackPointExtension v2 speed (m/s) or course (degrees) on `point`,
+/// unless the point already has a GPX 1.0 <speed> / <course>.
+fn apply_track_point_v2(point: &mut GpxPoint, local_name: &[u8], text: &str) {
+    let value = text.trim().parse().ok();
+    match local_name {
+        b"speed" => point.speed = point.speed.or(value),
+        b"course" => point.course = point.course.or(value),
+        _ => {}
+    }
+}
+
+/// Collect Garmin `<gpxx:rpt lat lon>` shape points from a captured tree, at any depth.
+fn collect_shape_points(elements: &[XmlElement], shape_points: &mut Vec<(f64, f64)>) {
+    for el in elements {
+        if el.name == "rpt" {
+            let attr = |name| {
+                el.attributes
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .and_then(|(_, value)| value.trim().parse::<f64>().ok())
+            };
+            match (attr("lat"), attr("lon")) {
+                (Some(lat), Some(lon)) => shape_points.push((lat, lon)),
+                _ => log::warn!("Skipping <rpt> without a valid lat/lon"),
+            }
+        } else {
+            collect_shape_points(&el.children, shape_points);
+        }
+    }
+}
+
+/// Store one known extension value (see `POINT_EXTENSION_FIELDS`) on `point`.
+fn apply_point_extension(point: &mut GpxPoint, local_name: &[u8], text: &str) {
+    let text = text.trim();
+    match local_name {
+        b"hr" => point.heart_rate = text.parse().ok(),
+        b"cad" => point.cadence = text.parse().ok(),
+        b"atemp" | b"temp" => point.temperature = text.parse().ok(),
+        b"distance" => point.distance = text.parse().ok(),
+        b"power" => point.power = text.parse().ok(),
+        _ => {}
+    }
+}
+
+/// Read the Garmin `<gpxx:WaypointExtension>` from a waypoint's <extensions> tree.
+fn parse_garmin_waypoint(elements: &[XmlElement]) -> Option<GarminWaypoint> {
+    let ext = find_element(elements, "WaypointExtension")?;
+    let number = |name| find_text(&ext.children, name).and_then(|t| t.parse().ok());
+
+    let address = find_element(&ext.children, "Address").map(|addr| {
+        let street: Vec<&str> = addr
+            .children
+            .iter()
+            .filter(|el| el.name == "StreetAddress")
+            .map(|el| el.text.as_str())
+            .collect();
+        GarminAddress {
+            street: (!street.is_empty()).then(|| street.join(", ")),
+            city: find_text(&addr.children, "City"),
+            state: find_text(&addr.children, "State"),
+            country: find_text(&addr.children, "Country"),
+            postal_code: find_text(&addr.children, "PostalCode"),
+        }
+    });
+    let categories = find_element(&ext.children, "Categories")
+        .map(|cats| cats.children.iter().map(|el| el.text.clone()).collect())
+        .unwrap_or_default();
+
+    Some(GarminWaypoint {
+        address,
+        phone: find_text(&ext.children, "PhoneNumber"),
+        categories,
+        proximity: number("Proximity"),
+        depth: number("Depth"),
+    })
+}
+
+/// The first element named `name` in a captured tree, at any depth.
+fn find_element<'e>(elements: &'e [XmlElement], name: &str) -> Option<&'e XmlElement> {
+    elements.iter().find_map(|el| {
+        if el.name == name {
+            Some(el)
+        } else {
+            find_element(&el.children, name)
+        }
+    })
+}
+
+/// Text of the first element named `name` in a captured tree, at any depth.
+fn find_text(elements: &[XmlElement], name: &str) -> Option<String> {
+    find_element(elements, name).map(|el| el.text.clone())
+}
+
+/// Read an element and its whole subtree into an XmlElement.
+/// Elements nested deeper than `MAX_EXTENSION_DEPTH` are skipped.
+fn read_element(
+    reader: &mut NsReader<&[u8]>,
+    start: &BytesStart<'_>,
+    ctx: &mut ParseContext<'_>,
+    depth: usize,
+) -> Result<XmlElement> {
+    let mut element = element_from_start(start);
+    let end_name = start.name().0.to_vec();
+
+    loop {
+        let event = reader.read_event();
+        if let Ok(Event::Start(e) | Event::Empty(e)) = &event {
+            ctx.check_element(reader, e)?;
+        }
+        match event {
+            Ok(Event::Start(e)) if depth < MAX_EXTENSION_DEPTH => {
+                element
+                    .children
+                    .push(read_element(reader, &e, ctx, depth + 1)?);
+            }
+            Ok(Event::Start(e)) => {
+                reader
+                    .read_to_end(e.name())
+                    .map_err(Gpx2GeoJsonError::XmlParse)?;
+            }
+            Ok(Event::Empty(e)) => element.children.push(element_from_start(&e)),
+            Ok(Event::End(e)) if e.name().0 == end_name.as_slice() => break,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(Gpx2GeoJsonError::XmlParse(e)),
+            Ok(event) => push_text_event(&mut element.text, &event, ctx)?,
+        }
+    }
+
+    element.text = element.text.trim().to_string();
+    Ok(element)
+}
+
+fn element_from_start(start: &BytesStart<'_>) -> XmlElement {
+    XmlElement {
+        name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+        prefix: start
+            .name()
+            .prefix()
+            .map(|p| String::from_utf8_lossy(p.as_ref()).into_owned()),
+        attributes: start
+            .attributes()
+            .flatten()
+            // Namespace declarations (xmlns, xmlns:*) are not data
+            .filter(|attr| attr.key.as_namespace_binding().is_none())
+            .map(|attr| {
+                (
+                    String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned(),
+                    attribute_value(&attr).into_owned(),
+                )
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Parse one child element of <metadata>.
+fn parse_metadata_child(
+    e: &BytesStart<'_>,
+    reader: &mut NsReader<&[u8]>,
+    ctx: &mut ParseContext<'_>,
+    metadata: &mut GpxMetadata,
+) -> Result<()> {
+    match gpx_name(reader, e) {
+        b"name" => metadata.name = Some(read_text_field(reader, e, ctx)?),
+        b"desc" => metadata.desc = Some(read_text_field(reader, e, ctx)?),
+        b"time" => {
+            let time = read_text_owned(reader, e, ctx)?;
+            ctx.check_time(&time)?;
+            metadata.time = Some(time);
+        }
+        b"keywords" => metadata.keywords = Some(read_text_owned(reader, e, ctx)?),
+        b"bounds" => {
+            metadata.bounds = parse_bounds(e);
+            reader
+                .read_to_end(e.name())
+                .map_err(Gpx2GeoJsonError::XmlParse)?;
+        }
+        _ => ctx.skip_element(reader, e)?,
+    }
+    Ok(())
+}
+
+/// Parse the attributes of a <bounds> element; `None` unless all four are valid.
+fn parse_bounds(e: &BytesStart<'_>) -> Option<GpxBounds> {
+    let (mut min_lat, mut min_lon, mut max_lat, mut max_lon) = (None, None, None, None);
+    for attr in e.attributes().flatten() {
+        let val = attribute_value(&attr).trim().parse::<f64>().ok();
+        match attr.key.local_name().as_ref() {
+            b"minlat" => min_lat = val,
+            b"minlon" => min_lon = val,
+            b"maxlat" => max_lat = val,
+            b"maxlon" => max_lon = val,
+            _ => {}
+        }
+    }
+    Some(GpxBounds {
+        min_lat: min_lat?,
+        min_lon: min_lon?,
+        max_lat: max_lat?,
+        max_lon: max_lon?,
+    })
+}
+
 /// Parse a <link> element.
 fn parse_link<'a>(
     start: &BytesStart<'a>,
-    reader: &mut Reader<&'a [u8]>,
+    reader: &mut NsReader<&'a [u8]>,
+    ctx: &mut ParseContext<'_>,
 ) -> Result<GpxLink> {
     let mut href = String::new();
-    for attr_result in start.attributes() {
-        if let Ok(attr) = attr_result {
-            if attr.key.local_name().as_ref() == b"href" {
-                href = std::str::from_utf8(&attr.value)
-                    .unwrap_or_default()
-                    .to_string();
-            }
+    for attr in start.attributes().flatten() {
+        if attr.key.local_name().as_ref() == b"href" {
+            href = attribute_value(&attr).into_owned();
         }
     }
 
@@ -174,14 +1409,10 @@ fn parse_link<'a>(
 
     loop {
         match reader.read_event() {
-            Ok(Event::Start(e)) => match e.local_name().as_ref() {
-                b"text" => text = Some(read_text_owned(reader, &e)?),
-                b"type" => link_type = Some(read_text_owned(reader, &e)?),
-                _ => {
-                    reader
-                        .read_to_end(e.name())
-                        .map_err(Gpx2GeoJsonError::XmlParse)?;
-                }
+            Ok(Event::Start(e)) => match gpx_name(reader, &e) {
+                b"text" => text = Some(read_text_owned(reader, &e, ctx)?),
+                b"type" => link_type = Some(read_text_owned(reader, &e, ctx)?),
+                _ => ctx.skip_element(reader, &e)?,
             },
             Ok(Event::End(e)) if e.local_name().as_ref() == b"link" => break,
             Ok(Event::Eof) => break,
@@ -197,169 +1428,289 @@ fn parse_link<'a>(
     })
 }
 
-/// Parse a <rte> element.
-fn parse_route<'a>(reader: &mut Reader<&'a [u8]>) -> Result<GpxRoute> {
-    let mut route = GpxRoute::default();
+/// Parse one child element of <rte>; route points are returned (see `parse_child`).
+fn parse_route_child<'a>(
+    e: &BytesStart<'a>,
+    reader: &mut NsReader<&'a [u8]>,
+    ctx: &mut ParseContext<'_>,
+    route: &mut GpxRoute,
+) -> Result<Option<GpxPoint>> {
+    match gpx_name(reader, e) {
+        b"name" => route.name = Some(read_text_field(reader, e, ctx)?),
+        b"cmt" => route.cmt = Some(read_text_field(reader, e, ctx)?),
+        b"desc" => route.desc = Some(read_text_field(reader, e, ctx)?),
+        b"src" => route.src = Some(read_text_field(reader, e, ctx)?),
+        b"type" => route.route_type = Some(read_text_owned(reader, e, ctx)?),
+        b"number" => {
+            let text = read_text_owned(reader, e, ctx)?;
+            route.number = text.parse::<u32>().ok();
+        }
+        b"link" => route.link = Some(parse_link(e, reader, ctx)?),
+        b"url" => {
+            route.link.get_or_insert_default().href = read_text_owned(reader, e, ctx)?;
+        }
+        b"urlname" => {
+            route.link.get_or_insert_default().text = Some(read_text_owned(reader, e, ctx)?);
+        }
+        b"rtept" => return parse_point(e, reader, ctx),
+        b"extensions" => {
+            let extensions = read_element(reader, e, ctx, 0)?.children;
+            route.display_color = find_text(&extensions, "DisplayColor");
+            ctx.apply_extension_mappings(&extensions, &mut route.extra);
+            ctx.note_dropped_extensions(&extensions, &|ctx, el| {
+                el.name == "DisplayColor" || ctx.mapped_element(el).is_some()
+            });
+            route.had_extensions = true;
+            if ctx.opts.include_extensions {
+                route.extensions = Some(extensions);
+            }
+        }
+        _ => ctx.skip_element(reader, e)?,
+    }
+    Ok(None)
+}
 
-    loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) => match e.local_name().as_ref() {
-                b"name" => route.name = Some(read_text_owned(reader, &e)?),
-                b"cmt" => route.cmt = Some(read_text_owned(reader, &e)?),
-                b"desc" => route.desc = Some(read_text_owned(reader, &e)?),
-                b"src" => route.src = Some(read_text_owned(reader, &e)?),
-                b"type" => route.route_type = Some(read_text_owned(reader, &e)?),
-                b"number" => {
-                    let text = read_text_owned(reader, &e)?;
-                    route.number = text.parse::<u32>().ok();
-                }
-                b"link" => route.link = Some(parse_link(&e, reader)?),
-                b"rtept" => {
-                    if let Some(pt) = parse_point(&e, reader)? {
-                        route.points.push(pt);
-                    }
-                }
-                _ => {
-                    reader
-                        .read_to_end(e.name())
-                        .map_err(Gpx2GeoJsonError::XmlParse)?;
-                }
-            },
-            Ok(Event::Empty(e)) => {
-                if e.local_name().as_ref() == b"rtept" {
-                    if let Ok((lat, lon)) = parse_lat_lon(&e) {
-                        route.points.push(GpxPoint::new(lat, lon));
-                    }
-                }
+/// Parse one child element of <trk> other than <trkseg>.
+fn parse_track_child<'a>(
+    e: &BytesStart<'a>,
+    reader: &mut NsReader<&'a [u8]>,
+    ctx: &mut ParseContext<'_>,
+    track: &mut GpxTrack,
+) -> Result<()> {
+    match gpx_name(reader, e) {
+        b"name" => track.name = Some(read_text_field(reader, e, ctx)?),
+        b"cmt" => track.cmt = Some(read_text_field(reader, e, ctx)?),
+        b"desc" => track.desc = Some(read_text_field(reader, e, ctx)?),
+        b"src" => track.src = Some(read_text_field(reader, e, ctx)?),
+        b"type" => track.track_type = Some(read_text_owned(reader, e, ctx)?),
+        b"number" => {
+            let text = read_text_owned(reader, e, ctx)?;
+            track.number = text.parse::<u32>().ok();
+        }
+        b"link" => track.link = Some(parse_link(e, reader, ctx)?),
+        b"url" => {
+            track.link.get_or_insert_default().href = read_text_owned(reader, e, ctx)?;
+        }
+        b"urlname" => {
+            track.link.get_or_insert_default().text = Some(read_text_owned(reader, e, ctx)?);
+        }
+        b"extensions" => {
+            let extensions = read_element(reader, e, ctx, 0)?.children;
+            track.display_color = find_text(&extensions, "DisplayColor");
+            ctx.apply_extension_mappings(&extensions, &mut track.extra);
+            ctx.note_dropped_extensions(&extensions, &|ctx, el| {
+                el.name == "DisplayColor" || ctx.mapped_element(el).is_some()
+            });
+            track.had_extensions = true;
+            if ctx.opts.include_extensions {
+                track.extensions = Some(extensions);
             }
-            Ok(Event::End(e)) if e.local_name().as_ref() == b"rte" => break,
-            Ok(Event::Eof) => break,
-            Err(e) => return Err(Gpx2GeoJsonError::XmlParse(e)),
-            _ => {}
         }
+        _ => ctx.skip_element(reader, e)?,
     }
+    Ok(())
+}
 
-    Ok(route)
+/// Parse one child element of <trkseg>; track points are returned (see `parse_child`).
+fn parse_segment_child<'a>(
+    e: &BytesStart<'a>,
+    reader: &mut NsReader<&'a [u8]>,
+    ctx: &mut ParseContext<'_>,
+    segment: &mut GpxSegment,
+) -> Result<Option<GpxPoint>> {
+    match gpx_name(reader, e) {
+        b"trkpt" => return parse_point(e, reader, ctx),
+        b"extensions" if ctx.opts.include_extensions || ctx.opts.include_diagnostics => {
+            let extensions = read_element(reader, e, ctx, 0)?.children;
+            ctx.note_dropped_extensions(&extensions, &|_, _| false);
+            if ctx.opts.include_extensions {
+                segment.extensions = Some(extensions);
+            }
+        }
+        _ => ctx.skip_element(reader, e)?,
+    }
+    Ok(None)
 }
 
-/// Parse a <trk> element.
-fn parse_track<'a>(reader: &mut Reader<&'a [u8]>) -> Result<GpxTrack> {
-    let mut track = GpxTrack::default();
+/// Read text content of an element as an owned String.
+/// Handles regular text, CDATA sections, and entity references (Event::GeneralRef).
+fn read_text_owned(
+    reader: &mut NsReader<&[u8]>,
+    start: &BytesStart<'_>,
+    ctx: &mut ParseContext<'_>,
+) -> Result<String> {
+    let end_name = start.name().0.to_vec();
+    let mut text = String::new();
 
     loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) => match e.local_name().as_ref() {
-                b"name" => track.name = Some(read_text_owned(reader, &e)?),
-                b"cmt" => track.cmt = Some(read_text_owned(reader, &e)?),
-                b"desc" => track.desc = Some(read_text_owned(reader, &e)?),
-                b"src" => track.src = Some(read_text_owned(reader, &e)?),
-                b"type" => track.track_type = Some(read_text_owned(reader, &e)?),
-                b"number" => {
-                    let text = read_text_owned(reader, &e)?;
-                    track.number = text.parse::<u32>().ok();
-                }
-                b"link" => track.link = Some(parse_link(&e, reader)?),
-                b"trkseg" => {
-                    let seg = parse_segment(reader)?;
-                    if !seg.points.is_empty() {
-                        track.segments.push(seg);
-                    }
-                }
-                _ => {
-                    reader
-                        .read_to_end(e.name())
-                        .map_err(Gpx2GeoJsonError::XmlParse)?;
-                }
-            },
-            Ok(Event::End(e)) if e.local_name().as_ref() == b"trk" => break,
+        let event = reader.read_event();
+        if let Ok(Event::Start(e) | Event::Empty(e)) = &event {
+            ctx.check_element(reader, e)?;
+        }
+        match event {
+            Ok(Event::End(e)) if e.name().0 == end_name.as_slice() => break,
             Ok(Event::Eof) => break,
             Err(e) => return Err(Gpx2GeoJsonError::XmlParse(e)),
-            _ => {}
+            Ok(event) => push_text_event(&mut text, &event, ctx)?,
         }
     }
 
-    Ok(track)
+    Ok(text)
 }
 
-/// Parse a <trkseg> element.
-fn parse_segment<'a>(reader: &mut Reader<&'a [u8]>) -> Result<GpxSegment> {
-    let mut segment = GpxSegment::default();
+/// Read a name/desc/cmt/src element, with whitespace normalized for `trimText`:
+/// the indentation of pretty-printed files is not part of the value.
+fn read_text_field(
+    reader: &mut NsReader<&[u8]>,
+    start: &BytesStart<'_>,
+    ctx: &mut ParseContext<'_>,
+) -> Result<String> {
+    let text = read_text_owned(reader, start, ctx)?;
+    if !ctx.opts.trim_text {
+        return Ok(text);
+    }
+    Ok(text.split_ascii_whitespace().collect::<Vec<_>>().join(" "))
+}
 
-    loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) => match e.local_name().as_ref() {
-                b"trkpt" => {
-                    if let Some(pt) = parse_point(&e, reader)? {
-                        segment.points.push(pt);
-                    }
-                }
-                _ => {
-                    reader
-                        .read_to_end(e.name())
-                        .map_err(Gpx2GeoJsonError::XmlParse)?;
+/// Append the text carried by a Text, CData or GeneralRef event; other events are ignored.
+fn push_text_event(text: &mut String, event: &Event<'_>, ctx: &mut ParseContext<'_>) -> Result<()> {
+    match event {
+        Event::Text(e) => {
+            let raw = std::str::from_utf8(e.as_ref()).unwrap_or_default();
+            text.push_str(raw);
+        }
+        Event::CData(e) => {
+            let s = std::str::from_utf8(e.as_ref()).unwrap_or_default();
+            text.push_str(s);
+        }
+        Event::GeneralRef(e) => {
+            // Handle character references (&#60; &#x3C;) and predefined entities
+            if let Ok(Some(ch)) = e.resolve_char_ref() {
+                text.push(ch);
+            } else {
+                let name = std::str::from_utf8(e.as_ref()).unwrap_or_default();
+                let before = text.len();
+                push_entity(text, name, ctx, 0)?;
+                ctx.expanded_bytes += text.len() - before;
+                if ctx.expanded_bytes > ctx.limits.max_total_expansion_bytes {
+                    return Err(Gpx2GeoJsonError::LimitExceeded {
+                        limit: "maxTotalExpansionBytes",
+                        max: ctx.limits.max_total_expansion_bytes,
+                    });
                 }
-            },
-            Ok(Event::Empty(e)) => {
-                if e.local_name().as_ref() == b"trkpt" {
-                    if let Ok((lat, lon)) = parse_lat_lon(&e) {
-                        segment.points.push(GpxPoint::new(lat, lon));
-                    }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Append the expansion of entity `&name;` to `text`.
+/// Unknown entities are dropped; DOCTYPE-declared ones are expanded recursively.
+fn push_entity(
+    text: &mut String,
+    name: &str,
+    ctx: &mut ParseContext<'_>,
+    depth: usize,
+) -> Result<()> {
+    // Predefined XML entities: amp, lt, gt, quot, apos
+    match name {
+        "amp" => text.push('&'),
+        "lt" => text.push('<'),
+        "gt" => text.push('>'),
+        "quot" => text.push('"'),
+        "apos" => text.push('\''),
+        _ => match ctx.entities.get(name).cloned() {
+            Some(value) => {
+                if depth >= ctx.limits.max_entity_depth {
+                    return Err(Gpx2GeoJsonError::LimitExceeded {
+                        limit: "maxEntityDepth",
+                        max: ctx.limits.max_entity_depth,
+                    });
                 }
+                push_entity_value(text, &value, ctx, depth + 1)?;
             }
-            Ok(Event::End(e)) if e.local_name().as_ref() == b"trkseg" => break,
-            Ok(Event::Eof) => break,
-            Err(e) => return Err(Gpx2GeoJsonError::XmlParse(e)),
-            _ => {}
+            None => match html::named_entity(name) {
+                Some(c) => text.push(c),
+                None => {
+                    log::debug!("Dropping unknown entity &{name};");
+                    ctx.note_unknown_entity(name);
+                }
+            },
+        },
+    }
+
+    if text.len() > ctx.limits.max_expanded_text_bytes {
+        return Err(Gpx2GeoJsonError::LimitExceeded {
+            limit: "maxExpandedTextBytes",
+            max: ctx.limits.max_expanded_text_bytes,
+        });
+    }
+    Ok(())
+}
+
+/// Append a declared entity value, expanding the references it contains.
+fn push_entity_value(
+    text: &mut String,
+    value: &str,
+    ctx: &mut ParseContext<'_>,
+    depth: usize,
+) -> Result<()> {
+    let mut rest = value;
+    while let Some(amp) = rest.find('&') {
+        text.push_str(&rest[..amp]);
+        let Some(semi) = rest[amp..].find(';') else {
+            rest = &rest[amp..];
+            break;
+        };
+        let name = &rest[amp + 1..amp + semi];
+        match parse_char_ref(name) {
+            Some(ch) => text.push(ch),
+            None => push_entity(text, name, ctx, depth)?,
         }
+        rest = &rest[amp + semi + 1..];
     }
+    text.push_str(rest);
+    Ok(())
+}
 
-    Ok(segment)
+/// Decode a character reference body such as `#233` or `#xE9`.
+fn parse_char_ref(name: &str) -> Option<char> {
+    let code = match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => name.strip_prefix('#')?.parse().ok()?,
+    };
+    char::from_u32(code)
 }
 
-/// Read text content of an element as an owned String.
-/// Handles regular text, CDATA sections, and entity references (Event::GeneralRef).
-fn read_text_owned<'a>(
-    reader: &mut Reader<&'a [u8]>,
-    start: &BytesStart<'_>,
-) -> Result<String> {
-    let end_name = start.name().0.to_vec();
-    let mut text = String::new();
+/// Collect `<!ENTITY name "value">` declarations from a DOCTYPE internal subset.
+/// Parameter entities and external (SYSTEM/PUBLIC) entities are ignored.
+fn parse_internal_entities(doctype: &str) -> HashMap<String, String> {
+    let mut entities = HashMap::new();
+    let mut rest = doctype;
 
-    loop {
-        match reader.read_event() {
-            Ok(Event::Text(e)) => {
-                let raw = std::str::from_utf8(e.as_ref()).unwrap_or_default();
-                text.push_str(raw);
-            }
-            Ok(Event::CData(e)) => {
-                let s = std::str::from_utf8(e.as_ref()).unwrap_or_default();
-                text.push_str(s);
-            }
-            Ok(Event::GeneralRef(e)) => {
-                // Handle character references (&#60; &#x3C;) and predefined entities
-                if let Ok(Some(ch)) = e.resolve_char_ref() {
-                    text.push(ch);
-                } else {
-                    // Predefined XML entities: amp, lt, gt, quot, apos
-                    let name = std::str::from_utf8(e.as_ref()).unwrap_or_default();
-                    match name {
-                        "amp" => text.push('&'),
-                        "lt" => text.push('<'),
-                        "gt" => text.push('>'),
-                        "quot" => text.push('"'),
-                        "apos" => text.push('\''),
-                        _ => {} // Unknown entity, skip
-                    }
-                }
-            }
-            Ok(Event::End(e)) if e.name().0 == end_name.as_slice() => break,
-            Ok(Event::Eof) => break,
-            Err(e) => return Err(Gpx2GeoJsonError::XmlParse(e)),
-            _ => {}
+    while let Some(pos) = rest.find("<!ENTITY") {
+        rest = rest[pos + "<!ENTITY".len()..].trim_start();
+        if rest.starts_with('%') {
+            continue;
         }
+        let name_end = rest.find(|c: char| c.is_whitespace()).unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        rest = rest[name_end..].trim_start();
+
+        let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        let Some(len) = rest[1..].find(quote) else {
+            break;
+        };
+        entities
+            .entry(name.to_string())
+            .or_insert_with(|| rest[1..1 + len].to_string());
+        rest = &rest[1 + len + 1..];
     }
 
-    Ok(text)
+    entities
 }
 
 #[cfg(test)]
@@ -405,6 +1756,708 @@ mod tests {
         assert_eq!(pt.point_type.as_deref(), Some("POI"));
     }
 
+    #[test]
+    fn test_attribute_references_decoded() {
+        let xml = r#"<gpx>
+  <wpt lat="&#51;5.0" lon="139.0">
+    <link href="https://example.com/map?a=1&amp;b=&#x32;"><text>Map</text></link>
+    <extensions><x:photo x:src="a&amp;b.jpg"/></extensions>
+  </wpt>
+</gpx>"#;
+        let opts = ConvertOptions {
+            include_extensions: true,
+            ..Default::default()
+        };
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        let pt = &data.waypoints[0];
+        assert_eq!(pt.lat, 35.0);
+        assert_eq!(
+            pt.link.as_ref().unwrap().href,
+            "https://example.com/map?a=1&b=2"
+        );
+        let photo = &pt.extensions.as_ref().unwrap()[0];
+        assert_eq!(photo.attributes[0].1, "a&b.jpg");
+    }
+
+    #[test]
+    fn test_elevation_range() {
+        let xml = r#"<gpx><trk><trkseg>
+  <trkpt lat="35.0" lon="139.0"><ele>-9999</ele></trkpt>
+  <trkpt lat="35.1" lon="139.1"><ele>120.5</ele></trkpt>
+  <trkpt lat="35.2" lon="139.2"><ele>65535</ele></trkpt>
+</trkseg></trk></gpx>"#;
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"elevationRange": [-500, 9000]}"#).unwrap();
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        let eles: Vec<_> = data.tracks[0].segments[0]
+            .points
+            .iter()
+            .map(|pt| pt.ele)
+            .collect();
+        assert_eq!(eles, vec![None, Some(120.5), None]);
+    }
+
+    #[test]
+    fn test_trim_text() {
+        let xml = "<gpx><trk>\n  <name>\n      Morning   Run\n    </name>\n  <desc> Line one\n\tline two </desc>\n</trk></gpx>";
+        let track = &parse_gpx(xml).unwrap().tracks[0];
+        assert_eq!(track.name.as_deref(), Some("Morning Run"));
+        assert_eq!(track.desc.as_deref(), Some("Line one line two"));
+
+        let opts: ConvertOptions = serde_json::from_str(r#"{"trimText": false}"#).unwrap();
+        let track = &parse_gpx_with_options(xml, &opts).unwrap().tracks[0];
+        assert_eq!(track.name.as_deref(), Some("\n      Morning   Run\n    "));
+    }
+
+    #[test]
+    fn test_fix_quality_fields() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0">
+    <fix>3d</fix>
+    <sat>9</sat>
+    <hdop>1.2</hdop>
+    <vdop>1.8</vdop>
+    <pdop>2.1</pdop>
+  </wpt>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let pt = &data.waypoints[0];
+        assert_eq!(pt.fix.as_deref(), Some("3d"));
+        assert_eq!(pt.sat, Some(9));
+        assert_eq!(pt.hdop, Some(1.2));
+        assert_eq!(pt.vdop, Some(1.8));
+        assert_eq!(pt.pdop, Some(2.1));
+    }
+
+    #[test]
+    fn test_garmin_track_point_extension() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0">
+      <extensions>
+        <gpxtpx:TrackPointExtension>
+          <gpxtpx:hr>145</gpxtpx:hr>
+          <gpxtpx:cad>82</gpxtpx:cad>
+          <gpxtpx:atemp>18.5</gpxtpx:atemp>
+        </gpxtpx:TrackPointExtension>
+      </extensions>
+    </trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let pt = &data.tracks[0].segments[0].points[0];
+        assert_eq!(pt.heart_rate, Some(145));
+        assert_eq!(pt.cadence, Some(82));
+        assert_eq!(pt.temperature, Some(18.5));
+        assert!(pt.extensions.is_none());
+
+        // Capturing the raw extensions keeps the typed values
+        let opts = ConvertOptions {
+            include_extensions: true,
+            ..Default::default()
+        };
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        let pt = &data.tracks[0].segments[0].points[0];
+        assert_eq!(pt.heart_rate, Some(145));
+        assert_eq!(pt.temperature, Some(18.5));
+        let extensions = pt.extensions.as_ref().unwrap();
+        assert_eq!(extensions[0].name, "TrackPointExtension");
+        assert_eq!(extensions[0].children.len(), 3);
+    }
+
+    #[test]
+    fn test_garmin_track_point_extension_v2() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v2">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0">
+      <extensions>
+        <gpxtpx:TrackPointExtension>
+          <gpxtpx:hr>150</gpxtpx:hr>
+          <gpxtpx:speed>3.2</gpxtpx:speed>
+          <gpxtpx:course>270.5</gpxtpx:course>
+        </gpxtpx:TrackPointExtension>
+        <other:speed>99</other:speed>
+      </extensions>
+    </trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        for include_extensions in [false, true] {
+            let opts = ConvertOptions {
+                include_extensions,
+                ..Default::default()
+            };
+            let data = parse_gpx_with_options(xml, &opts).unwrap();
+            let pt = &data.tracks[0].segments[0].points[0];
+            assert_eq!(pt.heart_rate, Some(150));
+            assert_eq!(pt.speed, Some(3.2));
+            assert_eq!(pt.course, Some(270.5));
+        }
+    }
+
+    #[test]
+    fn test_gpx10_url_as_link() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.0">
+  <wpt lat="35.0" lon="139.0">
+    <url>https://example.com/wpt</url>
+    <urlname>Waypoint page</urlname>
+  </wpt>
+  <rte><urlname>Route page</urlname><url>https://example.com/rte</url></rte>
+  <trk><url>https://example.com/trk</url></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+
+        let link = data.waypoints[0].link.as_ref().unwrap();
+        assert_eq!(link.href, "https://example.com/wpt");
+        assert_eq!(link.text.as_deref(), Some("Waypoint page"));
+
+        // <urlname> before <url> still ends up in the same link
+        let link = data.routes[0].link.as_ref().unwrap();
+        assert_eq!(link.href, "https://example.com/rte");
+        assert_eq!(link.text.as_deref(), Some("Route page"));
+
+        let link = data.tracks[0].link.as_ref().unwrap();
+        assert_eq!(link.href, "https://example.com/trk");
+        assert_eq!(link.text, None);
+    }
+
+    #[test]
+    fn test_auto_extensions() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0">
+      <extensions>
+        <gpxtpx:TrackPointExtension><gpxtpx:hr>140</gpxtpx:hr></gpxtpx:TrackPointExtension>
+        <radar:rangeMeters>42.5</radar:rangeMeters>
+        <moxy:smo2>61</moxy:smo2>
+        <note>not a number</note>
+      </extensions>
+    </trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let opts = ConvertOptions {
+            auto_extensions: true,
+            ..Default::default()
+        };
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        let pt = &data.tracks[0].segments[0].points[0];
+        assert_eq!(pt.heart_rate, Some(140));
+        let names: Vec<&str> = pt.extension_values.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["rangeMeters", "smo2"]);
+        assert_eq!(pt.extension_values["rangeMeters"], 42.5);
+        assert!(pt.extensions.is_none());
+    }
+
+    #[test]
+    fn test_segment_extensions() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"/>
+    <extensions><lap><trigger>manual</trigger></lap></extensions>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        assert!(data.tracks[0].segments[0].extensions.is_none());
+
+        let opts = ConvertOptions {
+            include_extensions: true,
+            ..Default::default()
+        };
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        let seg = &data.tracks[0].segments[0];
+        assert_eq!(seg.points.len(), 1);
+        let extensions = seg.extensions.as_ref().unwrap();
+        assert_eq!(extensions[0].name, "lap");
+        assert_eq!(extensions[0].children[0].text, "manual");
+    }
+
+    #[test]
+    fn test_garmin_route_shape_points() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:gpxx="http://www.garmin.com/xmlschemas/GpxExtensions/v3">
+  <rte>
+    <rtept lat="35.0" lon="139.0">
+      <extensions>
+        <gpxx:RoutePointExtension>
+          <gpxx:Subclass>000000000000FFFFFFFFFFFFFFFFFFFFFFFF</gpxx:Subclass>
+          <gpxx:rpt lat="35.01" lon="139.02"/>
+          <gpxx:rpt lat="35.02" lon="139.03"/>
+        </gpxx:RoutePointExtension>
+      </extensions>
+    </rtept>
+    <rtept lat="35.1" lon="139.1"/>
+  </rte>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        assert!(data.routes[0].points[0].shape_points.is_empty());
+
+        for include_extensions in [false, true] {
+            let opts = ConvertOptions {
+                expand_route_shape_points: true,
+                include_extensions,
+                ..Default::default()
+            };
+            let data = parse_gpx_with_options(xml, &opts).unwrap();
+            assert_eq!(
+                data.routes[0].points[0].shape_points,
+                vec![(35.01, 139.02), (35.02, 139.03)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_lenient_numbers() {
+        let xml = r#"<gpx>
+  <wpt lat="35,6762" lon=" +139,6503 "><ele> 40,5 </ele></wpt>
+  <wpt lat="1,234.5" lon="139.0"/>
+</gpx>"#;
+        assert!(parse_gpx(xml).unwrap().waypoints.is_empty());
+
+        let opts = ConvertOptions {
+            lenient_numbers: true,
+            ..Default::default()
+        };
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        assert_eq!(data.waypoints.len(), 1);
+        let wpt = &data.waypoints[0];
+        assert_eq!((wpt.lat, wpt.lon, wpt.ele), (35.6762, 139.6503, Some(40.5)));
+    }
+
+    #[test]
+    fn test_dropped_data() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <metadata><author><name>Someone</name></author><copyright author="Someone"/></metadata>
+  <trk>
+    <extensions><gpxx:TrackExtension><gpxx:DisplayColor>Red</gpxx:DisplayColor></gpxx:TrackExtension></extensions>
+    <trkseg>
+      <extensions><acme:quality>good</acme:quality></extensions>
+      <trkpt lat="35.0" lon="139.0">
+        <extensions>
+          <gpxtpx:TrackPointExtension>
+            <gpxtpx:hr>140</gpxtpx:hr>
+            <gpxtpx:depth>0</gpxtpx:depth>
+          </gpxtpx:TrackPointExtension>
+          <moxy:smo2>61</moxy:smo2>
+        </extensions>
+      </trkpt>
+      <trkpt lon="139.1"/>
+      <trkpt lat="bad" lon="139.2"><ele>10</ele></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+        let opts = ConvertOptions {
+            include_diagnostics: true,
+            ..Default::default()
+        };
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        let dropped = &data.dropped;
+        assert_eq!(dropped.skipped_points, 2);
+        assert_eq!(dropped.ignored_elements["author"], 1);
+        assert_eq!(dropped.ignored_elements["copyright"], 1);
+        assert_eq!(dropped.dropped_extensions["gpxtpx"], 1);
+        assert_eq!(dropped.dropped_extensions["moxy"], 1);
+        assert_eq!(dropped.dropped_extensions["acme"], 1);
+        assert!(!dropped.dropped_extensions.contains_key("gpxx"));
+        assert_eq!(data.tracks[0].segments[0].points[0].heart_rate, Some(140));
+
+        // Mapped and auto-extracted values are not lost
+        let opts = ConvertOptions {
+            auto_extensions: true,
+            include_diagnostics: true,
+            ..Default::default()
+        };
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        assert!(!data.dropped.dropped_extensions.contains_key("moxy"));
+
+        // Nothing is counted unless asked for
+        let data = parse_gpx(xml).unwrap();
+        assert_eq!(data.dropped, DroppedData::default());
+    }
+
+    #[test]
+    fn test_fragment_input() {
+        let counts = |xml: &str| {
+            let data = parse_gpx(xml).unwrap();
+            let points = |segments: &[GpxSegment]| segments.iter().map(|s| s.points.len()).sum();
+            (
+                data.waypoints.len(),
+                data.routes
+                    .iter()
+                    .map(|r| r.points.len())
+                    .collect::<Vec<_>>(),
+                data.tracks
+                    .iter()
+                    .map(|t| points(&t.segments))
+                    .collect::<Vec<usize>>(),
+            )
+        };
+
+        let wpts = r#"<wpt lat="1" lon="2"/><wpt lat="3" lon="4"><name>x</name></wpt>"#;
+        assert_eq!(counts(wpts), (2, vec![], vec![]));
+        let trk = r#"<trk><trkseg><trkpt lat="1" lon="2"/></trkseg></trk>"#;
+        assert_eq!(counts(trk), (0, vec![], vec![1]));
+
+        let segments = r#"<trkseg><trkpt lat="1" lon="2"/></trkseg>
+<trkseg><trkpt lat="3" lon="4"><ele>5</ele></trkpt><trkpt lat="5" lon="6"/></trkseg>"#;
+        let data = parse_gpx(segments).unwrap();
+        assert_eq!(data.tracks.len(), 1);
+        assert_eq!(data.tracks[0].segments.len(), 2);
+        assert_eq!(data.tracks[0].segments[1].points[0].ele, Some(5.0));
+
+        let points = r#"<trkpt lat="1" lon="2"><time>2024-01-01T00:00:00Z</time></trkpt><trkpt lat="3" lon="4"/>"#;
+        assert_eq!(counts(points), (0, vec![], vec![2]));
+        let rtepts = r#"<rtept lat="1" lon="2"/><rtept lat="3" lon="4"></rtept>"#;
+        assert_eq!(counts(rtepts), (0, vec![2], vec![]));
+
+        // Inside a <gpx> root, stray points are not part of the document
+        let stray =
+            r#"<gpx><trkpt lat="1" lon="2"/><trkseg><trkpt lat="3" lon="4"/></trkseg></gpx>"#;
+        assert_eq!(counts(stray), (0, vec![], vec![]));
+    }
+
+    #[test]
+    fn test_parse_gpx_located() {
+        let xml = "<gpx>\n  <trk>\n  </rte>\n</gpx>";
+        let err = parse_gpx_located(xml, &ConvertOptions::default()).unwrap_err();
+        assert!(matches!(err.error, Gpx2GeoJsonError::XmlParse(_)));
+        assert_eq!(err.position as usize, xml.find("</rte>").unwrap());
+
+        let xml = "<gpx>\n  <wpt lat=\"95\" lon=\"139\"/>\n</gpx>";
+        let opts = ConvertOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let err = parse_gpx_located(xml, &opts).unwrap_err();
+        assert_eq!(err.position as usize, xml.find("<wpt").unwrap());
+    }
+
+    #[test]
+    fn test_strict() {
+        let strict = ConvertOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let error = |xml: &str| {
+            parse_gpx_with_options(xml, &strict)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let valid = r#"<gpx version="1.1">
+  <metadata><time>2024-01-01T00:00:00Z</time></metadata>
+  <wpt lat="-90" lon="-180"/>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"><ele>10</ele><time>2024-01-01T00:00:00+09:00</time><name>a</name></trkpt></trkseg></trk>
+</gpx>"#;
+        assert!(parse_gpx_with_options(valid, &strict).is_ok());
+
+        let bad_lat = r#"<gpx><trk><trkseg><trkpt lat="91" lon="0"/></trkseg></trk></gpx>"#;
+        assert_eq!(
+            error(bad_lat),
+            "Invalid GPX at byte 18: lat 91 on <trkpt> is outside [-90, 90]"
+        );
+        // Without strict the point is skipped
+        assert!(parse_gpx(bad_lat).is_ok());
+
+        let bad_lon = r#"<gpx><wpt lat="0" lon="180"></wpt></gpx>"#;
+        assert!(error(bad_lon).contains("lon 180 on <wpt> is outside [-180, 180)"));
+        let missing = r#"<gpx><wpt lat="0"/></gpx>"#;
+        assert!(error(missing).contains("Missing attribute 'lon'"));
+
+        let bad_time = r#"<gpx><wpt lat="0" lon="0"><time>yesterday</time></wpt></gpx>"#;
+        assert!(error(bad_time).contains("invalid dateTime 'yesterday'"));
+        let bad_ele = r#"<gpx><wpt lat="0" lon="0"><ele>high</ele></wpt></gpx>"#;
+        assert!(error(bad_ele).contains("invalid decimal 'high' in <ele>"));
+
+        let point_order = r#"<gpx><wpt lat="0" lon="0"><name>a</name><ele>1</ele></wpt></gpx>"#;
+        assert!(error(point_order).contains("<ele> must come before <name> in <wpt>"));
+        let gpx_order = r#"<gpx><trk/><wpt lat="0" lon="0"/></gpx>"#;
+        assert!(error(gpx_order).contains("<wpt> must come before <trk> in <gpx>"));
+        // Each concatenated document has its own order
+        let concatenated = r#"<gpx><trk/></gpx><gpx><wpt lat="0" lon="0"/></gpx>"#;
+        assert!(parse_gpx_with_options(concatenated, &strict).is_ok());
+
+        // Positions count from the start of the stream
+        let mut parser = StreamParser::new(strict.clone());
+        let err = bad_lat
+            .as_bytes()
+            .chunks(5)
+            .find_map(|chunk| parser.push(chunk).err())
+            .unwrap();
+        assert!(err.to_string().contains("at byte 18"));
+    }
+
+    #[test]
+    fn test_namespaces() {
+        // Prefixed GPX elements, and a vendor element named like a GPX field
+        let xml = r#"<?xml version="1.0"?>
+<g:gpx xmlns:g="http://www.topografix.com/GPX/1/1" xmlns:v="urn:vendor" version="1.1">
+  <g:trk>
+    <g:name>Prefixed</g:name>
+    <g:trkseg>
+      <g:trkpt lat="35.0" lon="139.0"><g:name>Real</g:name><v:name>Vendor</v:name></g:trkpt>
+      <g:trkpt lat="35.1" lon="139.1"><v:ele>999</v:ele></g:trkpt>
+    </g:trkseg>
+  </g:trk>
+  <v:wpt lat="1" lon="2"/>
+  <wpt xmlns="urn:vendor" lat="3" lon="4"/>
+</g:gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        assert_eq!(data.tracks[0].name.as_deref(), Some("Prefixed"));
+        let points = &data.tracks[0].segments[0].points;
+        assert_eq!(points[0].name.as_deref(), Some("Real"));
+        assert_eq!(points[1].ele, None);
+        assert!(data.waypoints.is_empty());
+
+        // Namespaces declared in an earlier chunk stay in scope
+        let expected = format!("{data:?}");
+        for chunk_size in [1, 5, 32] {
+            let mut parser = StreamParser::new(ConvertOptions::default());
+            for chunk in xml.as_bytes().chunks(chunk_size) {
+                parser.push(chunk).unwrap();
+            }
+            let data = parser.finish().unwrap();
+            assert_eq!(format!("{data:?}"), expected, "chunk size {chunk_size}");
+        }
+
+        // GPX 1.0 namespace, and an https variant
+        for ns in [
+            "http://www.topografix.com/GPX/1/0",
+            "https://www.topografix.com/GPX/1/1/",
+        ] {
+            let xml = format!(r#"<gpx xmlns="{ns}"><wpt lat="1" lon="2"/></gpx>"#);
+            assert_eq!(parse_gpx(&xml).unwrap().waypoints.len(), 1, "{ns}");
+        }
+    }
+
+    #[test]
+    fn test_stream_parser() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+  <metadata><name>Stream</name><bounds minlat="35" minlon="139" maxlat="36" maxlon="140"/></metadata>
+  <wpt lat="35.5" lon="139.5"><name>東京タワー</name><!-- comment --></wpt>
+  <wpt lat="35.6" lon="139.6"/>
+  <rte><name>Route</name><rtept lat="35.0" lon="139.0"/><rtept lat="35.1" lon="139.1"><ele>5</ele></rtept></rte>
+  <trk>
+    <name>Track &amp; field</name>
+    <trkseg>
+      <trkpt lat="35.0" lon="139.0"><ele>10.5</ele><time>2024-01-01T00:00:00Z</time>
+        <extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>120</gpxtpx:hr></gpxtpx:TrackPointExtension></extensions>
+      </trkpt>
+      <trkpt lat="35.1" lon="139.1"><desc><![CDATA[<b>bold</b>]]></desc></trkpt>
+    </trkseg>
+    <trkseg><trkpt lat="36.0" lon="140.0"/></trkseg>
+  </trk>
+</gpx>"#;
+        let expected = format!("{:?}", parse_gpx(xml).unwrap());
+
+        for chunk_size in [1, 2, 7, 64, xml.len()] {
+            let mut parser = StreamParser::new(ConvertOptions::default());
+            for chunk in xml.as_bytes().chunks(chunk_size) {
+                parser
+                    .push(chunk)
+                    .unwrap_or_else(|e| panic!("{chunk_size} {} {e}", parser.pending.len()));
+                // Only an incomplete tail is buffered
+                assert!(parser.pending.len() < 300, "chunk size {chunk_size}");
+            }
+            let data = parser.finish().unwrap();
+            assert_eq!(format!("{data:?}"), expected, "chunk size {chunk_size}");
+        }
+    }
+
+    #[test]
+    fn test_stream_parser_errors() {
+        let mut parser = StreamParser::new(ConvertOptions::default());
+        parser.push(b"<gpx><trk><name>Broken").unwrap();
+        assert!(parser.push(b"</trk></gpx>").is_err());
+
+        // An error before the end of the buffered input fails the push that
+        // contains it, rather than being retried on every later push
+        let bad = br#"<gpx><trk><trkseg><trkpt lat="1" lon="2"><ele>5</ele oops></trkpt>"#;
+        let mut parser = StreamParser::new(ConvertOptions::default());
+        parser.push(b"<gpx><trk><trkseg>").unwrap();
+        assert!(parser.push(&bad[18..]).is_err());
+        // At the very end of a chunk it could still be cut off, so the next push reports it
+        let mut parser = StreamParser::new(ConvertOptions::default());
+        parser.push(&bad[..bad.len() - 8]).unwrap();
+        assert!(parser.push(br#"</trkpt><trkpt lat="3" lon="4"/>"#).is_err());
+
+        // A tag cut off at the end of the input only fails at finish()
+        let truncated = r#"<gpx><trk><trkseg><trkpt lat="1" lon="2"/><trkpt lat="3""#;
+        assert!(parse_gpx(truncated).is_err());
+        let mut parser = StreamParser::new(ConvertOptions::default());
+        parser.push(truncated.as_bytes()).unwrap();
+        assert!(parser.finish().is_err());
+    }
+
+    #[test]
+    fn test_limit_stops_early() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"/>
+  <wpt lat="35.1" lon="139.1"/>
+  <trk><name>First</name><trkseg><trkpt lat="35.0" lon="139.0"/></trkseg></trk>
+  <trk><name>Second</name><trkseg><trkpt lat="35.0" lon="139.0"/></trkseg></trk>
+</gpx>"#;
+        // Routes are unlimited, so the rest of the document is still scanned
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"limit": {"waypoints": 1, "tracks": 1}}"#).unwrap();
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        assert_eq!(data.waypoints.len(), 1);
+        assert_eq!(data.tracks.len(), 1);
+        assert_eq!(data.tracks[0].name.as_deref(), Some("First"));
+
+        // Once every included type is at its limit, the rest is never read
+        let malformed = format!(
+            "{}<trk><name>Broken</trk>",
+            &xml[..xml.len() - "</gpx>".len()]
+        );
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"limit": {"tracks": 1}, "types": ["track"]}"#).unwrap();
+        let data = parse_gpx_with_options(&malformed, &opts).unwrap();
+        assert_eq!(data.tracks.len(), 1);
+        assert!(parse_gpx(&malformed).is_err());
+    }
+
+    #[test]
+    fn test_types_skip_excluded_elements() {
+        let xml = r#"<gpx>
+  <wpt lat="35.0" lon="139.0"/>
+  <wpt lat="35.2" lon="139.2"><name>Far</name></wpt>
+  <rte><rtept lat="35.0" lon="139.0"/></rte>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/><trkpt lat="bad" lon="139.1"/></trkseg></trk>
+</gpx>"#;
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"types": ["waypoint"], "includeDiagnostics": true}"#).unwrap();
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        assert_eq!(data.waypoints.len(), 2);
+        assert!(data.routes.is_empty() && data.tracks.is_empty());
+        // The invalid trkpt is never looked at
+        assert_eq!(data.dropped.skipped_points, 0);
+
+        let opts: ConvertOptions = serde_json::from_str(r#"{"types": ["track"]}"#).unwrap();
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        assert!(data.waypoints.is_empty() && data.routes.is_empty());
+        assert_eq!(data.tracks[0].segments[0].points.len(), 1);
+
+        // Lines are still read when waypoints are filtered by them
+        let opts: ConvertOptions =
+            serde_json::from_str(r#"{"types": ["waypoint"], "waypointsNearTracksOnly": 100}"#)
+                .unwrap();
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        assert_eq!((data.routes.len(), data.tracks.len()), (1, 1));
+    }
+
+    #[test]
+    fn test_garmin_waypoint_extension() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:gpxx="http://www.garmin.com/xmlschemas/GpxExtensions/v3">
+  <wpt lat="35.0" lon="139.0">
+    <name>Hotel</name>
+    <extensions>
+      <gpxx:WaypointExtension>
+        <gpxx:Proximity>150</gpxx:Proximity>
+        <gpxx:Depth>3.5</gpxx:Depth>
+        <gpxx:Categories>
+          <gpxx:Category>Lodging</gpxx:Category>
+          <gpxx:Category>Favorites</gpxx:Category>
+        </gpxx:Categories>
+        <gpxx:Address>
+          <gpxx:StreetAddress>1-2-3 Shiba</gpxx:StreetAddress>
+          <gpxx:StreetAddress>Building 4</gpxx:StreetAddress>
+          <gpxx:City>Minato</gpxx:City>
+          <gpxx:Country>Japan</gpxx:Country>
+          <gpxx:PostalCode>105-0011</gpxx:PostalCode>
+        </gpxx:Address>
+        <gpxx:PhoneNumber Category="Phone">+81-3-0000-0000</gpxx:PhoneNumber>
+      </gpxx:WaypointExtension>
+    </extensions>
+  </wpt>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let wpt = &data.waypoints[0];
+        assert!(wpt.extensions.is_none());
+        let garmin = wpt.garmin_waypoint.as_ref().unwrap();
+        assert_eq!(garmin.proximity, Some(150.0));
+        assert_eq!(garmin.depth, Some(3.5));
+        assert_eq!(garmin.categories, vec!["Lodging", "Favorites"]);
+        assert_eq!(garmin.phone.as_deref(), Some("+81-3-0000-0000"));
+        let address = garmin.address.as_ref().unwrap();
+        assert_eq!(address.street.as_deref(), Some("1-2-3 Shiba, Building 4"));
+        assert_eq!(address.city.as_deref(), Some("Minato"));
+        assert_eq!(address.state, None);
+        assert_eq!(address.postal_code.as_deref(), Some("105-0011"));
+    }
+
+    #[test]
+    fn test_bare_power_extension() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0"><extensions><power>215</power></extensions></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        assert_eq!(data.tracks[0].segments[0].points[0].power, Some(215));
+    }
+
+    #[test]
+    fn test_cluetrust_gpxdata_extension() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:gpxdata="http://www.cluetrust.com/XML/GPXDATA/1/0">
+  <trk><trkseg>
+    <trkpt lat="35.0" lon="139.0">
+      <extensions>
+        <gpxdata:hr>132</gpxdata:hr>
+        <gpxdata:temp>21.5</gpxdata:temp>
+        <gpxdata:distance>1520.4</gpxdata:distance>
+      </extensions>
+    </trkpt>
+  </trkseg></trk>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let pt = &data.tracks[0].segments[0].points[0];
+        assert_eq!(pt.heart_rate, Some(132));
+        assert_eq!(pt.temperature, Some(21.5));
+        assert_eq!(pt.distance, Some(1520.4));
+    }
+
+    #[test]
+    fn test_extra_point_elements() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns="http://www.topografix.com/GPX/1/1">
+  <wpt lat="35.0" lon="139.0">
+    <odometer>36.7</odometer>
+    <customtag>left</customtag>
+    <vdop>3.0</vdop>
+  </wpt>
+  <wpt lat="35.0" lon="139.0">
+    <v:customtag xmlns:v="urn:vendor">5</v:customtag>
+  </wpt>
+</gpx>"#;
+        let opts = ConvertOptions {
+            extra_point_elements: vec!["odometer".to_string(), "customtag".to_string()],
+            ..Default::default()
+        };
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        let extra = &data.waypoints[0].extra;
+        assert_eq!(extra.get("odometer").map(String::as_str), Some("36.7"));
+        assert_eq!(extra.get("customtag").map(String::as_str), Some("left"));
+        assert!(!extra.contains_key("vdop"));
+        // A vendor-prefixed child outside <extensions>
+        let extra = &data.waypoints[1].extra;
+        assert_eq!(extra.get("customtag").map(String::as_str), Some("5"));
+
+        // Nothing is captured unless requested
+        let data = parse_gpx(xml).unwrap();
+        assert!(data.waypoints[0].extra.is_empty());
+    }
+
     #[test]
     fn test_simple_route() {
         let xml = r#"<?xml version="1.0"?>
@@ -608,8 +2661,133 @@ mod tests {
   </wpt>
 </gpx>"#;
         let data = parse_gpx(xml).unwrap();
-        // quick-xml's read_text handles standard XML entities
-        assert!(data.waypoints[0].name.is_some());
+        assert_eq!(data.waypoints[0].name.as_deref(), Some("Café & Bar"));
+
+        let xml = r#"<gpx><wpt lat="35.0" lon="139.0"><desc>12&deg;C &ndash; &Uuml;ber&nbsp;alles &bogus;</desc></wpt></gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        assert_eq!(
+            data.waypoints[0].desc.as_deref(),
+            Some("12°C – Über\u{a0}alles")
+        );
+    }
+
+    #[test]
+    fn test_dtd_entities_resolved() {
+        let xml = r#"<?xml version="1.0"?>
+<!DOCTYPE gpx [
+  <!ENTITY hut "Hut &amp; Caf&#233;">
+  <!ENTITY summit "&hut; Summit">
+  <!ENTITY % param "ignored">
+]>
+<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><name>&summit;</name></wpt>
+</gpx>"#;
+        let opts = ConvertOptions {
+            resolve_dtd_entities: true,
+            ..Default::default()
+        };
+        let data = parse_gpx_with_options(xml, &opts).unwrap();
+        assert_eq!(data.waypoints[0].name.as_deref(), Some("Hut & Café Summit"));
+
+        // Disabled by default: declared entities are dropped
+        let data = parse_gpx(xml).unwrap();
+        assert_eq!(data.waypoints[0].name.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_recursive_dtd_entity_rejected() {
+        let xml = r#"<?xml version="1.0"?>
+<!DOCTYPE gpx [
+  <!ENTITY a "&b;">
+  <!ENTITY b "&a;">
+]>
+<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"><name>&a;</name></wpt>
+</gpx>"#;
+        let opts = ConvertOptions {
+            resolve_dtd_entities: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            parse_gpx_with_options(xml, &opts),
+            Err(Gpx2GeoJsonError::LimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_entity_expansion_limits() {
+        // Billion laughs: each level expands to ten of the one below
+        let mut doctype = String::from("<!ENTITY l0 \"lol\">");
+        for i in 1..=7 {
+            let refs = format!("&l{};", i - 1).repeat(10);
+            doctype.push_str(&format!("<!ENTITY l{i} \"{refs}\">"));
+        }
+        let xml = format!(
+            "<!DOCTYPE gpx [{doctype}]><gpx><wpt lat=\"1\" lon=\"2\"><name>&l7;</name></wpt></gpx>"
+        );
+        let opts = ConvertOptions {
+            resolve_dtd_entities: true,
+            ..Default::default()
+        };
+        let err = parse_gpx_with_options(&xml, &opts).unwrap_err();
+        assert!(matches!(
+            err,
+            Gpx2GeoJsonError::LimitExceeded {
+                limit: "maxExpandedTextBytes",
+                ..
+            }
+        ));
+
+        // Many values each under the per-text limit still add up
+        let big = "x".repeat(1000);
+        let wpts = "<wpt lat=\"1\" lon=\"2\"><name>&big;</name></wpt>".repeat(20);
+        let xml = format!("<!DOCTYPE gpx [<!ENTITY big \"{big}\">]><gpx>{wpts}</gpx>");
+        assert_eq!(
+            parse_gpx_with_options(&xml, &opts).unwrap().waypoints.len(),
+            20
+        );
+        config::init(
+            serde_json::from_value(serde_json::json!({
+                "panicHook": false,
+                "limits": { "maxTotalExpansionBytes": 10_000 }
+            }))
+            .unwrap(),
+        );
+        let err = parse_gpx_with_options(&xml, &opts).unwrap_err();
+        config::init(config::InitConfig::default());
+        assert_eq!(
+            err.to_string(),
+            "Limit exceeded: maxTotalExpansionBytes (max 10000)"
+        );
+    }
+
+    #[test]
+    fn test_element_limits() {
+        let limit = |xml: &str| match parse_gpx(xml) {
+            Err(Gpx2GeoJsonError::LimitExceeded { limit, .. }) => limit,
+            other => panic!("expected a limit error, got {other:?}"),
+        };
+        let nested = |depth: usize| format!("{}{}", "<x>".repeat(depth), "</x>".repeat(depth));
+
+        // Nesting at document level, in point extensions and in text elements
+        assert_eq!(limit(&format!("<gpx>{}</gpx>", nested(300))), "maxDepth");
+        let in_extensions = format!(
+            r#"<gpx><trk><trkseg><trkpt lat="1" lon="2"><extensions>{}</extensions></trkpt></trkseg></trk></gpx>"#,
+            nested(300)
+        );
+        assert_eq!(limit(&in_extensions), "maxDepth");
+        let in_name = format!(
+            r#"<gpx><wpt lat="1" lon="2"><name>{}</name></wpt></gpx>"#,
+            nested(300)
+        );
+        assert_eq!(limit(&in_name), "maxDepth");
+        assert!(parse_gpx(&format!("<gpx>{}</gpx>", nested(200))).is_ok());
+
+        let attributes: String = (0..300).map(|i| format!(" a{i}=\"\"")).collect();
+        assert_eq!(
+            limit(&format!(r#"<gpx><wpt lat="1" lon="2"{attributes}/></gpx>"#)),
+            "maxAttributes"
+        );
     }
 
     #[test]
@@ -0,0 +1,116 @@
+use geojson::{Feature, Geometry, Value};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::gpx_types::*;
+use crate::options::PreviewOptions;
+
+/// Build a single, heavily downsampled LineString Feature for thumbnails.
+///
+/// All track points (or route points, if the file has no tracks) are joined
+/// in document order and evenly sampled down to `max_points`, always keeping
+/// the first and last point. Returns a Feature with no geometry if the file
+/// has fewer than two points.
+pub fn to_preview_feature(data: &GpxData, opts: &PreviewOptions) -> Feature {
+    let mut points: Vec<&GpxPoint> = data
+        .tracks
+        .iter()
+        .flat_map(|trk| &trk.segments)
+        .flat_map(|seg| &seg.points)
+        .collect();
+    if points.is_empty() {
+        points = data.routes.iter().flat_map(|rte| &rte.points).collect();
+    }
+
+    let geometry = (points.len() >= 2).then(|| {
+        let coords = sample_indices(points.len(), opts.max_points)
+            .map(|i| vec![points[i].lon, points[i].lat])
+            .collect();
+        Geometry::new(Value::LineString(coords))
+    });
+
+    let mut props = Map::new();
+    props.insert(
+        "gpxType".to_string(),
+        JsonValue::String("preview".to_string()),
+    );
+    let name = data
+        .metadata
+        .as_ref()
+        .and_then(|m| m.name.as_ref())
+        .or_else(|| data.tracks.iter().find_map(|trk| trk.name.as_ref()))
+        .or_else(|| data.routes.iter().find_map(|rte| rte.name.as_ref()));
+    if let Some(name) = name {
+        props.insert("name".to_string(), JsonValue::String(name.clone()));
+    }
+    props.insert("pointCount".to_string(), points.len().into());
+
+    Feature {
+        bbox: None,
+        geometry,
+        id: None,
+        properties: Some(props),
+        foreign_members: None,
+    }
+}
+
+/// Evenly spaced indices into `0..len`, at most `max` of them (at least 2).
+fn sample_indices(len: usize, max: usize) -> impl Iterator<Item = usize> {
+    let count = len.min(max.max(2));
+    (0..count).map(move |i| {
+        if count == len {
+            i
+        } else {
+            ((i * (len - 1)) as f64 / (count - 1) as f64).round() as usize
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_gpx;
+
+    #[test]
+    fn test_preview_downsamples_tracks() {
+        let trkpts: String = (0..100)
+            .map(|i| format!(r#"<trkpt lat="35.0" lon="{}"/>"#, 139.0 + i as f64 * 0.001))
+            .collect();
+        let xml = format!(
+            r#"<gpx version="1.1"><trk><name>Ride</name><trkseg>{trkpts}</trkseg></trk></gpx>"#
+        );
+        let data = parse_gpx(&xml).unwrap();
+        let f = to_preview_feature(&data, &PreviewOptions { max_points: 10 });
+
+        match &f.geometry.as_ref().unwrap().value {
+            Value::LineString(coords) => {
+                assert_eq!(coords.len(), 10);
+                assert!((coords[0][0] - 139.0).abs() < 1e-9);
+                assert!((coords[9][0] - 139.099).abs() < 1e-9);
+            }
+            _ => panic!("Expected LineString"),
+        }
+        let props = f.properties.as_ref().unwrap();
+        assert_eq!(props["name"], "Ride");
+        assert_eq!(props["pointCount"], 100);
+    }
+
+    #[test]
+    fn test_preview_falls_back_to_routes() {
+        let xml = r#"<gpx version="1.1">
+  <rte><rtept lat="35.0" lon="139.0"/><rtept lat="35.1" lon="139.1"/></rte>
+</gpx>"#;
+        let data = parse_gpx(xml).unwrap();
+        let f = to_preview_feature(&data, &PreviewOptions::default());
+        assert!(matches!(
+            &f.geometry.as_ref().unwrap().value,
+            Value::LineString(coords) if coords.len() == 2
+        ));
+
+        let empty = parse_gpx(r#"<gpx version="1.1"/>"#).unwrap();
+        assert!(
+            to_preview_feature(&empty, &PreviewOptions::default())
+                .geometry
+                .is_none()
+        );
+    }
+}
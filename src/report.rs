@@ -0,0 +1,106 @@
+use geojson::GeoJson;
+use serde::Serialize;
+use serde_json::{Value as JsonValue, json};
+
+use crate::gpx_types::DroppedData;
+
+/// Result of `gpxToGeoJsonWithReport`.
+#[derive(Debug, Serialize)]
+pub struct ConversionReport {
+    pub geojson: GeoJson,
+    pub warnings: Vec<ConversionWarning>,
+}
+
+/// Something in the input that the conversion skipped or lost.
+#[derive(Debug, Serialize)]
+pub struct ConversionWarning {
+    /// Stable identifier, e.g. "skippedPoints".
+    pub code: &'static str,
+    /// Human-readable description, e.g. "3 point(s) skipped for missing or invalid lat/lon".
+    pub message: String,
+    /// The counts and names behind the message.
+    pub context: JsonValue,
+}
+
+impl ConversionWarning {
+    fn new(code: &'static str, message: String, context: JsonValue) -> Self {
+        Self {
+            code,
+            message,
+            context,
+        }
+    }
+}
+
+/// One warning per kind of loss counted in `dropped`, plus one for the
+/// `filtered_points` removed by the quality filters.
+pub fn conversion_warnings(
+    dropped: &DroppedData,
+    filtered_points: usize,
+) -> Vec<ConversionWarning> {
+    let mut warnings = Vec::new();
+    let skipped = dropped.skipped_points;
+    if skipped > 0 {
+        warnings.push(ConversionWarning::new(
+            "skippedPoints",
+            format!("{skipped} point(s) skipped for missing or invalid lat/lon"),
+            json!({ "count": skipped }),
+        ));
+    }
+    if filtered_points > 0 {
+        warnings.push(ConversionWarning::new(
+            "filteredPoints",
+            format!("{filtered_points} point(s) removed by minFixQuality / maxHdop"),
+            json!({ "count": filtered_points }),
+        ));
+    }
+    for (element, &count) in &dropped.ignored_elements {
+        warnings.push(ConversionWarning::new(
+            "ignoredElement",
+            format!("{count} <{element}> element(s) ignored"),
+            json!({ "element": element, "count": count }),
+        ));
+    }
+    for (prefix, &count) in &dropped.dropped_extensions {
+        warnings.push(ConversionWarning::new(
+            "droppedExtensions",
+            format!("{count} extension element(s) with prefix '{prefix}' not in the output"),
+            json!({ "prefix": prefix, "count": count }),
+        ));
+    }
+    for (entity, &count) in &dropped.unknown_entities {
+        warnings.push(ConversionWarning::new(
+            "unknownEntity",
+            format!("{count} reference(s) to undeclared entity &{entity}; dropped"),
+            json!({ "entity": entity, "count": count }),
+        ));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion_warnings() {
+        let mut dropped = DroppedData {
+            skipped_points: 3,
+            ..Default::default()
+        };
+        dropped.unknown_entities.insert("bogus".to_string(), 2);
+        let warnings = conversion_warnings(&dropped, 0);
+        let codes: Vec<&str> = warnings.iter().map(|w| w.code).collect();
+        assert_eq!(codes, vec!["skippedPoints", "unknownEntity"]);
+        assert_eq!(
+            warnings[0].message,
+            "3 point(s) skipped for missing or invalid lat/lon"
+        );
+        assert_eq!(
+            warnings[1].context,
+            json!({ "entity": "bogus", "count": 2 })
+        );
+
+        assert!(conversion_warnings(&DroppedData::default(), 0).is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+use geojson::{Feature, Value};
+use serde_json::Value as JsonValue;
+
+/// A position of a cut line, with the (line, point) index it came from in
+/// the original geometry, or `None` for a point inserted on the antimeridian.
+type CutPosition = (Option<(usize, usize)>, Vec<f64>);
+
+/// Rewrite features to conform strictly to RFC 7946.
+///
+/// Lines crossing the antimeridian are cut in two (section 3.1.9), and the
+/// `coordinateProperties` arrays are moved up into `properties`. Polygons
+/// are never emitted, so no winding order fix-up is needed.
+pub fn make_strict(features: &mut [Feature]) {
+    for feature in features {
+        cut_antimeridian(feature);
+        flatten_coordinate_properties(feature);
+    }
+}
+
+/// Split a LineString / MultiLineString feature at every antimeridian
+/// crossing into a MultiLineString, keeping `coordinateProperties` aligned.
+/// Points inserted on the antimeridian get `null` values. The lines of a
+/// GeometryCollection (`trackGeometryCollection`) are split the same way.
+pub fn cut_antimeridian(feature: &mut Feature) {
+    let Some(geometry) = &mut feature.geometry else {
+        return;
+    };
+    let Some((cut, nested)) = cut_value(&mut geometry.value) else {
+        return;
+    };
+
+    let columns = feature
+        .properties
+        .as_mut()
+        .and_then(|props| props.get_mut("coordinateProperties"))
+        .and_then(JsonValue::as_object_mut);
+    for values in columns.into_iter().flat_map(|c| c.values_mut()) {
+        let pick = |index: Option<(usize, usize)>| match index {
+            Some((line, pt)) if nested => values[line][pt].clone(),
+            Some((_, pt)) => values[pt].clone(),
+            None => JsonValue::Null,
+        };
+        let realigned: Vec<JsonValue> = cut
+            .iter()
+            .map(|line| line.iter().map(|(index, _)| pick(*index)).collect())
+            .collect();
+        *values = JsonValue::Array(realigned);
+    }
+}
+
+/// Cut the lines of `value` at the antimeridian. Returns where each position
+/// of the cut lines came from, and whether the original was a
+/// MultiLineString, or `None` if nothing was cut. In a GeometryCollection
+/// the `coordinateProperties` belong to the line, so its cut is returned.
+fn cut_value(value: &mut Value) -> Option<(Vec<Vec<CutPosition>>, bool)> {
+    let (lines, nested): (Vec<&Vec<Vec<f64>>>, bool) = match value {
+        Value::LineString(line) => (vec![line], false),
+        Value::MultiLineString(lines) => (lines.iter().collect(), true),
+        Value::GeometryCollection(geometries) => {
+            return geometries
+                .iter_mut()
+                .map(|g| cut_value(&mut g.value))
+                .fold(None, |found, cut| found.or(cut));
+        }
+        _ => return None,
+    };
+    if !lines
+        .iter()
+        .any(|line| line.windows(2).any(|w| crosses(&w[0], &w[1])))
+    {
+        return None;
+    }
+
+    let cut: Vec<Vec<CutPosition>> = lines
+        .iter()
+        .enumerate()
+        .flat_map(|(i, line)| cut_line(i, line))
+        .collect();
+    *value = Value::MultiLineString(
+        cut.iter()
+            .map(|line| line.iter().map(|(_, pos)| pos.clone()).collect())
+            .collect(),
+    );
+    Some((cut, nested))
+}
+
+/// Whether the segment from `a` to `b` is shorter going across the antimeridian.
+fn crosses(a: &[f64], b: &[f64]) -> bool {
+    (b[0] - a[0]).abs() > 180.0
+}
+
+/// Cut one line at its antimeridian crossings.
+fn cut_line(line_index: usize, line: &[Vec<f64>]) -> Vec<Vec<CutPosition>> {
+    let mut parts = Vec::new();
+    let mut current = Vec::new();
+    for (i, pos) in line.iter().enumerate() {
+        if i > 0 && crosses(&line[i - 1], pos) {
+            let (end, start) = crossing_positions(&line[i - 1], pos);
+            current.push((None, end));
+            parts.push(std::mem::take(&mut current));
+            current.push((None, start));
+        }
+        current.push((Some((line_index, i)), pos.clone()));
+    }
+    parts.push(current);
+    parts
+}
+
+/// The positions on either side of the antimeridian where `a`→`b` crosses it.
+/// Latitude and elevation are interpolated linearly.
+fn crossing_positions(a: &[f64], b: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let edge = if a[0] > 0.0 { 180.0 } else { -180.0 };
+    let b_lon = b[0] + 2.0 * edge;
+    let t = (edge - a[0]) / (b_lon - a[0]);
+    let rest: Vec<f64> = (1..a.len().min(b.len()))
+        .map(|k| a[k] + (b[k] - a[k]) * t)
+        .collect();
+
+    let end = std::iter::once(edge).chain(rest.iter().copied()).collect();
+    let start = std::iter::once(-edge).chain(rest).collect();
+    (end, start)
+}
+
+/// Move every `coordinateProperties` array up into `properties`.
+/// An array whose name is already a property is dropped.
+fn flatten_coordinate_properties(feature: &mut Feature) {
+    let Some(props) = &mut feature.properties else {
+        return;
+    };
+    let Some(JsonValue::Object(columns)) = props.remove("coordinateProperties") else {
+        return;
+    };
+    for (key, values) in columns {
+        if props.contains_key(&key) {
+            log::warn!("Dropping coordinate property {key}: conflicts with a feature property");
+            continue;
+        }
+        props.insert(key, values);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geojson::Geometry;
+    use serde_json::{Map, json};
+
+    fn line_feature(coords: Vec<Vec<f64>>, times: JsonValue) -> Feature {
+        let mut props = Map::new();
+        props.insert(
+            "coordinateProperties".to_string(),
+            json!({ "times": times }),
+        );
+        Feature {
+            bbox: None,
+            geometry: Some(Geometry::new(Value::LineString(coords))),
+            id: None,
+            properties: Some(props),
+            foreign_members: None,
+        }
+    }
+
+    #[test]
+    fn test_cut_antimeridian() {
+        let mut f = line_feature(
+            vec![vec![179.0, 10.0], vec![-179.0, 20.0], vec![-178.0, 20.0]],
+            json!(["t0", "t1", "t2"]),
+        );
+        cut_antimeridian(&mut f);
+
+        match &f.geometry.as_ref().unwrap().value {
+            Value::MultiLineString(lines) => {
+                assert_eq!(lines.len(), 2);
+                assert_eq!(lines[0], vec![vec![179.0, 10.0], vec![180.0, 15.0]]);
+                assert_eq!(lines[1][0], vec![-180.0, 15.0]);
+                assert_eq!(lines[1].len(), 3);
+            }
+            _ => panic!("Expected MultiLineString"),
+        }
+        assert_eq!(
+            f.properties.as_ref().unwrap()["coordinateProperties"]["times"],
+            json!([["t0", null], [null, "t1", "t2"]])
+        );
+    }
+
+    #[test]
+    fn test_cut_antimeridian_geometry_collection() {
+        let line = vec![vec![179.0, 10.0], vec![-179.0, 20.0]];
+        let mut f = line_feature(Vec::new(), json!(["t0", "t1"]));
+        f.geometry = Some(Geometry::new(Value::GeometryCollection(vec![
+            Geometry::new(Value::LineString(line)),
+            Geometry::new(Value::Point(vec![179.0, 10.0])),
+            Geometry::new(Value::Point(vec![-179.0, 20.0])),
+        ])));
+        cut_antimeridian(&mut f);
+
+        let Value::GeometryCollection(geometries) = &f.geometry.as_ref().unwrap().value else {
+            panic!("Expected GeometryCollection");
+        };
+        assert_eq!(
+            geometries[0].value,
+            Value::MultiLineString(vec![
+                vec![vec![179.0, 10.0], vec![180.0, 15.0]],
+                vec![vec![-180.0, 15.0], vec![-179.0, 20.0]],
+            ])
+        );
+        assert_eq!(geometries[1].value, Value::Point(vec![179.0, 10.0]));
+        assert_eq!(
+            f.properties.as_ref().unwrap()["coordinateProperties"]["times"],
+            json!([["t0", null], [null, "t1"]])
+        );
+    }
+
+    #[test]
+    fn test_make_strict_flattens_coordinate_properties() {
+        let mut features = vec![line_feature(
+            vec![vec![139.0, 35.0], vec![139.1, 35.1]],
+            json!(["t0", "t1"]),
+        )];
+        make_strict(&mut features);
+
+        let props = features[0].properties.as_ref().unwrap();
+        assert!(!props.contains_key("coordinateProperties"));
+        assert_eq!(props["times"], json!(["t0", "t1"]));
+        assert!(matches!(
+            features[0].geometry.as_ref().unwrap().value,
+            Value::LineString(_)
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+use serde_json::{Map, Value as JsonValue, json};
+
+use crate::activity;
+use crate::geo;
+use crate::gpx_types::GpxPoint;
+use crate::options::DistanceFormula;
+
+/// Per-feature track statistics for `includeStats`, computed over the
+/// points of the feature's lines (all segments of a MultiLineString).
+/// Elevation figures are left out when no point has an elevation, duration
+/// and speed when no point has a timestamp.
+pub fn track_stats(
+    lines: &[&[GpxPoint]],
+    gradient_window_m: f64,
+    formula: DistanceFormula,
+) -> Map<String, JsonValue> {
+    let mut stats = Map::new();
+    let points: Vec<&GpxPoint> = lines.iter().flat_map(|line| line.iter()).collect();
+    let Some(start) = points.first() else {
+        return stats;
+    };
+
+    let max_from_start = points
+        .iter()
+        .map(|pt| geo::distance(start, pt, formula))
+        .fold(0.0, f64::max);
+    stats.insert("maxDistanceFromStart".to_string(), json!(max_from_start));
+
+    let (center, radius) = bounding_circle(&points, formula);
+    stats.insert(
+        "boundingCircle".to_string(),
+        json!({ "center": [center.lon, center.lat], "radius": radius }),
+    );
+
+    if let Some(duration) = activity::duration(points.iter().copied()) {
+        stats.insert("duration".to_string(), json!(duration));
+        if duration > 0.0 {
+            let distance: f64 = lines
+                .iter()
+                .map(|line| geo::path_length(line, formula))
+                .sum();
+            stats.insert("averageSpeed".to_string(), json!(distance / duration));
+        }
+    }
+
+    let elevations = points.iter().filter_map(|pt| pt.ele);
+    if let Some((min, max)) = elevations.fold(None, |acc, ele| match acc {
+        None => Some((ele, ele)),
+        Some((min, max)) => Some((ele.min(min), ele.max(max))),
+    }) {
+        stats.insert("minElevation".to_string(), json!(min));
+        stats.insert("maxElevation".to_string(), json!(max));
+    }
+    let max_gradient = lines
+        .iter()
+        .filter_map(|line| max_gradient(line, gradient_window_m, formula))
+        .reduce(f64::max);
+    if let Some(gradient) = max_gradient {
+        stats.insert("maxGradient".to_string(), json!(gradient));
+    }
+
+    stats
+}
+
+/// Steepest climb along a line in percent, measured between each point and
+/// the first point at least `window_m` meters further on, so that GPS
+/// elevation noise between close points doesn't produce absurd grades.
+/// Points without an elevation are skipped. `None` if the line is shorter
+/// than the window.
+fn max_gradient(line: &[GpxPoint], window_m: f64, formula: DistanceFormula) -> Option<f64> {
+    let points: Vec<(&GpxPoint, f64)> = line
+        .iter()
+        .filter_map(|pt| pt.ele.map(|ele| (pt, ele)))
+        .collect();
+    let mut along = Vec::with_capacity(points.len());
+    let mut total = 0.0;
+    for (i, (pt, _)) in points.iter().enumerate() {
+        if i > 0 {
+            total += geo::distance(points[i - 1].0, pt, formula);
+        }
+        along.push(total);
+    }
+
+    let window_m = window_m.max(f64::EPSILON);
+    let mut best: Option<f64> = None;
+    let mut j = 0;
+    for i in 0..points.len() {
+        // The window end only moves forward as the start does
+        j = j.max(i);
+        while j < points.len() && along[j] - along[i] < window_m {
+            j += 1;
+        }
+        let Some((_, end_ele)) = points.get(j) else {
+            break;
+        };
+        let gradient = (end_ele - points[i].1) / (along[j] - along[i]) * 100.0;
+        best = Some(best.map_or(gradient, |b| b.max(gradient)));
+    }
+    best
+}
+
+/// A circle containing every point: centered on the middle of their
+/// bounding box, with the distance to the farthest point as radius (meters).
+/// Not the smallest enclosing circle, but never more than twice its radius.
+fn bounding_circle(points: &[&GpxPoint], formula: DistanceFormula) -> (GpxPoint, f64) {
+    let (mut min_lat, mut min_lon) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_lat, mut max_lon) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for pt in points {
+        min_lat = min_lat.min(pt.lat);
+        min_lon = min_lon.min(pt.lon);
+        max_lat = max_lat.max(pt.lat);
+        max_lon = max_lon.max(pt.lon);
+    }
+    let center = GpxPoint::new((min_lat + max_lat) / 2.0, (min_lon + max_lon) / 2.0);
+    let radius = points
+        .iter()
+        .map(|pt| geo::distance(&center, pt, formula))
+        .fold(0.0, f64::max);
+    (center, radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_track_stats() {
+        // Out and back along a meridian: ~1112 m north, then home
+        let line = vec![
+            GpxPoint::new(35.00, 139.0),
+            GpxPoint::new(35.01, 139.0),
+            GpxPoint::new(35.00, 139.0),
+        ];
+        let stats = track_stats(&[&line], 100.0, DistanceFormula::Haversine);
+
+        let max = stats["maxDistanceFromStart"].as_f64().unwrap();
+        assert!((max - 1112.0).abs() < 1.0, "got {max}");
+        let circle = &stats["boundingCircle"];
+        assert_eq!(circle["center"][0], 139.0);
+        assert!((circle["center"][1].as_f64().unwrap() - 35.005).abs() < 1e-9);
+        let radius = circle["radius"].as_f64().unwrap();
+        assert!((radius - 556.0).abs() < 1.0, "got {radius}");
+
+        assert!(!stats.contains_key("minElevation"));
+        assert!(!stats.contains_key("maxGradient"));
+        assert!(track_stats(&[], 100.0, DistanceFormula::Haversine).is_empty());
+    }
+
+    #[test]
+    fn test_elevation_stats() {
+        // Points ~11 m apart along a meridian
+        let point = |i: usize, ele: f64| {
+            let mut pt = GpxPoint::new(35.0 + i as f64 * 0.0001, 139.0);
+            pt.ele = Some(ele);
+            pt
+        };
+        // A steady 10% climb with a 2 m spike of noise at one point
+        let line: Vec<GpxPoint> = (0..20)
+            .map(|i| point(i, 100.0 + i as f64 * 1.112 + if i == 5 { 2.0 } else { 0.0 }))
+            .collect();
+        let stats = track_stats(&[&line], 50.0, DistanceFormula::Haversine);
+
+        assert_eq!(stats["minElevation"], 100.0);
+        assert!(stats["maxElevation"].as_f64().unwrap() > 121.0);
+        let gradient = stats["maxGradient"].as_f64().unwrap();
+        assert!((gradient - 10.0).abs() < 5.0, "got {gradient}");
+
+        // Without a window the spike dominates
+        let noisy = track_stats(&[&line], 0.0, DistanceFormula::Haversine)["maxGradient"]
+            .as_f64()
+            .unwrap();
+        assert!(noisy > 25.0, "got {noisy}");
+
+        // Shorter than the window
+        assert!(
+            !track_stats(&[&line[..2]], 50.0, DistanceFormula::Haversine)
+                .contains_key("maxGradient")
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use geojson::{Feature, Value};
+use serde_json::Value as JsonValue;
+
+/// Palette used by `autoColor: true` (Tableau 10).
+pub const DEFAULT_PALETTE: &[&str] = &[
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+    "#9c755f", "#bab0ac",
+];
+
+/// Give each track and route feature the next palette color, cycling when
+/// the palette runs out. Lines get `stroke`; Points (single-point tracks) get
+/// `marker-color`. Any existing color is replaced.
+pub fn apply_auto_color(features: &mut [Feature], palette: &[&str]) {
+    if palette.is_empty() {
+        return;
+    }
+    let mut colors = palette.iter().cycle();
+    for feature in features {
+        let Some(props) = feature.properties.as_mut() else {
+            continue;
+        };
+        if !matches!(
+            props.get("gpxType").and_then(JsonValue::as_str),
+            Some("track" | "route")
+        ) {
+            continue;
+        }
+        let key = match feature.geometry.as_ref().map(|g| &g.value) {
+            Some(Value::Point(_)) => "marker-color",
+            _ => "stroke",
+        };
+        if let Some(color) = colors.next() {
+            props.insert(key.to_string(), JsonValue::String(color.to_string()));
+        }
+    }
+}
+
+/// Hex color for a Garmin `<gpxx:DisplayColor>` name, or `None` for
+/// `Transparent` and unknown names.
+pub fn garmin_display_color(name: &str) -> Option<&'static str> {
+    let hex = match name.trim() {
+        "Black" => "#000000",
+        "DarkRed" => "#8b0000",
+        "DarkGreen" => "#006400",
+        "DarkYellow" => "#8b8b00",
+        "DarkBlue" => "#00008b",
+        "DarkMagenta" => "#8b008b",
+        "DarkCyan" => "#008b8b",
+        "LightGray" => "#d3d3d3",
+        "DarkGray" => "#a9a9a9",
+        "Red" => "#ff0000",
+        "Green" => "#00ff00",
+        "Yellow" => "#ffff00",
+        "Blue" => "#0000ff",
+        "Magenta" => "#ff00ff",
+        "Cyan" => "#00ffff",
+        "White" => "#ffffff",
+        _ => return None,
+    };
+    Some(hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_auto_color_cycles() {
+        use geojson::Geometry;
+        use serde_json::{Map, json};
+
+        let feature = |gpx_type: &str, value: Value| {
+            let mut props = Map::new();
+            props.insert("gpxType".to_string(), json!(gpx_type));
+            Feature {
+                bbox: None,
+                geometry: Some(Geometry::new(value)),
+                id: None,
+                properties: Some(props),
+                foreign_members: None,
+            }
+        };
+        let line = || Value::LineString(vec![vec![0.0, 0.0], vec![1.0, 1.0]]);
+        let mut features = vec![
+            feature("waypoint", Value::Point(vec![0.0, 0.0])),
+            feature("track", line()),
+            feature("route", line()),
+            feature("track", Value::Point(vec![0.0, 0.0])),
+        ];
+        apply_auto_color(&mut features, &["#111111", "#222222"]);
+
+        let props = |i: usize| features[i].properties.as_ref().unwrap();
+        assert!(!props(0).contains_key("marker-color"));
+        assert_eq!(props(1)["stroke"], "#111111");
+        assert_eq!(props(2)["stroke"], "#222222");
+        assert_eq!(props(3)["marker-color"], "#111111");
+    }
+
+    #[test]
+    fn test_garmin_display_color() {
+        assert_eq!(garmin_display_color("DarkRed"), Some("#8b0000"));
+        assert_eq!(garmin_display_color(" Cyan "), Some("#00ffff"));
+        assert_eq!(garmin_display_color("Transparent"), None);
+        assert_eq!(garmin_display_color("Plaid"), None);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use geojson::{Feature, Value};
+use serde_json::{Map, Value as JsonValue, json};
+
+use crate::datetime;
+use crate::geo;
+use crate::gpx_types::GpxPoint;
+use crate::options::DistanceFormula;
+
+/// Aggregate the converted features into a `summary` foreign member.
+///
+/// The summary is computed from the emitted features, so it reflects type
+/// filters and cropping. Section features are excerpts of tracks and are
+/// counted, but not added to the distance or duration again.
+pub fn build_summary(features: &[Feature], formula: DistanceFormula) -> JsonValue {
+    let mut total_distance = 0.0;
+    let mut duration = 0.0;
+    let mut has_duration = false;
+    let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut time_range: Option<((f64, String), (f64, String))> = None;
+
+    for feature in features {
+        let props = feature.properties.as_ref();
+        let gpx_type = props
+            .and_then(|p| p.get("gpxType"))
+            .and_then(JsonValue::as_str)
+            .unwrap_or("unknown");
+        *counts.entry(gpx_type).or_default() += 1;
+
+        let lines = feature
+            .geometry
+            .as_ref()
+            .map_or_else(Vec::new, |g| lines_of(&g.value));
+
+        let times = props.map_or_else(Vec::new, feature_times);
+        for (secs, raw) in &times {
+            match &mut time_range {
+                None => time_range = Some(((*secs, raw.clone()), (*secs, raw.clone()))),
+                Some((first, last)) => {
+                    if *secs < first.0 {
+                        *first = (*secs, raw.clone());
+                    }
+                    if *secs > last.0 {
+                        *last = (*secs, raw.clone());
+                    }
+                }
+            }
+        }
+
+        if gpx_type == "section" {
+            continue;
+        }
+        total_distance += lines
+            .iter()
+            .map(|line| line_length(line, formula))
+            .sum::<f64>();
+        if matches!(gpx_type, "track" | "route") && times.len() >= 2 {
+            let min = times.iter().map(|t| t.0).fold(f64::INFINITY, f64::min);
+            let max = times.iter().map(|t| t.0).fold(f64::NEG_INFINITY, f64::max);
+            duration += max - min;
+            has_duration = true;
+        }
+    }
+
+    let mut summary = Map::new();
+    summary.insert("totalDistance".to_string(), json!(total_distance));
+    summary.insert(
+        "duration".to_string(),
+        if has_duration {
+            json!(duration)
+        } else {
+            JsonValue::Null
+        },
+    );
+    summary.insert("featureCounts".to_string(), json!(counts));
+    summary.insert(
+        "bbox".to_string(),
+        features_bbox(features).map_or(JsonValue::Null, |b| json!(b)),
+    );
+    summary.insert(
+        "timeRange".to_string(),
+        time_range.map_or(JsonValue::Null, |(first, last)| json!([first.1, last.1])),
+    );
+    JsonValue::Object(summary)
+}
+
+/// The coordinate sequences of a geometry. A Point, and each point of a
+/// MultiPoint, is a one-point line, so it adds bounds but no distance.
+fn lines_of(value: &Value) -> Vec<Vec<Vec<f64>>> {
+    match value {
+        Value::Point(c) => vec![vec![c.clone()]],
+        Value::MultiPoint(cs) => cs.iter().map(|c| vec![c.clone()]).collect(),
+        Value::LineString(cs) => vec![cs.clone()],
+        Value::MultiLineString(lines) | Value::Polygon(lines) => lines.clone(),
+        Value::MultiPolygon(polys) => polys.iter().flatten().cloned().collect(),
+        Value::GeometryCollection(geoms) => geoms.iter().flat_map(|g| lines_of(&g.value)).collect(),
+    }
+}
+
+fn line_length(line: &[Vec<f64>], formula: DistanceFormula) -> f64 {
+    line.windows(2)
+        .map(|w| {
+            let a = GpxPoint::new(w[0][1], w[0][0]);
+            let b = GpxPoint::new(w[1][1], w[1][0]);
+            geo::distance(&a, &b, formula)
+        })
+        .sum()
+}
+
+/// `[minLon, minLat, maxLon, maxLat]` over all feature geometries, or `None` if there are none.
+pub fn features_bbox(features: &[Feature]) -> Option<[f64; 4]> {
+    let mut bbox = None;
+    for feature in features {
+        if let Some(geometry) = &feature.geometry {
+            for coord in lines_of(&geometry.value).iter().flatten() {
+                extend_bbox(&mut bbox, coord);
+            }
+        }
+    }
+    bbox
+}
+
+fn extend_bbox(bbox: &mut Option<[f64; 4]>, coord: &[f64]) {
+    let (lon, lat) = (coord[0], coord[1]);
+    match bbox {
+        None => *bbox = Some([lon, lat, lon, lat]),
+        Some(b) => {
+            b[0] = b[0].min(lon);
+            b[1] = b[1].min(lat);
+            b[2] = b[2].max(lon);
+            b[3] = b[3].max(lat);
+        }
+    }
+}
+
+/// Parsed timestamps of a feature: its `time` property and `coordinateProperties.times`.
+fn feature_times(props: &Map<String, JsonValue>) -> Vec<(f64, String)> {
+    let mut raw: Vec<&str> = Vec::new();
+    if let Some(time) = props.get("time").and_then(JsonValue::as_str) {
+        raw.push(time);
+    }
+    if let Some(times) = props
+        .get("coordinateProperties")
+        .and_then(|c| c.get("times"))
+        .and_then(JsonValue::as_array)
+    {
+        for t in times {
+            match t {
+                JsonValue::String(s) => raw.push(s),
+                JsonValue::Array(nested) => raw.extend(nested.iter().filter_map(JsonValue::as_str)),
+                _ => {}
+            }
+        }
+    }
+    raw.into_iter()
+        .filter_map(|s| datetime::parse_timestamp(s).map(|secs| (secs, s.to_string())))
+        .collect()
+}
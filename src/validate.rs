@@ -0,0 +1,272 @@
+use geojson::{FeatureCollection, Position, Value};
+use serde::Serialize;
+
+use crate::error::Gpx2GeoJsonError;
+use crate::gpx_types::GpxData;
+use crate::options::{ConvertOptions, ValidationMode};
+use crate::parser;
+
+/// Result of `validateGpx`: whether the file is well-formed GPX 1.1, and
+/// what a conversion would find in it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    /// No errors: the file parses and passes the `strict` checks.
+    pub valid: bool,
+    /// The `version` attribute of the <gpx> root.
+    pub version: Option<String>,
+    /// An XML error or GPX 1.1 violation. Parsing stops at the first one,
+    /// so at most one is reported.
+    pub errors: Vec<String>,
+    /// Problems a conversion works around, e.g. points it skips.
+    pub warnings: Vec<String>,
+    pub counts: ElementCounts,
+}
+
+/// Number of elements read, as a lenient conversion would keep them.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElementCounts {
+    pub waypoints: usize,
+    pub routes: usize,
+    pub route_points: usize,
+    pub tracks: usize,
+    pub track_segments: usize,
+    pub track_points: usize,
+}
+
+/// Check a GPX string without converting it.
+///
+/// The file is parsed twice: leniently, as `gpxToGeoJson` would, for the
+/// counts and warnings, then with `strict` to find GPX 1.1 violations.
+pub fn validate_gpx(xml: &str) -> ValidationReport {
+    let lenient = ConvertOptions {
+        include_diagnostics: true,
+        ..Default::default()
+    };
+    let data = match parser::parse_gpx_with_options(xml, &lenient) {
+        Ok(data) => data,
+        Err(e) => {
+            return ValidationReport {
+                valid: false,
+                version: None,
+                errors: vec![e.to_string()],
+                warnings: Vec::new(),
+                counts: ElementCounts::default(),
+            };
+        }
+    };
+
+    let mut errors = Vec::new();
+    let strict = ConvertOptions {
+        strict: true,
+        ..Default::default()
+    };
+    if let Err(e) = parser::parse_gpx_with_options(xml, &strict) {
+        errors.push(e.to_string());
+    }
+
+    ValidationReport {
+        valid: errors.is_empty(),
+        version: data.version.clone(),
+        errors,
+        warnings: warnings(&data),
+        counts: count_elements(&data),
+    }
+}
+
+fn warnings(data: &GpxData) -> Vec<String> {
+    let mut warnings = Vec::new();
+    match data.version.as_deref() {
+        None => warnings.push("No version attribute on <gpx>".to_string()),
+        Some("1.0" | "1.1") => {}
+        Some(version) => warnings.push(format!("Unknown GPX version '{version}'")),
+    }
+    let skipped = data.dropped.skipped_points;
+    if skipped > 0 {
+        warnings.push(format!(
+            "{skipped} point(s) skipped for missing or invalid lat/lon"
+        ));
+    }
+    if data.waypoints.is_empty() && data.routes.is_empty() && data.tracks.is_empty() {
+        warnings.push("No waypoints, routes or tracks".to_string());
+    }
+    warnings
+}
+
+fn count_elements(data: &GpxData) -> ElementCounts {
+    let segments = data.tracks.iter().flat_map(|trk| &trk.segments);
+    ElementCounts {
+        waypoints: data.waypoints.len(),
+        routes: data.routes.len(),
+        route_points: data.routes.iter().map(|rte| rte.points.len()).sum(),
+        tracks: data.tracks.len(),
+        track_segments: segments.clone().count(),
+        track_points: segments.map(|seg| seg.points.len()).sum(),
+    }
+}
+
+/// For `validateOutput`: check that every geometry in `fc` is valid GeoJSON.
+///
+/// In `Warn` mode problems are only logged and the output is returned as is.
+pub fn check_output(fc: &FeatureCollection, mode: ValidationMode) -> Result<(), Gpx2GeoJsonError> {
+    for (feature, f) in fc.features.iter().enumerate() {
+        let Some(message) = f.geometry.as_ref().and_then(|g| geometry_problem(&g.value)) else {
+            continue;
+        };
+        match mode {
+            ValidationMode::Error => {
+                return Err(Gpx2GeoJsonError::InvalidOutput { feature, message });
+            }
+            ValidationMode::Warn => {
+                log::warn!("Invalid geometry in output feature {feature}: {message}")
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What makes `value` invalid: a non-finite coordinate, a line with fewer
+/// than 2 positions, a polygon ring that isn't closed, or positions that
+/// don't all have the same 2 or 3 dimensions.
+fn geometry_problem(value: &Value) -> Option<String> {
+    let mut positions: Vec<&Position> = Vec::new();
+    let mut lines: Vec<&[Position]> = Vec::new();
+    let mut rings: Vec<&[Position]> = Vec::new();
+    match value {
+        Value::Point(point) => positions.push(point),
+        Value::MultiPoint(points) => positions.extend(points),
+        Value::LineString(line) => lines.push(line),
+        Value::MultiLineString(multi) => lines.extend(multi.iter().map(Vec::as_slice)),
+        Value::Polygon(polygon) => rings.extend(polygon.iter().map(Vec::as_slice)),
+        Value::MultiPolygon(polygons) => {
+            rings.extend(polygons.iter().flatten().map(Vec::as_slice));
+        }
+        Value::GeometryCollection(geometries) => {
+            return geometries.iter().find_map(|g| geometry_problem(&g.value));
+        }
+    }
+
+    if let Some(line) = lines.iter().find(|line| line.len() < 2) {
+        return Some(format!("LineString with {} position(s)", line.len()));
+    }
+    if let Some(ring) = rings
+        .iter()
+        .find(|ring| ring.len() < 4 || ring.first() != ring.last())
+    {
+        return Some(format!(
+            "Polygon ring of {} position(s) is not closed",
+            ring.len()
+        ));
+    }
+    positions.extend(lines.into_iter().chain(rings).flatten());
+
+    if let Some(position) = positions.iter().find(|p| p.iter().any(|c| !c.is_finite())) {
+        return Some(format!("Non-finite coordinate in {position:?}"));
+    }
+    let dimensions = positions.first()?.len();
+    if !(2..=3).contains(&dimensions) {
+        return Some(format!("Position with {dimensions} coordinate(s)"));
+    }
+    if positions.iter().any(|p| p.len() != dimensions) {
+        return Some("Positions with and without elevation mixed".to_string());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_gpx() {
+        let xml = r#"<?xml version="1.0"?>
+<gpx version="1.1">
+  <wpt lat="35.0" lon="139.0"/>
+  <rte><rtept lat="35.0" lon="139.0"/><rtept lat="35.1" lon="139.1"/></rte>
+  <trk><trkseg><trkpt lat="35.0" lon="139.0"/></trkseg><trkseg><trkpt lat="35.1" lon="139.1"/></trkseg></trk>
+</gpx>"#;
+        let report = validate_gpx(xml);
+        assert!(report.valid);
+        assert_eq!(report.version.as_deref(), Some("1.1"));
+        assert!(report.errors.is_empty() && report.warnings.is_empty());
+        let counts = &report.counts;
+        assert_eq!(
+            (counts.waypoints, counts.routes, counts.route_points),
+            (1, 1, 2)
+        );
+        assert_eq!(
+            (counts.tracks, counts.track_segments, counts.track_points),
+            (1, 2, 2)
+        );
+    }
+
+    #[test]
+    fn test_validate_invalid_gpx() {
+        // Converts, but a point is skipped
+        let report = validate_gpx(r#"<gpx><wpt lat="1" lon="2"/><wpt lat="x" lon="2"/></gpx>"#);
+        assert!(!report.valid);
+        assert_eq!(report.counts.waypoints, 1);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("'lat'"), "{:?}", report.errors);
+        assert_eq!(
+            report.warnings,
+            vec![
+                "No version attribute on <gpx>",
+                "1 point(s) skipped for missing or invalid lat/lon"
+            ]
+        );
+
+        let report = validate_gpx("<gpx><trk></gpx>");
+        assert!(!report.valid);
+        assert!(report.errors[0].starts_with("XML parse error"));
+        assert_eq!(report.counts.tracks, 0);
+    }
+
+    #[test]
+    fn test_check_output() {
+        let fc = |value: Value| FeatureCollection {
+            bbox: None,
+            features: vec![geojson::Feature {
+                bbox: None,
+                geometry: Some(geojson::Geometry::new(value)),
+                id: None,
+                properties: None,
+                foreign_members: None,
+            }],
+            foreign_members: None,
+        };
+        let valid = fc(Value::LineString(vec![
+            vec![139.0, 35.0, 10.0],
+            vec![139.1, 35.1, 12.0],
+        ]));
+        assert!(check_output(&valid, ValidationMode::Error).is_ok());
+
+        let short = fc(Value::LineString(vec![vec![139.0, 35.0]]));
+        let err = check_output(&short, ValidationMode::Error).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid geometry in output feature 0: LineString with 1 position(s)"
+        );
+        assert!(check_output(&short, ValidationMode::Warn).is_ok());
+
+        let nan = fc(Value::Point(vec![f64::NAN, 35.0]));
+        assert!(
+            geometry_problem(&nan.features[0].geometry.as_ref().unwrap().value)
+                .unwrap()
+                .starts_with("Non-finite")
+        );
+        let mixed = Value::MultiPoint(vec![vec![139.0, 35.0], vec![139.0, 35.0, 5.0]]);
+        assert_eq!(
+            geometry_problem(&mixed).as_deref(),
+            Some("Positions with and without elevation mixed")
+        );
+        let open_ring = Value::Polygon(vec![vec![
+            vec![0.0, 0.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+            vec![0.0, 1.0],
+        ]]);
+        assert!(geometry_problem(&open_ring).is_some());
+    }
+}
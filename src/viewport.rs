@@ -0,0 +1,181 @@
+use std::f64::consts::PI;
+
+use geojson::{Feature, FeatureCollection, Geometry, Value};
+use serde::Serialize;
+
+use crate::options::ViewportOptions;
+use crate::summary;
+
+/// Tile size in pixels at zoom 0 (standard Web Mercator tiles).
+const TILE_SIZE: f64 = 256.0;
+/// Zoom used when the bbox has no extent (a single point).
+const MAX_ZOOM: f64 = 20.0;
+/// Circumference of the Web Mercator sphere at the equator, in meters.
+const EQUATOR_M: f64 = 2.0 * PI * 6_378_137.0;
+
+/// Web Mercator view fitting a set of features into an image.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Viewport {
+    /// `[minLon, minLat, maxLon, maxLat]`, as in the `summary` foreign member.
+    pub bbox: [f64; 4],
+    /// `[lon, lat]` of the image center.
+    pub center: [f64; 2],
+    /// Fractional zoom level (tile size 256).
+    pub zoom: f64,
+    /// Ground resolution at the center latitude.
+    pub meters_per_pixel: f64,
+    pub width: u32,
+    pub height: u32,
+    /// The features with coordinates in image pixels (`[x, y]`, origin top-left).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pixels: Option<FeatureCollection>,
+}
+
+/// Compute the view that fits `features` into `opts.width` × `opts.height`
+/// pixels, leaving `opts.padding` pixels on each side. Returns `None` when
+/// there is nothing to fit.
+pub fn fit_viewport(features: &[Feature], opts: &ViewportOptions) -> Option<Viewport> {
+    let bbox = summary::features_bbox(features)?;
+    let (x0, y1) = project(bbox[0], bbox[1]);
+    let (x1, y0) = project(bbox[2], bbox[3]);
+
+    let inner_w = (f64::from(opts.width) - 2.0 * f64::from(opts.padding)).max(1.0);
+    let inner_h = (f64::from(opts.height) - 2.0 * f64::from(opts.padding)).max(1.0);
+    let zoom_for = |span: f64, px: f64| {
+        if span > 0.0 {
+            (px / (span * TILE_SIZE)).log2()
+        } else {
+            MAX_ZOOM
+        }
+    };
+    let zoom = zoom_for(x1 - x0, inner_w)
+        .min(zoom_for(y1 - y0, inner_h))
+        .clamp(0.0, MAX_ZOOM);
+
+    let (cx, cy) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+    let (center_lon, center_lat) = unproject(cx, cy);
+    let world = TILE_SIZE * zoom.exp2();
+    let meters_per_pixel = EQUATOR_M * center_lat.to_radians().cos() / world;
+
+    let pixels = opts.include_pixels.then(|| {
+        let (half_w, half_h) = (f64::from(opts.width) / 2.0, f64::from(opts.height) / 2.0);
+        let to_pixel = |c: &[f64]| {
+            let (x, y) = project(c[0], c[1]);
+            vec![(x - cx) * world + half_w, (y - cy) * world + half_h]
+        };
+        FeatureCollection {
+            bbox: None,
+            features: features
+                .iter()
+                .map(|f| Feature {
+                    geometry: f
+                        .geometry
+                        .as_ref()
+                        .map(|g| Geometry::new(map_coords(&g.value, &to_pixel))),
+                    ..f.clone()
+                })
+                .collect(),
+            foreign_members: None,
+        }
+    });
+
+    Some(Viewport {
+        bbox,
+        center: [center_lon, center_lat],
+        zoom,
+        meters_per_pixel,
+        width: opts.width,
+        height: opts.height,
+        pixels,
+    })
+}
+
+/// Project lon/lat to normalized Web Mercator coordinates in `0.0..=1.0`
+/// (y grows southwards).
+fn project(lon: f64, lat: f64) -> (f64, f64) {
+    let lat = lat.clamp(-85.051_128_78, 85.051_128_78).to_radians();
+    let x = (lon + 180.0) / 360.0;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0;
+    (x, y)
+}
+
+fn unproject(x: f64, y: f64) -> (f64, f64) {
+    let lon = x * 360.0 - 180.0;
+    let lat = (PI * (1.0 - 2.0 * y)).sinh().atan().to_degrees();
+    (lon, lat)
+}
+
+fn map_coords(value: &Value, f: &impl Fn(&[f64]) -> Vec<f64>) -> Value {
+    let line = |cs: &Vec<Vec<f64>>| cs.iter().map(|c| f(c)).collect::<Vec<_>>();
+    match value {
+        Value::Point(c) => Value::Point(f(c)),
+        Value::MultiPoint(cs) => Value::MultiPoint(line(cs)),
+        Value::LineString(cs) => Value::LineString(line(cs)),
+        Value::MultiLineString(ls) => Value::MultiLineString(ls.iter().map(line).collect()),
+        Value::Polygon(ls) => Value::Polygon(ls.iter().map(line).collect()),
+        Value::MultiPolygon(ps) => {
+            Value::MultiPolygon(ps.iter().map(|p| p.iter().map(line).collect()).collect())
+        }
+        Value::GeometryCollection(gs) => Value::GeometryCollection(
+            gs.iter()
+                .map(|g| Geometry::new(map_coords(&g.value, f)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_feature(coords: Vec<Vec<f64>>) -> Feature {
+        Feature {
+            bbox: None,
+            geometry: Some(Geometry::new(Value::LineString(coords))),
+            id: None,
+            properties: None,
+            foreign_members: None,
+        }
+    }
+
+    #[test]
+    fn test_fit_whole_world_width() {
+        // 360° of longitude in 256 px → zoom 0
+        let features = [line_feature(vec![vec![-180.0, 0.0], vec![180.0, 0.0]])];
+        let opts = ViewportOptions {
+            width: 256,
+            height: 256,
+            ..Default::default()
+        };
+        let vp = fit_viewport(&features, &opts).unwrap();
+        assert!(vp.zoom.abs() < 1e-9, "got {}", vp.zoom);
+        assert!(vp.center[0].abs() < 1e-9);
+        assert!((vp.meters_per_pixel - 156_543.03).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_pixels_fill_the_image() {
+        let features = [line_feature(vec![vec![139.0, 35.0], vec![139.1, 35.05]])];
+        let opts = ViewportOptions {
+            width: 400,
+            height: 300,
+            padding: 10,
+            include_pixels: true,
+        };
+        let vp = fit_viewport(&features, &opts).unwrap();
+        let pixels = vp.pixels.unwrap();
+        match &pixels.features[0].geometry.as_ref().unwrap().value {
+            Value::LineString(cs) => {
+                // Width is the limiting side: the line spans exactly the padded width
+                assert!((cs[0][0] - 10.0).abs() < 1e-6, "got {:?}", cs[0]);
+                assert!((cs[1][0] - 390.0).abs() < 1e-6, "got {:?}", cs[1]);
+                // North is up
+                assert!(cs[1][1] < cs[0][1]);
+            }
+            _ => panic!("Expected LineString"),
+        }
+
+        assert!(fit_viewport(&[], &opts).is_none());
+    }
+}
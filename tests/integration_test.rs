@@ -242,11 +242,13 @@ fn test_12_no_namespace() {
 #[test]
 fn test_13_gpx10() {
     let fc = convert(&load_fixture("edge_cases/13_gpx10.gpx"));
-    // Should parse GPX 1.0 elements, skipping speed/course/url
+    // Should parse GPX 1.0 elements, including speed/course/url
     assert_eq!(fc.features.len(), 2); // 1 waypoint + 1 track
 
     let wpt_props = fc.features[0].properties.as_ref().unwrap();
     assert_eq!(wpt_props["name"], "Legacy Point");
+    assert_eq!(wpt_props["link"]["href"], "https://example.com");
+    assert_eq!(wpt_props["link"]["text"], "Example");
 
     let trk = &fc.features[1];
     let geom = trk.geometry.as_ref().unwrap();
@@ -255,6 +257,40 @@ fn test_13_gpx10() {
     } else {
         panic!("Expected LineString");
     }
+
+    let coord_props = trk.properties.as_ref().unwrap()["coordinateProperties"]
+        .as_object()
+        .unwrap();
+    assert_eq!(coord_props["speeds"], serde_json::json!([5.5, 6.0]));
+    assert_eq!(coord_props["courses"], serde_json::json!([180.0, 185.0]));
+}
+
+#[test]
+fn test_15_concatenated() {
+    let fc = convert(&load_fixture("edge_cases/15_concatenated.gpx"));
+    // Features from both <gpx> documents are merged
+    assert_eq!(fc.features.len(), 2);
+
+    let names: Vec<&str> = fc
+        .features
+        .iter()
+        .map(|f| {
+            f.properties.as_ref().unwrap()["name"]
+                .as_str()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(names, vec!["First Document", "Second Document"]);
+}
+
+#[test]
+fn test_16_wrapped() {
+    let fc = convert(&load_fixture("edge_cases/16_wrapped.gpx"));
+    // <gpx> nested inside an unrelated envelope is still found
+    assert_eq!(fc.features.len(), 1);
+
+    let props = fc.features[0].properties.as_ref().unwrap();
+    assert_eq!(props["name"], "Wrapped Track");
 }
 
 // ---- vendor/ ----
@@ -270,7 +306,6 @@ fn test_14_garmin_extensions() {
     assert_eq!(props["name"], "Garmin Activity");
     assert_eq!(props["type"], "running");
 
-    // Extensions are skipped but parsing succeeds
     let geom = f.geometry.as_ref().unwrap();
     if let Value::LineString(coords) = &geom.value {
         assert_eq!(coords.len(), 3);
@@ -282,4 +317,15 @@ fn test_14_garmin_extensions() {
     let coord_props = props["coordinateProperties"].as_object().unwrap();
     let times = coord_props["times"].as_array().unwrap();
     assert_eq!(times.len(), 3);
+
+    // TrackPointExtension values are aligned with times
+    assert_eq!(
+        coord_props["heartRates"],
+        serde_json::json!([145, 152, 158])
+    );
+    assert_eq!(coord_props["cadences"], serde_json::json!([82, 85, 88]));
+    assert_eq!(
+        coord_props["temperatures"],
+        serde_json::json!([18.0, 18.5, 19.0])
+    );
 }
@@ -172,6 +172,22 @@ fn snapshot_13_gpx10() {
     );
 }
 
+#[test]
+fn snapshot_15_concatenated() {
+    assert_snapshot_default(
+        "edge_cases/15_concatenated.gpx",
+        "edge_cases/15_concatenated.geojson",
+    );
+}
+
+#[test]
+fn snapshot_16_wrapped() {
+    assert_snapshot_default(
+        "edge_cases/16_wrapped.gpx",
+        "edge_cases/16_wrapped.geojson",
+    );
+}
+
 // ---- vendor/ ----
 
 #[test]